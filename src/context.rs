@@ -4,9 +4,24 @@ use std::sync::Arc;
 use mongodb::{Client, Collection};
 use mongodb::bson::Document;
 use crate::data::access::{
+    access_token_repo::MongoAccessTokenRepo,
+    audit_checkpoint_repo::MongoAuditCheckpointRepo,
+    audit_repo::MongoAuditRepo,
     auth_repo::MongoAuthRepo,
+    login_attempt_repo::MongoLoginAttemptRepo,
+    login_challenge_repo::MongoLoginChallengeRepo,
+    oauth_client_repo::MongoOAuthClientRepo,
+    oauth_code_repo::MongoOAuthCodeRepo,
+    oauth_consent_repo::MongoOAuthConsentRepo,
+    oauth_refresh_token_repo::MongoOAuthRefreshTokenRepo,
     perms_repo::MongoPermRepo,
+    service_account_repo::MongoServiceAccountRepo,
+    service_account_token_repo::MongoServiceAccountTokenRepo,
+    session_repo::MongoSessionRepo,
+    signing_key_repo::MongoSigningKeyRepo,
+    two_factor_policy_repo::MongoTwoFactorPolicyRepo,
     user_repo::MongoUserRepo,
+    webauthn_challenge_repo::MongoWebauthnChallengeRepo,
 };
 
 #[derive(Clone)]
@@ -17,6 +32,21 @@ pub struct Context
     pub user_repo:  Arc<MongoUserRepo>,
     pub auth_repo:  Arc<MongoAuthRepo>,
     pub perm_repo: Arc<MongoPermRepo>,
+    pub login_attempt_repo: Arc<MongoLoginAttemptRepo>,
+    pub login_challenge_repo: Arc<MongoLoginChallengeRepo>,
+    pub two_factor_policy_repo: Arc<MongoTwoFactorPolicyRepo>,
+    pub webauthn_challenge_repo: Arc<MongoWebauthnChallengeRepo>,
+    pub audit_repo: Arc<MongoAuditRepo>,
+    pub audit_checkpoint_repo: Arc<MongoAuditCheckpointRepo>,
+    pub session_repo: Arc<MongoSessionRepo>,
+    pub access_token_repo: Arc<MongoAccessTokenRepo>,
+    pub service_account_repo: Arc<MongoServiceAccountRepo>,
+    pub service_account_token_repo: Arc<MongoServiceAccountTokenRepo>,
+    pub oauth_client_repo: Arc<MongoOAuthClientRepo>,
+    pub oauth_code_repo: Arc<MongoOAuthCodeRepo>,
+    pub oauth_consent_repo: Arc<MongoOAuthConsentRepo>,
+    pub oauth_refresh_token_repo: Arc<MongoOAuthRefreshTokenRepo>,
+    pub signing_key_repo: Arc<MongoSigningKeyRepo>,
     
 }
 
@@ -31,11 +61,41 @@ impl Context
         let user_collection = arc_client.database(&db_name).collection("users");
         let auth_collection = arc_client.database(&db_name).collection("auth");
         let perm_collection = arc_client.database(&db_name).collection("perm");
+        let login_attempt_collection = arc_client.database(&db_name).collection("login_attempts");
+        let login_challenge_collection = arc_client.database(&db_name).collection("login_challenges");
+        let two_factor_policy_collection = arc_client.database(&db_name).collection("two_factor_policy");
+        let webauthn_challenge_collection = arc_client.database(&db_name).collection("webauthn_challenges");
+        let audit_collection = arc_client.database(&db_name).collection("audit_log");
+        let audit_checkpoint_collection = arc_client.database(&db_name).collection("audit_checkpoints");
+        let session_collection = arc_client.database(&db_name).collection("sessions");
+        let access_token_collection = arc_client.database(&db_name).collection("access_tokens");
+        let service_account_collection = arc_client.database(&db_name).collection("service_accounts");
+        let service_account_token_collection = arc_client.database(&db_name).collection("service_account_tokens");
+        let oauth_client_collection = arc_client.database(&db_name).collection("oauth_clients");
+        let oauth_code_collection = arc_client.database(&db_name).collection("oauth_codes");
+        let oauth_consent_collection = arc_client.database(&db_name).collection("oauth_consents");
+        let oauth_refresh_token_collection = arc_client.database(&db_name).collection("oauth_refresh_tokens");
+        let signing_key_collection = arc_client.database(&db_name).collection("signing_keys");
         
         Self { client:     arc_client.clone(),
                   user_repo:  Arc::new(MongoUserRepo::new(user_collection)),
                   auth_repo:  Arc::new(MongoAuthRepo::new(auth_collection)),
                   perm_repo: Arc::new(MongoPermRepo::new(perm_collection)),
+                  login_attempt_repo: Arc::new(MongoLoginAttemptRepo::new(login_attempt_collection)),
+                  login_challenge_repo: Arc::new(MongoLoginChallengeRepo::new(login_challenge_collection)),
+                  two_factor_policy_repo: Arc::new(MongoTwoFactorPolicyRepo::new(two_factor_policy_collection)),
+                  webauthn_challenge_repo: Arc::new(MongoWebauthnChallengeRepo::new(webauthn_challenge_collection)),
+                  audit_repo: Arc::new(MongoAuditRepo::new(audit_collection)),
+                  audit_checkpoint_repo: Arc::new(MongoAuditCheckpointRepo::new(audit_checkpoint_collection)),
+                  session_repo: Arc::new(MongoSessionRepo::new(session_collection)),
+                  access_token_repo: Arc::new(MongoAccessTokenRepo::new(access_token_collection)),
+                  service_account_repo: Arc::new(MongoServiceAccountRepo::new(service_account_collection)),
+                  service_account_token_repo: Arc::new(MongoServiceAccountTokenRepo::new(service_account_token_collection)),
+                  oauth_client_repo: Arc::new(MongoOAuthClientRepo::new(oauth_client_collection)),
+                  oauth_code_repo: Arc::new(MongoOAuthCodeRepo::new(oauth_code_collection)),
+                  oauth_consent_repo: Arc::new(MongoOAuthConsentRepo::new(oauth_consent_collection)),
+                  oauth_refresh_token_repo: Arc::new(MongoOAuthRefreshTokenRepo::new(oauth_refresh_token_collection)),
+                  signing_key_repo: Arc::new(MongoSigningKeyRepo::new(signing_key_collection)),
         }
     }
 
@@ -54,6 +114,81 @@ impl Context
         Arc::clone(&self.perm_repo)
     }
 
+    pub fn get_login_attempt_repo(&self) -> Arc<MongoLoginAttemptRepo>
+    {
+        Arc::clone(&self.login_attempt_repo)
+    }
+
+    pub fn get_login_challenge_repo(&self) -> Arc<MongoLoginChallengeRepo>
+    {
+        Arc::clone(&self.login_challenge_repo)
+    }
+
+    pub fn get_two_factor_policy_repo(&self) -> Arc<MongoTwoFactorPolicyRepo>
+    {
+        Arc::clone(&self.two_factor_policy_repo)
+    }
+
+    pub fn get_webauthn_challenge_repo(&self) -> Arc<MongoWebauthnChallengeRepo>
+    {
+        Arc::clone(&self.webauthn_challenge_repo)
+    }
+
+    pub fn get_audit_repo(&self) -> Arc<MongoAuditRepo>
+    {
+        Arc::clone(&self.audit_repo)
+    }
+
+    pub fn get_audit_checkpoint_repo(&self) -> Arc<MongoAuditCheckpointRepo>
+    {
+        Arc::clone(&self.audit_checkpoint_repo)
+    }
+
+    pub fn get_session_repo(&self) -> Arc<MongoSessionRepo>
+    {
+        Arc::clone(&self.session_repo)
+    }
+
+    pub fn get_access_token_repo(&self) -> Arc<MongoAccessTokenRepo>
+    {
+        Arc::clone(&self.access_token_repo)
+    }
+
+    pub fn get_service_account_repo(&self) -> Arc<MongoServiceAccountRepo>
+    {
+        Arc::clone(&self.service_account_repo)
+    }
+
+    pub fn get_service_account_token_repo(&self) -> Arc<MongoServiceAccountTokenRepo>
+    {
+        Arc::clone(&self.service_account_token_repo)
+    }
+
+    pub fn get_oauth_client_repo(&self) -> Arc<MongoOAuthClientRepo>
+    {
+        Arc::clone(&self.oauth_client_repo)
+    }
+
+    pub fn get_oauth_code_repo(&self) -> Arc<MongoOAuthCodeRepo>
+    {
+        Arc::clone(&self.oauth_code_repo)
+    }
+
+    pub fn get_oauth_consent_repo(&self) -> Arc<MongoOAuthConsentRepo>
+    {
+        Arc::clone(&self.oauth_consent_repo)
+    }
+
+    pub fn get_oauth_refresh_token_repo(&self) -> Arc<MongoOAuthRefreshTokenRepo>
+    {
+        Arc::clone(&self.oauth_refresh_token_repo)
+    }
+
+    pub fn get_signing_key_repo(&self) -> Arc<MongoSigningKeyRepo>
+    {
+        Arc::clone(&self.signing_key_repo)
+    }
+
     pub fn get_collection(&self, collection: &str) -> Collection<Document>
     {
         let db_name = env::var("MONGO_DATABASE").expect("Var MONGO_DATABASE no definida");
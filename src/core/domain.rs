@@ -1,3 +1,8 @@
+pub mod audit;
 pub mod auth;
+pub mod authz;
+pub mod oauth;
 pub mod perm;
+pub mod service_account;
+pub mod signing_key;
 pub mod user;
@@ -0,0 +1,86 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::audit::audit_chain::entry_hash;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditDiff, AuditOrigin};
+
+pub mod audit_chain;
+pub mod audit_error;
+pub mod audit_query;
+pub mod audit_type;
+
+/// One security relevant event. Entries are only ever inserted: nothing in the service updates
+/// or deletes them.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEntry
+{
+    pub _id:        Option<ObjectId>,
+    pub at:         DateTime,
+    pub action:     AuditAction,
+    /// Who did it: the token subject, the account logging in, or `system` for background jobs.
+    pub actor:      Option<String>,
+    /// Id of the affected record, usually a user id so entries survive erasure of the user's PII.
+    pub target:     Option<String>,
+    pub diff:       Option<AuditDiff>,
+    pub detail:     Option<String>,
+    pub ip:         Option<String>,
+    pub user_agent: Option<String>,
+    /// Position in the hash chain, from 1; set when the entry is appended.
+    #[serde(default)]
+    pub seq:        i64,
+    #[serde(default)]
+    pub prev_hash:  String,
+    #[serde(default)]
+    pub hash:       String,
+}
+
+impl AuditEntry
+{
+    pub fn new(action: AuditAction, origin: &AuditOrigin) -> Self
+    {
+        Self { _id: None,
+               at: DateTime::now(),
+               action,
+               actor: origin.actor.clone(),
+               target: None,
+               diff: None,
+               detail: None,
+               ip: origin.ip.clone(),
+               user_agent: origin.user_agent.clone(),
+               seq: 0,
+               prev_hash: String::new(),
+               hash: String::new() }
+    }
+
+    /// Places the entry right after `prev_hash` at position `seq` and seals it with its hash.
+    pub fn link(&mut self, seq: i64, prev_hash: String)
+    {
+        self.seq = seq;
+        self.prev_hash = prev_hash;
+        self.hash = entry_hash(self);
+    }
+
+    pub fn with_actor(mut self, actor: impl ToString) -> Self
+    {
+        self.actor = Some(actor.to_string());
+        self
+    }
+
+    pub fn with_target(mut self, target: impl ToString) -> Self
+    {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    pub fn with_diff(mut self, diff: AuditDiff) -> Self
+    {
+        self.diff = Some(diff);
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self
+    {
+        self.detail = Some(detail.into());
+        self
+    }
+}
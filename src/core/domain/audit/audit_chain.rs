@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+use std::env;
+
+use data_encoding::BASE64;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::audit::audit_type::{AuditAction, AuditDiff};
+use crate::core::domain::audit::AuditEntry;
+use crate::utils::secrets::sha256_hex;
+
+/// `prev_hash` of the first entry of the chain.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Fields covered by an entry's hash, serialized in this order. `_id` is left out since it is
+/// assigned by the database.
+#[derive(Serialize)]
+struct HashedEntry<'a>
+{
+    seq:        i64,
+    prev_hash:  &'a str,
+    at:         i64,
+    action:     AuditAction,
+    actor:      &'a Option<String>,
+    target:     &'a Option<String>,
+    diff:       &'a Option<AuditDiff>,
+    detail:     &'a Option<String>,
+    ip:         &'a Option<String>,
+    user_agent: &'a Option<String>,
+}
+
+/// SHA-256 over the entry's content and the hash of the entry before it.
+pub fn entry_hash(entry: &AuditEntry) -> String
+{
+    let hashed = HashedEntry { seq:        entry.seq,
+                               prev_hash:  &entry.prev_hash,
+                               at:         entry.at.timestamp_millis(),
+                               action:     entry.action,
+                               actor:      &entry.actor,
+                               target:     &entry.target,
+                               diff:       &entry.diff,
+                               detail:     &entry.detail,
+                               ip:         &entry.ip,
+                               user_agent: &entry.user_agent, };
+    sha256_hex(&serde_json::to_string(&hashed).unwrap_or_default())
+}
+
+/// A signed statement that the chain had `hash` at position `seq`. Without it anyone with write
+/// access could rewrite the whole chain, or cut its tail, and recompute consistent hashes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Checkpoint
+{
+    pub _id:       Option<ObjectId>,
+    pub seq:       i64,
+    pub hash:      String,
+    pub at:        DateTime,
+    pub key_id:    String,
+    /// Base64 Ed25519 signature over `message()`.
+    pub signature: String,
+}
+
+impl Checkpoint
+{
+    pub fn message(&self) -> String
+    {
+        format!("audit-checkpoint:{}:{}:{}", self.seq, self.hash, self.at.timestamp_millis())
+    }
+}
+
+/// Ed25519 key that signs checkpoints, read from `AUDIT_SIGNING_KEY` (base64 of the 32 byte seed).
+/// The seed is a secret: it comes from the environment of the process writing checkpoints and is
+/// never needed to check them, see `CheckpointVerifier`.
+pub struct CheckpointSigner
+{
+    key: SigningKey,
+}
+
+impl CheckpointSigner
+{
+    pub fn new(seed: [u8; 32]) -> Self
+    {
+        Self { key: SigningKey::from_bytes(&seed) }
+    }
+
+    /// `None` when the key is missing or malformed; checkpoints are then not written.
+    pub fn from_env() -> Option<Self>
+    {
+        let encoded = env::var("AUDIT_SIGNING_KEY").ok()?;
+        let seed: [u8; 32] = BASE64.decode(encoded.trim().as_bytes()).ok()?.try_into().ok()?;
+        Some(Self::new(seed))
+    }
+
+    /// Short fingerprint of the public key, stored with each checkpoint.
+    pub fn key_id(&self) -> String
+    {
+        key_id(&self.key.verifying_key())
+    }
+
+    /// Base64 public key, the value `AUDIT_VERIFYING_KEY` has to be set to.
+    pub fn public_key(&self) -> String
+    {
+        BASE64.encode(self.key.verifying_key().as_bytes())
+    }
+
+    pub fn verifier(&self) -> CheckpointVerifier
+    {
+        CheckpointVerifier { key: self.key.verifying_key() }
+    }
+
+    pub fn sign(&self, seq: i64, hash: String, at: DateTime) -> Checkpoint
+    {
+        let mut checkpoint = Checkpoint { _id: None, seq, hash, at, key_id: self.key_id(), signature: String::new() };
+        let signature = self.key.sign(checkpoint.message().as_bytes());
+        checkpoint.signature = BASE64.encode(&signature.to_bytes());
+        checkpoint
+    }
+}
+
+/// Public half of the checkpoint key, read from `AUDIT_VERIFYING_KEY` (base64 of the 32 byte
+/// key). Holding it is enough to check checkpoints but not to forge them.
+pub struct CheckpointVerifier
+{
+    key: VerifyingKey,
+}
+
+impl CheckpointVerifier
+{
+    /// `None` when `encoded` isn't the base64 of a valid Ed25519 public key.
+    pub fn from_base64(encoded: &str) -> Option<Self>
+    {
+        let bytes: [u8; 32] = BASE64.decode(encoded.trim().as_bytes()).ok()?.try_into().ok()?;
+        Some(Self { key: VerifyingKey::from_bytes(&bytes).ok()? })
+    }
+
+    pub fn from_env() -> Option<Self>
+    {
+        Self::from_base64(&env::var("AUDIT_VERIFYING_KEY").ok()?)
+    }
+
+    pub fn verify(&self, checkpoint: &Checkpoint) -> bool
+    {
+        let key = self.key;
+        if checkpoint.key_id != key_id(&key)
+        {
+            return false;
+        }
+        let Some(signature) = BASE64.decode(checkpoint.signature.as_bytes())
+                                    .ok()
+                                    .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        else
+        {
+            return false;
+        };
+        key.verify_strict(checkpoint.message().as_bytes(), &signature).is_ok()
+    }
+}
+
+fn key_id(key: &VerifyingKey) -> String
+{
+    sha256_hex(&BASE64.encode(key.as_bytes()))[..16].to_string()
+}
+
+/// Where the chain stops being trustworthy.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BrokenLink
+{
+    pub seq:      i64,
+    pub entry_id: Option<String>,
+    pub reason:   String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ChainReport
+{
+    pub verified_entries:     u64,
+    pub head_seq:             i64,
+    pub head_hash:            String,
+    pub checkpoints_verified: u64,
+    /// `None` when the whole chain checks out.
+    pub broken:               Option<BrokenLink>,
+}
+
+/// Walks the chain in `seq` order, checking numbering, linkage, each entry's own hash and the
+/// checkpoints (already signature-checked) that fall on it.
+pub struct ChainVerifier
+{
+    checkpoints:          VecDeque<Checkpoint>,
+    next_seq:             i64,
+    prev_hash:            String,
+    verified_entries:     u64,
+    checkpoints_verified: u64,
+}
+
+impl ChainVerifier
+{
+    pub fn new(mut checkpoints: Vec<Checkpoint>) -> Self
+    {
+        checkpoints.sort_by_key(|checkpoint| checkpoint.seq);
+        Self { checkpoints:          checkpoints.into(),
+               next_seq:             1,
+               prev_hash:            GENESIS_HASH.to_string(),
+               verified_entries:     0,
+               checkpoints_verified: 0, }
+    }
+
+    pub fn push(&mut self, entry: &AuditEntry) -> Result<(), BrokenLink>
+    {
+        let broken = |reason: String| BrokenLink { seq: entry.seq, entry_id: entry._id.map(|id| id.to_hex()), reason };
+
+        if entry.seq != self.next_seq
+        {
+            return Err(broken(format!("expected seq {}, found {}", self.next_seq, entry.seq)));
+        }
+        if entry.prev_hash != self.prev_hash
+        {
+            return Err(broken("prev_hash does not match the previous entry".to_string()));
+        }
+        if entry.hash != entry_hash(entry)
+        {
+            return Err(broken("content does not match its hash".to_string()));
+        }
+        if let Some(checkpoint) = self.checkpoints.front().filter(|checkpoint| checkpoint.seq == entry.seq)
+        {
+            if checkpoint.hash != entry.hash
+            {
+                return Err(broken("hash differs from the signed checkpoint".to_string()));
+            }
+            self.checkpoints.pop_front();
+            self.checkpoints_verified += 1;
+        }
+
+        self.next_seq += 1;
+        self.prev_hash = entry.hash.clone();
+        self.verified_entries += 1;
+        Ok(())
+    }
+
+    /// Checkpoints past the last entry mean entries were cut off the end of the chain.
+    pub fn finish(self, broken: Option<BrokenLink>) -> ChainReport
+    {
+        let broken = broken.or_else(|| {
+            self.checkpoints.front().map(|checkpoint| BrokenLink {
+                seq:      self.next_seq,
+                entry_id: None,
+                reason:   format!("entries missing: checkpoint covers seq {}", checkpoint.seq),
+            })
+        });
+        ChainReport { verified_entries: self.verified_entries,
+                      head_seq: self.next_seq - 1,
+                      head_hash: self.prev_hash,
+                      checkpoints_verified: self.checkpoints_verified,
+                      broken }
+    }
+}
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AuditError {
+    #[error("Not has permission")]
+    NotHasPermission,
+
+    #[error("Invalid audit query: {0}")]
+    InvalidQuery(String),
+
+    #[error("Audit entry could not be encoded")]
+    EncodingError,
+
+    #[error("Audit entry could not be read")]
+    DecodingError,
+
+    #[error("Audit chain is busy, entry not appended")]
+    ChainContention,
+
+    #[error("Audit verifying key is not configured")]
+    VerifyingKeyMissing,
+
+    #[error("Mongo error: {0}")]
+    MongoError(#[from] mongodb::error::Error),
+}
@@ -0,0 +1,119 @@
+use mongodb::bson::{doc, oid::ObjectId, to_bson, DateTime, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::audit::audit_error::AuditError;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditDiff};
+use crate::core::domain::audit::AuditEntry;
+
+pub const DEFAULT_AUDIT_PAGE_SIZE: u32 = 50;
+pub const MAX_AUDIT_PAGE_SIZE: u32 = 500;
+
+/// Query string of `GET /api/audit`. Entries come newest first; `cursor` is the `id` of the last
+/// entry of the previous page.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery
+{
+    pub actor:  Option<String>,
+    pub action: Option<AuditAction>,
+    pub target: Option<String>,
+    /// Unix millis, inclusive.
+    pub from:   Option<i64>,
+    /// Unix millis, exclusive.
+    pub to:     Option<i64>,
+    pub cursor: Option<String>,
+    pub limit:  Option<u32>,
+}
+
+impl AuditQuery
+{
+    pub fn page_size(&self) -> u32
+    {
+        self.limit.unwrap_or(DEFAULT_AUDIT_PAGE_SIZE).clamp(1, MAX_AUDIT_PAGE_SIZE)
+    }
+
+    pub fn filter(&self) -> Result<Document, AuditError>
+    {
+        let mut filter = Document::new();
+        if let Some(actor) = &self.actor
+        {
+            filter.insert("actor", actor);
+        }
+        if let Some(action) = &self.action
+        {
+            filter.insert("action", to_bson(action).map_err(|_| AuditError::EncodingError)?);
+        }
+        if let Some(target) = &self.target
+        {
+            filter.insert("target", target);
+        }
+
+        let mut at = Document::new();
+        if let Some(from) = self.from
+        {
+            at.insert("$gte", DateTime::from_millis(from));
+        }
+        if let Some(to) = self.to
+        {
+            at.insert("$lt", DateTime::from_millis(to));
+        }
+        if !at.is_empty()
+        {
+            filter.insert("at", at);
+        }
+
+        if let Some(cursor) = &self.cursor
+        {
+            let id = ObjectId::parse_str(cursor).map_err(|_| AuditError::InvalidQuery("invalid cursor".to_string()))?;
+            filter.insert("_id", doc! { "$lt": id });
+        }
+        Ok(filter)
+    }
+}
+
+/// Entries where `subject`, usually a user id, is the actor or the target.
+pub fn involving(subject: &str) -> Document
+{
+    doc! { "$or": [{ "actor": subject }, { "target": subject }] }
+}
+
+/// An entry as returned by the API, with the id as hex and the timestamp in unix millis.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord
+{
+    pub id:         String,
+    pub at:         i64,
+    pub action:     AuditAction,
+    pub actor:      Option<String>,
+    pub target:     Option<String>,
+    pub diff:       Option<AuditDiff>,
+    pub detail:     Option<String>,
+    pub ip:         Option<String>,
+    pub user_agent: Option<String>,
+    pub seq:        i64,
+    pub hash:       String,
+}
+
+impl From<AuditEntry> for AuditRecord
+{
+    fn from(entry: AuditEntry) -> Self
+    {
+        Self { id:         entry._id.map(|id| id.to_hex()).unwrap_or_default(),
+               at:         entry.at.timestamp_millis(),
+               action:     entry.action,
+               actor:      entry.actor,
+               target:     entry.target,
+               diff:       entry.diff,
+               detail:     entry.detail,
+               ip:         entry.ip,
+               user_agent: entry.user_agent,
+               seq:        entry.seq,
+               hash:       entry.hash, }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditPage
+{
+    pub items:       Vec<AuditRecord>,
+    pub next_cursor: Option<String>,
+}
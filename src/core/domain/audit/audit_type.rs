@@ -0,0 +1,130 @@
+use std::fmt;
+
+use actix_web::{http::header, HttpRequest};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::core::domain::auth::auth_caller::caller_subject;
+use crate::utils::client_ip::client_ip;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuditAction
+{
+    LoginSucceeded,
+    LoginFailed,
+    LockoutCleared,
+    TwoFactorEnabled,
+    TwoFactorDisabled,
+    TwoFactorPolicyChanged,
+    SessionRevoked,
+    AccessTokenCreated,
+    AccessTokenRevoked,
+    UserCreated,
+    UsersImported,
+    UserStatusChanged,
+    UserDeleted,
+    UserRestored,
+    UserPurged,
+    UserDataExported,
+    UserErased,
+    PermissionsChanged,
+    CatalogImported,
+    ServiceAccountCreated,
+    ServiceAccountSecretRotated,
+    ServiceAccountSecretRevoked,
+    ServiceAccountDisabled,
+    OAuthClientRegistered,
+    OAuthClientDisabled,
+    OAuthConsentGranted,
+    OAuthTokenRevoked,
+}
+
+impl fmt::Display for AuditAction
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Where a request came from. The actor is the token subject, so it is only filled in for
+/// requests that went through `TokenOps::has_permission`; login events set it to the account
+/// instead.
+#[derive(Debug, Clone, Default)]
+pub struct AuditOrigin
+{
+    pub actor:      Option<String>,
+    pub ip:         Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl AuditOrigin
+{
+    pub fn from_request(req: &HttpRequest) -> Self
+    {
+        Self { actor:      caller_subject(req),
+               ip:         client_ip(req),
+               user_agent: req.headers()
+                              .get(header::USER_AGENT)
+                              .and_then(|value| value.to_str().ok())
+                              .map(|value| value.to_string()), }
+    }
+
+    /// Background jobs such as the retention purge.
+    pub fn system() -> Self
+    {
+        Self { actor: Some("system".to_string()), ..Self::default() }
+    }
+}
+
+/// Fields that changed, with their values before and after. Only top level fields are compared;
+/// callers pass views of the records without secrets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditDiff
+{
+    pub before: Option<Value>,
+    pub after:  Option<Value>,
+}
+
+impl AuditDiff
+{
+    pub fn created<T: Serialize>(after: &T) -> Self
+    {
+        Self { before: None, after: serde_json::to_value(after).ok() }
+    }
+
+    pub fn removed<T: Serialize>(before: &T) -> Self
+    {
+        Self { before: serde_json::to_value(before).ok(), after: None }
+    }
+
+    pub fn between<T: Serialize>(before: &T, after: &T) -> Self
+    {
+        let before = serde_json::to_value(before).unwrap_or(Value::Null);
+        let after = serde_json::to_value(after).unwrap_or(Value::Null);
+        match (before, after)
+        {
+            (Value::Object(before), Value::Object(after)) => {
+                let mut old = Map::new();
+                let mut new = Map::new();
+                for key in before.keys().chain(after.keys().filter(|key| !before.contains_key(*key)))
+                {
+                    let (was, is) = (before.get(key), after.get(key));
+                    if was != is
+                    {
+                        old.insert(key.clone(), was.cloned().unwrap_or(Value::Null));
+                        new.insert(key.clone(), is.cloned().unwrap_or(Value::Null));
+                    }
+                }
+                Self { before: Some(Value::Object(old)), after: Some(Value::Object(new)) }
+            },
+            (before, after) => Self { before: Some(before), after: Some(after) },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        let empty = |value: &Option<Value>| value.as_ref().is_none_or(|value| value.as_object().is_some_and(Map::is_empty));
+        empty(&self.before) && empty(&self.after)
+    }
+}
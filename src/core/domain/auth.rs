@@ -1,13 +1,26 @@
+use mongodb::bson::DateTime;
 use serde::{Deserialize, Serialize};
 use crate::core::domain::auth::auth_error::AuthError;
-use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::auth::auth_totp::TwoFactor;
+use crate::core::domain::auth::auth_type::{Claims, Role};
+use crate::core::domain::auth::auth_webauthn::PasskeyCredential;
+use crate::core::domain::authz::authz_resource::ResourceGrant;
+use crate::core::domain::user::user_status::{AccountStatus, Suspension};
 use crate::data::access::auth_repo::MongoAuthRepo;
 use crate::utils::domains_ids::{AuthID, UserID};
 
+pub mod auth_access_token;
+pub mod auth_introspection;
 pub mod auth_repo;
 
 pub mod auth_type;
 pub mod auth_error;
+pub mod auth_caller;
+pub mod auth_lockout;
+pub mod auth_password;
+pub mod auth_session;
+pub mod auth_totp;
+pub mod auth_webauthn;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Auth
@@ -19,6 +32,40 @@ pub struct Auth
     pub password:    String,
     pub roles:       Role,
     pub permissions: Vec<u32>,
+    #[serde(default)]
+    pub two_factor:  Option<TwoFactor>,
+    #[serde(default)]
+    pub passkeys:    Vec<PasskeyCredential>,
+    #[serde(default)]
+    pub status:      AccountStatus,
+    #[serde(default)]
+    pub suspension:  Option<Suspension>,
+    /// Permissions held on some resources only, on top of `permissions`.
+    #[serde(default)]
+    pub resource_grants: Vec<ResourceGrant>,
+}
+
+impl Auth
+{
+    /// Refuses suspended and deactivated accounts. A suspension whose end date has passed no
+    /// longer blocks the login even before an admin lifts it.
+    pub fn ensure_active(&self, now: DateTime) -> Result<(), AuthError>
+    {
+        match self.status
+        {
+            AccountStatus::Active => Ok(()),
+            AccountStatus::Suspended if self.suspension.as_ref().is_some_and(|suspension| suspension.is_over(now)) => Ok(()),
+            AccountStatus::Suspended => Err(AuthError::AccountSuspended),
+            AccountStatus::Deactivated => Err(AuthError::AccountDeactivated),
+        }
+    }
+
+    /// Claims for a login token. Only the id, role and permissions go in; the token is readable
+    /// by anyone holding it.
+    pub fn token_claims(&self) -> Claims
+    {
+        Claims::new(self.user_id.value().to_hex(), self.roles.clone(), self.permissions.clone())
+    }
 }
 
 #[derive(Clone)]
@@ -41,6 +88,11 @@ impl<'a>AuthEntity<'a>
             password: new_auth.password,
             roles: new_auth.roles,
             permissions: new_auth.permissions,
+            two_factor: new_auth.two_factor,
+            passkeys: new_auth.passkeys,
+            status: new_auth.status,
+            suspension: new_auth.suspension,
+            resource_grants: new_auth.resource_grants,
         }}
     }
     
@@ -63,24 +115,40 @@ impl<'a>AuthEntity<'a>
     {
         self.props.permissions = permissions;
     }
-    pub async fn save(self) -> Result<Auth, auth_error::AuthError>
+    pub async fn update_identity(&mut self, username: String, email: String)
     {
-        println!("{:?}", self.props);
-        self.repo.save(self.props).await
+        self.props.username = username;
+        self.props.email = email;
     }
-}
 
-impl From<Auth> for perms::token::Auth {
-    fn from(service_auth: Auth) -> Self {
-        perms::token::Auth {
-            _id: Option::from(service_auth._id.expect("REASON").to_string()),
-            user_id: service_auth.user_id.to_string(),
-            username: service_auth.username,
-            email: service_auth.email,
-            password: service_auth.password,
-            roles: service_auth.roles.to_string(),
-            permissions: service_auth.permissions,
-        }
+    pub async fn update_password(&mut self, password: String)
+    {
+        self.props.password = password;
+    }
+
+    pub async fn update_resource_grants(&mut self, resource_grants: Vec<ResourceGrant>)
+    {
+        self.props.resource_grants = resource_grants;
+    }
+
+    pub async fn update_two_factor(&mut self, two_factor: Option<TwoFactor>)
+    {
+        self.props.two_factor = two_factor;
+    }
+
+    pub async fn update_passkeys(&mut self, passkeys: Vec<PasskeyCredential>)
+    {
+        self.props.passkeys = passkeys;
     }
-}
 
+    pub async fn update_status(&mut self, status: AccountStatus, suspension: Option<Suspension>)
+    {
+        self.props.status = status;
+        self.props.suspension = suspension;
+    }
+
+    pub async fn save(self) -> Result<Auth, auth_error::AuthError>
+    {
+        self.repo.save(self.props).await
+    }
+}
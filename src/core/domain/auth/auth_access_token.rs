@@ -0,0 +1,144 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_session::touch_cutoff;
+use crate::core::domain::auth::auth_type::AccountProof;
+use crate::utils::domains_ids::{AuthID, UserID};
+use crate::utils::secrets::{random_token, sha256_hex};
+
+/// Marks a personal access token in the `Authorization` header, as opposed to a login JWT.
+pub const ACCESS_TOKEN_PREFIX: &str = "pat_";
+
+/// A named, long-lived credential for scripts and services acting as a user. Only the hash of the
+/// token is stored; the token itself is returned once, when it is created.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccessToken
+{
+    pub _id:          Option<ObjectId>,
+    pub auth_id:      AuthID,
+    pub user_id:      UserID,
+    pub name:         String,
+    pub token_hash:   String,
+    /// Permissions the token may use; the owner's current permissions still cap them.
+    pub scopes:       Vec<u32>,
+    pub created_at:   DateTime,
+    pub expires_at:   Option<DateTime>,
+    pub last_used_at: Option<DateTime>,
+    pub revoked_at:   Option<DateTime>,
+}
+
+impl AccessToken
+{
+    /// Builds the record and the token it stands for.
+    pub fn issue(auth_id: AuthID, user_id: UserID, name: String, scopes: Vec<u32>, expires_at: Option<DateTime>) -> (Self, String)
+    {
+        let token = format!("{}{}", ACCESS_TOKEN_PREFIX, random_token(32));
+        let access_token = Self { _id: None,
+                                  auth_id,
+                                  user_id,
+                                  name,
+                                  token_hash: sha256_hex(&token),
+                                  scopes,
+                                  created_at: DateTime::now(),
+                                  expires_at,
+                                  last_used_at: None,
+                                  revoked_at: None };
+        (access_token, token)
+    }
+
+    pub fn ensure_usable(&self, now: DateTime) -> Result<(), AuthError>
+    {
+        if self.revoked_at.is_some()
+        {
+            return Err(AuthError::AccessTokenRevoked);
+        }
+        if self.expires_at.is_some_and(|expires_at| expires_at <= now)
+        {
+            return Err(AuthError::AccessTokenExpired);
+        }
+        Ok(())
+    }
+
+    /// Whether `last_used_at` is missing or lags more than `interval_secs` behind `now`, like
+    /// `Session::needs_touch`.
+    pub fn needs_touch(&self, now: DateTime, interval_secs: i64) -> bool
+    {
+        self.last_used_at.is_none_or(|last_used_at| last_used_at < touch_cutoff(now, interval_secs))
+    }
+
+    /// Scopes the owner still holds; a role change narrows existing tokens too.
+    pub fn effective_permissions(&self, owner_permissions: &[u32]) -> Vec<u32>
+    {
+        self.scopes.iter().copied().filter(|scope| owner_permissions.contains(scope)).collect()
+    }
+}
+
+/// Expiry of a new token from the requested Unix seconds. It has to be in the future and fit a
+/// BSON date.
+pub fn expiry_from_secs(secs: i64, now: DateTime) -> Result<DateTime, AuthError>
+{
+    let millis = secs.checked_mul(1000)
+                     .ok_or_else(|| AuthError::InvalidAccessToken("expiry is out of range".to_string()))?;
+    if millis <= now.timestamp_millis()
+    {
+        return Err(AuthError::InvalidAccessToken("expiry is in the past".to_string()));
+    }
+    Ok(DateTime::from_millis(millis))
+}
+
+/// Body of `POST /api/auth/tokens`. Minting a token needs the full login proof: the password,
+/// and the TOTP or recovery code when 2FA is enabled.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateAccessToken
+{
+    #[serde(flatten)]
+    pub proof:      AccountProof,
+    pub name:       String,
+    pub scopes:     Vec<u32>,
+    /// Unix seconds; omitted for a token that doesn't expire.
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RevokeAccessToken
+{
+    #[serde(flatten)]
+    pub proof:    AccountProof,
+    pub token_id: String,
+}
+
+/// Returned once on creation; `token` can't be retrieved again.
+#[derive(Serialize, Debug, Clone)]
+pub struct CreatedAccessToken
+{
+    pub token:   String,
+    #[serde(flatten)]
+    pub summary: AccessTokenSummary,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AccessTokenSummary
+{
+    pub id:           String,
+    pub name:         String,
+    pub scopes:       Vec<u32>,
+    pub created_at:   i64,
+    pub expires_at:   Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub revoked_at:   Option<i64>,
+}
+
+impl From<&AccessToken> for AccessTokenSummary
+{
+    fn from(access_token: &AccessToken) -> Self
+    {
+        Self { id:           access_token._id.map(|id| id.to_hex()).unwrap_or_default(),
+               name:         access_token.name.clone(),
+               scopes:       access_token.scopes.clone(),
+               created_at:   access_token.created_at.timestamp_millis(),
+               expires_at:   access_token.expires_at.map(|expires_at| expires_at.timestamp_millis()),
+               last_used_at: access_token.last_used_at.map(|last_used_at| last_used_at.timestamp_millis()),
+               revoked_at:   access_token.revoked_at.map(|revoked_at| revoked_at.timestamp_millis()), }
+    }
+}
@@ -0,0 +1,44 @@
+use actix_web::{http::header, HttpRequest};
+use data_encoding::BASE64URL_NOPAD;
+use serde::{de::DeserializeOwned, Deserialize};
+
+#[derive(Deserialize)]
+struct SubjectClaim
+{
+    sub: String,
+}
+
+#[derive(Deserialize)]
+struct ExpiryClaim
+{
+    exp: i64,
+}
+
+/// Token from the `Authorization` header, with or without the `Bearer ` prefix.
+pub fn bearer_token(req: &HttpRequest) -> Option<String>
+{
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?.trim();
+    let token = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+/// `sub` claim of the request's token. The payload is read without checking the signature, so
+/// this is only meaningful once `TokenOps::has_permission` has accepted the same request.
+pub fn caller_subject(req: &HttpRequest) -> Option<String>
+{
+    let token = bearer_token(req)?;
+    token_claims::<SubjectClaim>(&token).map(|claim| claim.sub)
+}
+
+/// `exp` claim (unix seconds) of a token this service issued.
+pub fn token_expiry(token: &str) -> Option<i64>
+{
+    token_claims::<ExpiryClaim>(token).map(|claim| claim.exp)
+}
+
+fn token_claims<T: DeserializeOwned>(token: &str) -> Option<T>
+{
+    let payload = token.split('.').nth(1)?;
+    let claims = BASE64URL_NOPAD.decode(payload.trim_end_matches('=').as_bytes()).ok()?;
+    serde_json::from_slice(&claims).ok()
+}
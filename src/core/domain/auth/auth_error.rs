@@ -31,6 +31,9 @@ pub enum AuthError {
     #[error("Incorrect Format Email")]
     IncorrectFormatEmail,
 
+    #[error("Invalid username")]
+    InvalidUsername,
+
     #[error("Email is used")]
     EmailIsUsed,
 
@@ -51,6 +54,68 @@ pub enum AuthError {
     
 
     #[error("Permission library error")]
-    PermLibError
-}
+    PermLibError,
+
+    #[error("Account is temporarily locked")]
+    AccountLocked,
+
+    #[error("Too many login attempts, try again later")]
+    TooManyAttempts,
+
+    #[error("Invalid two-factor code")]
+    InvalidTwoFactorCode,
+
+    #[error("Two-factor authentication isn't enrolled")]
+    TwoFactorNotEnrolled,
+
+    #[error("Two-factor authentication is already enabled")]
+    TwoFactorAlreadyEnabled,
+
+    #[error("Two-factor code required")]
+    TwoFactorRequired,
+
+    #[error("Two-factor authentication is required for this role")]
+    TwoFactorEnrollmentRequired,
+
+    #[error("Login challenge expired or not found")]
+    ChallengeNotFound,
+
+    #[error("Passkey verification failed: {0}")]
+    PasskeyVerification(&'static str),
+
+    #[error("Passkey not found")]
+    PasskeyNotFound,
 
+    #[error("Passkey already registered")]
+    PasskeyAlreadyRegistered,
+
+    #[error("Hash password error")]
+    HashPasswordError,
+
+    #[error("Unknown password hash format")]
+    UnknownPasswordHash,
+
+    #[error("Account is suspended")]
+    AccountSuspended,
+
+    #[error("Account is deactivated")]
+    AccountDeactivated,
+
+    #[error("Session not found")]
+    SessionNotFound,
+
+    #[error("Session has been revoked")]
+    SessionRevoked,
+
+    #[error("Access token not found")]
+    AccessTokenNotFound,
+
+    #[error("Access token has been revoked")]
+    AccessTokenRevoked,
+
+    #[error("Access token has expired")]
+    AccessTokenExpired,
+
+    #[error("Invalid access token: {0}")]
+    InvalidAccessToken(String),
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// Form body of `POST /api/auth/introspect` (RFC 7662 §2.1). The caller authenticates like at
+/// the token endpoint.
+#[derive(Deserialize, Debug, Clone)]
+pub struct IntrospectionRequest
+{
+    pub token:           String,
+    /// Accepted for compatibility; the token's prefix already tells its kind.
+    pub token_type_hint: Option<String>,
+    pub client_id:       Option<String>,
+    pub client_secret:   Option<String>,
+}
+
+/// What an active token stands for.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TokenDetails
+{
+    pub sub:         String,
+    pub username:    String,
+    pub role:        String,
+    /// The permissions the token carries right now: its scopes, capped by what the subject
+    /// still holds.
+    pub permissions: Vec<u32>,
+    pub scope:       String,
+    /// Unix seconds; absent for tokens that don't expire.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp:         Option<i64>,
+    /// The OAuth client the token was issued to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id:   Option<String>,
+    /// `Bearer` for tokens sent to APIs; absent for refresh tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type:  Option<&'static str>,
+}
+
+/// RFC 7662 §2.2. An inactive token says nothing else, whatever the reason.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Introspection
+{
+    pub active:  bool,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub details: Option<TokenDetails>,
+}
+
+impl Introspection
+{
+    pub fn inactive() -> Self
+    {
+        Self { active: false, details: None }
+    }
+
+    pub fn active(details: TokenDetails) -> Self
+    {
+        Self { active: true, details: Some(details) }
+    }
+
+    /// How long the caller may reuse this answer: `cache_secs`, but never past the token's
+    /// expiry. Revocations show up once the cached answer runs out.
+    pub fn max_age(&self, now_secs: i64, cache_secs: i64) -> i64
+    {
+        match self.details.as_ref().and_then(|details| details.exp)
+        {
+            Some(exp) => cache_secs.min(exp - now_secs).max(0),
+            None => cache_secs,
+        }
+    }
+}
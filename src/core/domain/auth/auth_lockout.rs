@@ -0,0 +1,105 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::env::env_or;
+
+#[derive(Debug, Clone)]
+pub struct LockoutPolicy
+{
+    pub max_attempts_per_account: u32,
+    pub max_attempts_per_ip:      u32,
+    pub backoff_base_secs:        i64,
+    pub backoff_max_secs:         i64,
+    pub lockout_secs:             i64,
+    /// Failures older than this are forgotten: the next one starts a new count.
+    pub failure_window_secs:      i64,
+}
+
+impl LockoutPolicy
+{
+    pub fn from_env() -> Self
+    {
+        Self {
+            max_attempts_per_account: env_or("LOGIN_MAX_ATTEMPTS", 5),
+            max_attempts_per_ip:      env_or("LOGIN_IP_MAX_ATTEMPTS", 20),
+            backoff_base_secs:        env_or("LOGIN_BACKOFF_BASE_SECS", 1),
+            backoff_max_secs:         env_or("LOGIN_BACKOFF_MAX_SECS", 60),
+            lockout_secs:             env_or("LOGIN_LOCKOUT_SECS", 900),
+            failure_window_secs:      env_or("LOGIN_FAILURE_WINDOW_SECS", 900),
+        }
+    }
+
+    pub fn threshold(&self, key: &LoginAttemptKey) -> u32
+    {
+        match key
+        {
+            LoginAttemptKey::Account(_) => self.max_attempts_per_account,
+            LoginAttemptKey::Ip(_) => self.max_attempts_per_ip,
+        }
+    }
+
+    /// Counts that started before this instant have expired.
+    pub fn window_start(&self, now: DateTime) -> DateTime
+    {
+        DateTime::from_millis(now.timestamp_millis() - self.failure_window_secs * 1000)
+    }
+
+    /// Delay before the next attempt is accepted: base * 2^(failures - 1), capped at `backoff_max_secs`.
+    pub fn backoff_secs(&self, failures: u32) -> i64
+    {
+        if failures == 0
+        {
+            return 0;
+        }
+        let exponent = (failures - 1).min(30);
+        self.backoff_base_secs
+            .saturating_mul(1_i64 << exponent)
+            .min(self.backoff_max_secs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginAttemptKey
+{
+    Account(String),
+    Ip(String),
+}
+
+impl LoginAttemptKey
+{
+    pub fn as_key(&self) -> String
+    {
+        match self
+        {
+            LoginAttemptKey::Account(username) => format!("account:{}", username),
+            LoginAttemptKey::Ip(ip) => format!("ip:{}", ip),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoginAttempt
+{
+    pub _id:               Option<ObjectId>,
+    pub key:               String,
+    pub failures:          u32,
+    /// First failure of the current count.
+    #[serde(default)]
+    pub window_started_at: Option<DateTime>,
+    pub last_failure_at:   DateTime,
+    pub next_attempt_at:   Option<DateTime>,
+    pub locked_until:      Option<DateTime>,
+}
+
+impl LoginAttempt
+{
+    pub fn is_locked(&self, now: DateTime) -> bool
+    {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+
+    pub fn is_throttled(&self, now: DateTime) -> bool
+    {
+        self.next_attempt_at.is_some_and(|next| next > now)
+    }
+}
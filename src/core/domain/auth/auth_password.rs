@@ -0,0 +1,395 @@
+use std::env;
+use std::sync::Arc;
+
+use actix_web::web;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString},
+    Algorithm,
+    Argon2,
+    Params,
+    Version,
+};
+
+use data_encoding::{BASE64, HEXLOWER};
+use md5::{Digest, Md5};
+use sha2::Sha256;
+
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::utils::env::env_or;
+use crate::utils::secrets::{constant_time_eq, random_token};
+
+/// One password hashing scheme. Hashes are self-describing strings (PHC or modular crypt), so
+/// the scheme that produced a stored hash can always be recognised from the hash alone.
+pub trait PasswordHasher: Send + Sync
+{
+    fn algorithm(&self) -> &'static str;
+
+    /// Whether `stored` was produced by this scheme.
+    fn recognizes(&self, stored: &str) -> bool;
+
+    fn hash(&self, password: &str) -> Result<String, AuthError>;
+
+    fn verify(&self, password: &str, stored: &str) -> Result<bool, AuthError>;
+
+    /// Whether `stored` was produced with weaker parameters than the current ones.
+    fn needs_rehash(&self, stored: &str) -> bool;
+
+    /// Brings a hash exported by another system into the self-describing form `recognizes`
+    /// accepts. Most formats already carry their own tag and salt.
+    fn tag(&self, hash: &str, _salt: Option<&str>) -> String
+    {
+        hash.to_string()
+    }
+}
+
+pub struct Argon2idHasher
+{
+    params: Params,
+}
+
+impl Argon2idHasher
+{
+    pub fn new(memory_kib: u32, time_cost: u32, parallelism: u32) -> Result<Self, AuthError>
+    {
+        let params = Params::new(memory_kib, time_cost, parallelism, None).map_err(|_| AuthError::HashPasswordError)?;
+        Ok(Self { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static>
+    {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl PasswordHasher for Argon2idHasher
+{
+    fn algorithm(&self) -> &'static str
+    {
+        "argon2id"
+    }
+
+    fn recognizes(&self, stored: &str) -> bool
+    {
+        stored.starts_with("$argon2id$")
+    }
+
+    fn hash(&self, password: &str) -> Result<String, AuthError>
+    {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = self.argon2()
+                       .hash_password(password.as_bytes(), &salt)
+                       .map_err(|_| AuthError::HashPasswordError)?;
+        Ok(hash.to_string())
+    }
+
+    fn verify(&self, password: &str, stored: &str) -> Result<bool, AuthError>
+    {
+        let parsed = PasswordHash::new(stored).map_err(|_| AuthError::HashPasswordError)?;
+        Ok(self.argon2().verify_password(password.as_bytes(), &parsed).is_ok())
+    }
+
+    fn needs_rehash(&self, stored: &str) -> bool
+    {
+        let Ok(parsed) = PasswordHash::new(stored) else { return true };
+        let Ok(params) = Params::try_from(&parsed) else { return true };
+        params.m_cost() < self.params.m_cost()
+        || params.t_cost() < self.params.t_cost()
+        || params.p_cost() < self.params.p_cost()
+    }
+}
+
+pub struct BcryptHasher
+{
+    cost: u32,
+}
+
+impl BcryptHasher
+{
+    pub fn new(cost: u32) -> Self
+    {
+        Self { cost }
+    }
+}
+
+impl PasswordHasher for BcryptHasher
+{
+    fn algorithm(&self) -> &'static str
+    {
+        "bcrypt"
+    }
+
+    fn recognizes(&self, stored: &str) -> bool
+    {
+        ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|prefix| stored.starts_with(prefix))
+    }
+
+    fn hash(&self, password: &str) -> Result<String, AuthError>
+    {
+        bcrypt::hash(password, self.cost).map_err(|_| AuthError::HashPasswordError)
+    }
+
+    fn verify(&self, password: &str, stored: &str) -> Result<bool, AuthError>
+    {
+        bcrypt::verify(password, stored).map_err(|_| AuthError::HashPasswordError)
+    }
+
+    fn needs_rehash(&self, stored: &str) -> bool
+    {
+        stored.get(4..6)
+              .and_then(|cost| cost.parse::<u32>().ok())
+              .is_none_or(|cost| cost < self.cost)
+    }
+}
+
+/// Django's `pbkdf2_sha256$<iterations>$<salt>$<base64 hash>`.
+pub struct Pbkdf2Sha256Hasher
+{
+    iterations: u32,
+}
+
+impl Pbkdf2Sha256Hasher
+{
+    pub fn new(iterations: u32) -> Self
+    {
+        Self { iterations }
+    }
+
+    fn parse(stored: &str) -> Option<(u32, &str, Vec<u8>)>
+    {
+        let mut parts = stored.split('$');
+        if parts.next()? != "pbkdf2_sha256"
+        {
+            return None;
+        }
+        let iterations = parts.next()?.parse().ok().filter(|iterations| *iterations > 0)?;
+        let salt = parts.next()?;
+        let hash = BASE64.decode(parts.next()?.as_bytes()).ok().filter(|hash| !hash.is_empty())?;
+        parts.next().is_none().then_some((iterations, salt, hash))
+    }
+
+    fn derive(password: &str, salt: &str, iterations: u32, len: usize) -> Vec<u8>
+    {
+        let mut derived = vec![0u8; len];
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut derived);
+        derived
+    }
+}
+
+impl PasswordHasher for Pbkdf2Sha256Hasher
+{
+    fn algorithm(&self) -> &'static str
+    {
+        "pbkdf2_sha256"
+    }
+
+    fn recognizes(&self, stored: &str) -> bool
+    {
+        Self::parse(stored).is_some()
+    }
+
+    fn hash(&self, password: &str) -> Result<String, AuthError>
+    {
+        let salt = random_token(16);
+        let derived = Self::derive(password, &salt, self.iterations, 32);
+        Ok(format!("pbkdf2_sha256${}${}${}", self.iterations, salt, BASE64.encode(&derived)))
+    }
+
+    fn verify(&self, password: &str, stored: &str) -> Result<bool, AuthError>
+    {
+        let (iterations, salt, expected) = Self::parse(stored).ok_or(AuthError::UnknownPasswordHash)?;
+        Ok(constant_time_eq(&Self::derive(password, salt, iterations, expected.len()), &expected))
+    }
+
+    fn needs_rehash(&self, stored: &str) -> bool
+    {
+        Self::parse(stored).is_none_or(|(iterations, _, _)| iterations < self.iterations)
+    }
+}
+
+/// glibc `$6$[rounds=N$]<salt>$<hash>`.
+pub struct Sha512CryptHasher;
+
+impl PasswordHasher for Sha512CryptHasher
+{
+    fn algorithm(&self) -> &'static str
+    {
+        "sha512_crypt"
+    }
+
+    fn recognizes(&self, stored: &str) -> bool
+    {
+        stored.starts_with("$6$") && stored.matches('$').count() >= 3
+    }
+
+    fn hash(&self, password: &str) -> Result<String, AuthError>
+    {
+        pwhash::sha512_crypt::hash(password).map_err(|_| AuthError::HashPasswordError)
+    }
+
+    fn verify(&self, password: &str, stored: &str) -> Result<bool, AuthError>
+    {
+        Ok(pwhash::sha512_crypt::verify(password, stored))
+    }
+
+    fn needs_rehash(&self, _stored: &str) -> bool
+    {
+        true
+    }
+}
+
+/// `md5$<salt>$<hex md5(salt + password)>`. Only kept to let imported accounts log in once.
+pub struct SaltedMd5Hasher;
+
+impl SaltedMd5Hasher
+{
+    fn parse(stored: &str) -> Option<(&str, &str)>
+    {
+        let mut parts = stored.split('$');
+        if parts.next()? != "md5"
+        {
+            return None;
+        }
+        let salt = parts.next()?;
+        let hash = parts.next().filter(|hash| hash.len() == 32 && hash.bytes().all(|b| b.is_ascii_hexdigit()))?;
+        parts.next().is_none().then_some((salt, hash))
+    }
+
+    fn digest(salt: &str, password: &str) -> String
+    {
+        HEXLOWER.encode(&Md5::digest(format!("{}{}", salt, password).as_bytes()))
+    }
+}
+
+impl PasswordHasher for SaltedMd5Hasher
+{
+    fn algorithm(&self) -> &'static str
+    {
+        "md5"
+    }
+
+    fn recognizes(&self, stored: &str) -> bool
+    {
+        Self::parse(stored).is_some()
+    }
+
+    fn hash(&self, password: &str) -> Result<String, AuthError>
+    {
+        let salt = random_token(12);
+        Ok(format!("md5${}${}", salt, Self::digest(&salt, password)))
+    }
+
+    fn verify(&self, password: &str, stored: &str) -> Result<bool, AuthError>
+    {
+        let (salt, expected) = Self::parse(stored).ok_or(AuthError::UnknownPasswordHash)?;
+        Ok(constant_time_eq(Self::digest(salt, password).as_bytes(), expected.to_ascii_lowercase().as_bytes()))
+    }
+
+    fn needs_rehash(&self, _stored: &str) -> bool
+    {
+        true
+    }
+
+    /// Legacy tables usually keep the salt in its own column next to a bare hex digest.
+    fn tag(&self, hash: &str, salt: Option<&str>) -> String
+    {
+        match salt
+        {
+            Some(salt) if !hash.starts_with("md5$") => format!("md5${}${}", salt, hash.to_ascii_lowercase()),
+            _ => hash.to_string(),
+        }
+    }
+}
+
+/// Hashes new passwords with the preferred scheme and verifies against any known one. Cloning
+/// is cheap, the schemes are shared.
+#[derive(Clone)]
+pub struct Passwords
+{
+    preferred: Arc<dyn PasswordHasher>,
+    legacy:    Vec<Arc<dyn PasswordHasher>>,
+}
+
+impl Passwords
+{
+    pub fn new(preferred: Box<dyn PasswordHasher>, legacy: Vec<Box<dyn PasswordHasher>>) -> Self
+    {
+        Self { preferred: Arc::from(preferred), legacy: legacy.into_iter().map(Arc::from).collect() }
+    }
+
+    /// `PASSWORD_HASH_ALGORITHM` selects `argon2id` (default) or `bcrypt`; the other one, and the
+    /// formats accepted by the legacy import, are still accepted for verification.
+    pub fn from_env() -> Self
+    {
+        let argon2id: Box<dyn PasswordHasher> = Box::new(
+            Argon2idHasher::new(env_or("ARGON2_MEMORY_KIB", 19_456), env_or("ARGON2_TIME_COST", 2), env_or("ARGON2_PARALLELISM", 1))
+                .unwrap_or_else(|_| Argon2idHasher::new(19_456, 2, 1).expect("Default Argon2 parameters are valid")),
+        );
+        let bcrypt: Box<dyn PasswordHasher> = Box::new(BcryptHasher::new(env_or("BCRYPT_COST", 10)));
+
+        let (preferred, fallback) = match env::var("PASSWORD_HASH_ALGORITHM").as_deref()
+        {
+            Ok("bcrypt") => (bcrypt, argon2id),
+            _ => (argon2id, bcrypt),
+        };
+        Self::new(preferred,
+                  vec![fallback,
+                       Box::new(Pbkdf2Sha256Hasher::new(env_or("PBKDF2_ITERATIONS", 870_000))),
+                       Box::new(Sha512CryptHasher),
+                       Box::new(SaltedMd5Hasher),])
+    }
+
+    /// Looks a scheme up by its `algorithm` name.
+    pub fn hasher(&self, algorithm: &str) -> Option<&dyn PasswordHasher>
+    {
+        std::iter::once(&self.preferred).chain(self.legacy.iter())
+                                        .find(|hasher| hasher.algorithm() == algorithm)
+                                        .map(|hasher| hasher.as_ref())
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, AuthError>
+    {
+        self.preferred.hash(password)
+    }
+
+    pub fn verify(&self, password: &str, stored: &str) -> Result<bool, AuthError>
+    {
+        match self.hasher_for(stored)
+        {
+            Some(hasher) => hasher.verify(password, stored),
+            None => Err(AuthError::UnknownPasswordHash),
+        }
+    }
+
+    /// `hash` on the blocking thread pool. Hashing is slow on purpose; done inline in a handler it
+    /// would hold up every other request served by the same worker.
+    pub async fn hash_async(&self, password: &str) -> Result<String, AuthError>
+    {
+        let passwords = self.clone();
+        let password = password.to_string();
+        web::block(move || passwords.hash(&password)).await
+                                                      .map_err(|_| AuthError::HashPasswordError)?
+    }
+
+    /// `verify` on the blocking thread pool, like `hash_async`.
+    pub async fn verify_async(&self, password: &str, stored: &str) -> Result<bool, AuthError>
+    {
+        let passwords = self.clone();
+        let (password, stored) = (password.to_string(), stored.to_string());
+        web::block(move || passwords.verify(&password, &stored)).await
+                                                                 .map_err(|_| AuthError::HashPasswordError)?
+    }
+
+    /// True when the hash isn't from the preferred scheme or uses outdated parameters.
+    pub fn needs_rehash(&self, stored: &str) -> bool
+    {
+        !self.preferred.recognizes(stored) || self.preferred.needs_rehash(stored)
+    }
+
+    fn hasher_for(&self, stored: &str) -> Option<&dyn PasswordHasher>
+    {
+        std::iter::once(&self.preferred).chain(self.legacy.iter())
+                                        .find(|hasher| hasher.recognizes(stored))
+                                        .map(|hasher| hasher.as_ref())
+    }
+}
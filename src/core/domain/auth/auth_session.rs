@@ -0,0 +1,96 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::audit::audit_type::AuditOrigin;
+use crate::utils::domains_ids::{AuthID, UserID};
+use crate::utils::env::env_or;
+use crate::utils::secrets::sha256_hex;
+
+/// A signed-in device. The session is found again through the hash of the token it was issued
+/// with, so revoking it rejects that token in `session_guard`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Session
+{
+    pub _id:          Option<ObjectId>,
+    pub auth_id:      AuthID,
+    pub user_id:      UserID,
+    pub token_hash:   String,
+    /// How the login was completed: `password`, `totp` or `passkey`.
+    pub method:       String,
+    pub ip:           Option<String>,
+    pub user_agent:   Option<String>,
+    pub created_at:   DateTime,
+    pub last_seen_at: DateTime,
+    /// The record is dropped by a TTL index at this point; it should outlive the token.
+    pub expires_at:   DateTime,
+    pub revoked_at:   Option<DateTime>,
+}
+
+impl Session
+{
+    pub fn new(auth_id: AuthID, user_id: UserID, token: &str, method: &str, origin: &AuditOrigin) -> Self
+    {
+        let now = DateTime::now();
+        let max_age_secs: i64 = env_or("SESSION_MAX_AGE_SECS", 30 * 24 * 3600);
+        Self { _id: None,
+               auth_id,
+               user_id,
+               token_hash: sha256_hex(token),
+               method: method.to_string(),
+               ip: origin.ip.clone(),
+               user_agent: origin.user_agent.clone(),
+               created_at: now,
+               last_seen_at: now,
+               expires_at: DateTime::from_millis(now.timestamp_millis() + max_age_secs * 1000),
+               revoked_at: None }
+    }
+
+    /// Whether `last_seen_at` lags more than `interval_secs` behind `now` and is worth rewriting.
+    pub fn needs_touch(&self, now: DateTime, interval_secs: i64) -> bool
+    {
+        self.last_seen_at < touch_cutoff(now, interval_secs)
+    }
+}
+
+/// Minimum lag of a session's `last_seen_at`, or an access token's `last_used_at`, before a
+/// request rewrites it, from `SESSION_TOUCH_INTERVAL_SECS`. Without it every authenticated
+/// request would also be a write.
+pub fn touch_interval_secs() -> i64
+{
+    env_or("SESSION_TOUCH_INTERVAL_SECS", 300)
+}
+
+/// Sessions last seen before this instant are stale.
+pub fn touch_cutoff(now: DateTime, interval_secs: i64) -> DateTime
+{
+    DateTime::from_millis(now.timestamp_millis() - interval_secs.saturating_mul(1000))
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionSummary
+{
+    pub id:           String,
+    pub method:       String,
+    pub ip:           Option<String>,
+    pub user_agent:   Option<String>,
+    pub created_at:   i64,
+    pub last_seen_at: i64,
+    pub revoked_at:   Option<i64>,
+    /// The session making the request.
+    pub current:      bool,
+}
+
+impl SessionSummary
+{
+    pub fn new(session: &Session, current_token_hash: Option<&str>) -> Self
+    {
+        Self { id:           session._id.map(|id| id.to_hex()).unwrap_or_default(),
+               method:       session.method.clone(),
+               ip:           session.ip.clone(),
+               user_agent:   session.user_agent.clone(),
+               created_at:   session.created_at.timestamp_millis(),
+               last_seen_at: session.last_seen_at.timestamp_millis(),
+               revoked_at:   session.revoked_at.map(|revoked_at| revoked_at.timestamp_millis()),
+               current:      current_token_hash == Some(session.token_hash.as_str()), }
+    }
+}
@@ -0,0 +1,120 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+
+use crate::utils::secrets::{random_bytes, sha256_hex};
+
+const TOTP_STEP_SECS: i64 = 30;
+const TOTP_DIGITS: u32 = 6;
+const TOTP_SKEW_STEPS: i64 = 1;
+const RECOVERY_CODES: usize = 10;
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TwoFactor
+{
+    pub secret:         String,
+    pub enabled:        bool,
+    pub last_used_step: Option<i64>,
+    pub recovery_codes: Vec<String>,
+}
+
+impl TwoFactor
+{
+    pub fn pending(secret: String) -> Self
+    {
+        Self { secret, ..Default::default() }
+    }
+
+    /// Returns the matched time step if `code` is valid and hasn't been used before.
+    pub fn verify_code(&self, code: &str, unix_secs: i64) -> Option<i64>
+    {
+        let step = verify_totp(&self.secret, code, unix_secs)?;
+        match self.last_used_step
+        {
+            Some(last) if step <= last => None,
+            _ => Some(step),
+        }
+    }
+
+    /// Consumes a recovery code, returning whether it matched one of the stored hashes.
+    pub fn consume_recovery_code(&mut self, code: &str) -> bool
+    {
+        let hashed = sha256_hex(&normalize_recovery_code(code));
+        let before = self.recovery_codes.len();
+        self.recovery_codes.retain(|stored| *stored != hashed);
+        self.recovery_codes.len() != before
+    }
+}
+
+pub fn generate_secret() -> String
+{
+    BASE32_NOPAD.encode(&random_bytes(20))
+}
+
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String
+{
+    format!("otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            uri_encode(issuer),
+            uri_encode(account),
+            secret,
+            uri_encode(issuer),
+            TOTP_DIGITS,
+            TOTP_STEP_SECS)
+}
+
+/// RFC 6238 code for the given time step.
+pub fn totp_at(secret: &str, step: i64) -> Option<String>
+{
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).ok()?;
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset] & 0x7f, digest[offset + 1], digest[offset + 2], digest[offset + 3]]);
+    let code = binary % 10_u32.pow(TOTP_DIGITS);
+    Some(format!("{:0width$}", code, width = TOTP_DIGITS as usize))
+}
+
+/// Checks `code` against the current step and its neighbours, returning the step that matched.
+pub fn verify_totp(secret: &str, code: &str, unix_secs: i64) -> Option<i64>
+{
+    let code = code.trim();
+    let current = unix_secs / TOTP_STEP_SECS;
+    (current - TOTP_SKEW_STEPS..=current + TOTP_SKEW_STEPS).find(|step| totp_at(secret, *step).as_deref() == Some(code))
+}
+
+/// Generates single-use recovery codes; returns `(plain, hashed)`.
+pub fn generate_recovery_codes() -> (Vec<String>, Vec<String>)
+{
+    let plain: Vec<String> = (0..RECOVERY_CODES).map(|_| {
+                                                    let chars: String = random_bytes(10)
+                                                        .iter()
+                                                        .map(|b| RECOVERY_ALPHABET[*b as usize % RECOVERY_ALPHABET.len()] as char)
+                                                        .collect();
+                                                    format!("{}-{}", &chars[..5], &chars[5..])
+                                                })
+                                                .collect();
+    let hashed = plain.iter()
+                      .map(|code| sha256_hex(&normalize_recovery_code(code)))
+                      .collect();
+    (plain, hashed)
+}
+
+fn normalize_recovery_code(code: &str) -> String
+{
+    code.trim().to_lowercase().replace('-', "")
+}
+
+fn uri_encode(value: &str) -> String
+{
+    value.bytes()
+         .map(|b| match b
+         {
+             b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+             _ => format!("%{:02X}", b),
+         })
+         .collect()
+}
@@ -1,21 +1,163 @@
 use std::{fmt, str::FromStr};
 
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
 
+use crate::utils::domains_ids::AuthID;
+use crate::utils::env::env_or;
+use crate::utils::secrets::random_token;
+
+const DEFAULT_ACCESS_TOKEN_TTL_SECS: i64 = 24 * 60 * 60;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AuthLogin
+{
+    /// Username or email.
+    #[serde(alias = "identifier")]
+    pub username: String,
+    pub password: String,
+}
+
+/// What self-service endpoints take to prove the caller owns the account: everything a login
+/// takes, so the TOTP or recovery code is required when the account has 2FA enabled.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountProof
+{
+    /// Username or email.
+    #[serde(alias = "identifier")]
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub code:     Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnlockRequest
+{
+    pub username: Option<String>,
+    pub ip:       Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TotpEnrollment
+{
+    pub secret:      String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TotpConfirm
 {
     pub username: String,
     pub password: String,
+    pub code:     String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecoveryCodes
+{
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwoFactorLogin
+{
+    pub challenge_token: String,
+    pub code:            String,
 }
 
-#[derive(Debug, Serialize, Deserialize)] 
-pub struct Claims {
-    sub: String,
-    exp: usize,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwoFactorPolicy
+{
+    pub role:     Role,
+    pub required: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PasskeyLoginStart
+{
+    pub username: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PasskeyRemove
+{
+    pub username:      String,
+    pub password:      String,
+    #[serde(default)]
+    pub code:          Option<String>,
+    pub credential_id: String,
+}
+
+/// Short-lived record behind the challenge token handed out by the first login step.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PendingLogin
+{
+    pub _id:        Option<ObjectId>,
+    pub token_hash: String,
+    pub auth_id:    AuthID,
+    pub expires_at: DateTime,
+    #[serde(default)]
+    pub purpose:    ChallengePurpose,
+}
+
+/// What a challenge token may be exchanged for. A 2FA challenge only proves the password, so it
+/// must never stand in for a completed login.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChallengePurpose
+{
+    /// Waiting for the second factor.
+    #[default]
+    TwoFactor,
+    /// The user logged in at the OAuth authorize step and is being asked for consent.
+    OAuthConsent,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoginChallenge
+{
+    pub challenge_token: String,
+    pub expires_in:      i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum LoginOutcome
+{
+    Token(Token),
+    TwoFactorRequired(LoginChallenge),
+}
+
+/// Claims of the access tokens this service signs. `sub` is the user id, or the client id of a
+/// service account; `jti` keeps two tokens issued in the same second apart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims
+{
+    pub sub:         String,
+    pub exp:         i64,
+    pub iat:         i64,
+    pub jti:         String,
     pub permissions: Vec<u32>,
-    role: Role,
+    pub role:        Role,
 }
+
+impl Claims
+{
+    /// Claims valid for `ACCESS_TOKEN_TTL_SECS` from now. The TTL shouldn't exceed
+    /// `SIGNING_KEY_RETIRE_AFTER_SECS`, or tokens outlive the key that checks them.
+    pub fn new(sub: String, role: Role, permissions: Vec<u32>) -> Self
+    {
+        let now = DateTime::now().timestamp_millis() / 1000;
+        let ttl_secs: i64 = env_or("ACCESS_TOKEN_TTL_SECS", DEFAULT_ACCESS_TOKEN_TTL_SECS);
+        Self { sub,
+               exp: now.saturating_add(ttl_secs),
+               iat: now,
+               jti: random_token(16),
+               permissions,
+               role }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Token
 {
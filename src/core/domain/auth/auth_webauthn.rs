@@ -0,0 +1,480 @@
+use std::{env, io::Cursor};
+
+use ciborium::value::{Integer, Value};
+use data_encoding::BASE64URL_NOPAD;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::utils::domains_ids::AuthID;
+
+pub const COSE_ALG_ES256: i64 = -7;
+pub const COSE_ALG_EDDSA: i64 = -8;
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_ATTESTED_DATA: u8 = 0x40;
+
+#[derive(Debug, Clone)]
+pub struct RelyingParty
+{
+    pub id:     String,
+    pub name:   String,
+    pub origin: String,
+}
+
+impl RelyingParty
+{
+    pub fn from_env() -> Self
+    {
+        Self {
+            id:     env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string()),
+            name:   env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "user".to_string()),
+            origin: env::var("WEBAUTHN_ORIGIN").unwrap_or_else(|_| "http://localhost:8080".to_string()),
+        }
+    }
+}
+
+/// Passkey stored inside `Auth`; `public_key` is the raw key (SEC1 point for ES256, 32 bytes for
+/// EdDSA) encoded as base64url.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PasskeyCredential
+{
+    pub credential_id: String,
+    pub name:          String,
+    pub algorithm:     i64,
+    pub public_key:    String,
+    pub sign_count:    u32,
+    pub created_at:    DateTime,
+    pub last_used_at:  Option<DateTime>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PasskeySummary
+{
+    pub credential_id: String,
+    pub name:          String,
+    pub sign_count:    u32,
+    pub created_at:    i64,
+    pub last_used_at:  Option<i64>,
+}
+
+impl From<&PasskeyCredential> for PasskeySummary
+{
+    fn from(credential: &PasskeyCredential) -> Self
+    {
+        Self {
+            credential_id: credential.credential_id.clone(),
+            name:          credential.name.clone(),
+            sign_count:    credential.sign_count,
+            created_at:    credential.created_at.timestamp_millis(),
+            last_used_at:  credential.last_used_at.map(|date| date.timestamp_millis()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Ceremony
+{
+    Registration,
+    Authentication,
+}
+
+/// Server-side state between the options and the response of a ceremony.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebauthnChallenge
+{
+    pub _id:        Option<ObjectId>,
+    pub challenge:  String,
+    pub ceremony:   Ceremony,
+    pub auth_id:    Option<AuthID>,
+    pub expires_at: DateTime,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RpEntity
+{
+    pub id:   String,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntity
+{
+    pub id:           String,
+    pub name:         String,
+    pub display_name: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PubKeyCredParam
+{
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub alg:  i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CredentialDescriptor
+{
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id:   String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection
+{
+    pub resident_key:      String,
+    pub user_verification: String,
+}
+
+/// `PublicKeyCredentialCreationOptions` in its JSON form (binary fields as base64url).
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreationOptions
+{
+    pub rp:                      RpEntity,
+    pub user:                    UserEntity,
+    pub challenge:               String,
+    pub pub_key_cred_params:     Vec<PubKeyCredParam>,
+    pub timeout:                 u64,
+    pub attestation:             String,
+    pub authenticator_selection: AuthenticatorSelection,
+    pub exclude_credentials:     Vec<CredentialDescriptor>,
+}
+
+/// `PublicKeyCredentialRequestOptions` in its JSON form.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions
+{
+    pub rp_id:             String,
+    pub challenge:         String,
+    pub timeout:           u64,
+    pub user_verification: String,
+    pub allow_credentials: Vec<CredentialDescriptor>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponse
+{
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json:   String,
+    pub attestation_object: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegistrationResponse
+{
+    pub id:       String,
+    pub name:     Option<String>,
+    pub response: AttestationResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResponse
+{
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json:   String,
+    pub authenticator_data: String,
+    pub signature:          String,
+    pub user_handle:        Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthenticationResponse
+{
+    pub id:       String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Deserialize, Debug)]
+struct ClientData
+{
+    #[serde(rename = "type")]
+    kind:      String,
+    challenge: String,
+    origin:    String,
+}
+
+struct AuthenticatorData<'a>
+{
+    rp_id_hash:    &'a [u8],
+    flags:         u8,
+    sign_count:    u32,
+    credential_id: Option<Vec<u8>>,
+    cose_key:      Option<Value>,
+}
+
+const TIMEOUT_MS: u64 = 60_000;
+
+fn credential_params() -> Vec<PubKeyCredParam>
+{
+    [COSE_ALG_ES256, COSE_ALG_EDDSA].iter()
+                                    .map(|alg| PubKeyCredParam { kind: "public-key".to_string(), alg: *alg })
+                                    .collect()
+}
+
+fn descriptors(credentials: &[PasskeyCredential]) -> Vec<CredentialDescriptor>
+{
+    credentials.iter()
+               .map(|credential| CredentialDescriptor { kind: "public-key".to_string(),
+                                                        id:   credential.credential_id.clone(), })
+               .collect()
+}
+
+pub fn creation_options(rp: &RelyingParty, user: UserEntity, challenge: &str, existing: &[PasskeyCredential])
+                        -> CreationOptions
+{
+    CreationOptions {
+        rp: RpEntity { id: rp.id.clone(), name: rp.name.clone() },
+        user,
+        challenge: challenge.to_string(),
+        pub_key_cred_params: credential_params(),
+        timeout: TIMEOUT_MS,
+        attestation: "none".to_string(),
+        // Logins don't list credentials, so they have to be discoverable.
+        authenticator_selection: AuthenticatorSelection { resident_key:      "required".to_string(),
+                                                          user_verification: "preferred".to_string(), },
+        exclude_credentials: descriptors(existing),
+    }
+}
+
+/// The allow list is always empty: the authenticator offers the discoverable credentials it holds
+/// for this relying party, and the options look the same whichever account is asked for.
+pub fn request_options(rp: &RelyingParty, challenge: &str) -> RequestOptions
+{
+    RequestOptions {
+        rp_id: rp.id.clone(),
+        challenge: challenge.to_string(),
+        timeout: TIMEOUT_MS,
+        user_verification: "preferred".to_string(),
+        allow_credentials: Vec::new(),
+    }
+}
+
+fn invalid(reason: &'static str) -> AuthError
+{
+    AuthError::PasskeyVerification(reason)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, AuthError>
+{
+    BASE64URL_NOPAD.decode(value.trim_end_matches('=').as_bytes())
+                   .map_err(|_| invalid("invalid base64url"))
+}
+
+fn check_client_data(rp: &RelyingParty, raw: &[u8], kind: &str, challenge: &str) -> Result<(), AuthError>
+{
+    let client_data: ClientData = serde_json::from_slice(raw).map_err(|_| invalid("invalid clientDataJSON"))?;
+    if client_data.kind != kind
+    {
+        return Err(invalid("unexpected ceremony type"));
+    }
+    if client_data.challenge.trim_end_matches('=') != challenge
+    {
+        return Err(invalid("challenge mismatch"));
+    }
+    if client_data.origin != rp.origin
+    {
+        return Err(invalid("origin mismatch"));
+    }
+    Ok(())
+}
+
+/// Reads the challenge the client signed, so the matching server-side state can be looked up.
+pub fn client_challenge(client_data_json: &str) -> Result<String, AuthError>
+{
+    let client_data: ClientData =
+        serde_json::from_slice(&decode(client_data_json)?).map_err(|_| invalid("invalid clientDataJSON"))?;
+    Ok(client_data.challenge.trim_end_matches('=').to_string())
+}
+
+fn parse_authenticator_data(data: &[u8]) -> Result<AuthenticatorData<'_>, AuthError>
+{
+    if data.len() < 37
+    {
+        return Err(invalid("authenticator data too short"));
+    }
+    let flags = data[32];
+    let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+
+    let (credential_id, cose_key) = if flags & FLAG_ATTESTED_DATA != 0
+    {
+        // aaguid (16) + credential id length (2) + credential id + COSE key
+        let rest = data.get(37 + 16..).ok_or(invalid("attested data too short"))?;
+        if rest.len() < 2
+        {
+            return Err(invalid("attested data too short"));
+        }
+        let id_len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+        let credential_id = rest.get(2..2 + id_len).ok_or(invalid("credential id truncated"))?.to_vec();
+        let mut reader = Cursor::new(&rest[2 + id_len..]);
+        let cose_key: Value = ciborium::from_reader(&mut reader).map_err(|_| invalid("invalid COSE key"))?;
+        (Some(credential_id), Some(cose_key))
+    }
+    else
+    {
+        (None, None)
+    };
+
+    Ok(AuthenticatorData { rp_id_hash: &data[..32], flags, sign_count, credential_id, cose_key })
+}
+
+fn check_authenticator_data(rp: &RelyingParty, auth_data: &AuthenticatorData) -> Result<(), AuthError>
+{
+    if auth_data.rp_id_hash != Sha256::digest(rp.id.as_bytes()).as_slice()
+    {
+        return Err(invalid("relying party id mismatch"));
+    }
+    if auth_data.flags & FLAG_USER_PRESENT == 0
+    {
+        return Err(invalid("user not present"));
+    }
+    Ok(())
+}
+
+fn map_get(map: &[(Value, Value)], key: i64) -> Option<&Value>
+{
+    map.iter()
+       .find(|(k, _)| k.as_integer() == Some(Integer::from(key)))
+       .map(|(_, v)| v)
+}
+
+fn map_get_text<'a>(map: &'a [(Value, Value)], key: &str) -> Option<&'a Value>
+{
+    map.iter()
+       .find(|(k, _)| k.as_text() == Some(key))
+       .map(|(_, v)| v)
+}
+
+fn value_i64(value: Option<&Value>) -> Option<i64>
+{
+    value.and_then(|v| v.as_integer()).and_then(|i| i64::try_from(i).ok())
+}
+
+/// Converts a COSE_Key into `(algorithm, raw public key)`.
+fn parse_cose_key(cose_key: &Value) -> Result<(i64, Vec<u8>), AuthError>
+{
+    let map = cose_key.as_map().ok_or(invalid("COSE key is not a map"))?;
+    let alg = value_i64(map_get(map, 3)).ok_or(invalid("COSE key without algorithm"))?;
+    let x = map_get(map, -2).and_then(|v| v.as_bytes()).ok_or(invalid("COSE key without x"))?;
+
+    match alg
+    {
+        COSE_ALG_ES256 => {
+            let y = map_get(map, -3).and_then(|v| v.as_bytes()).ok_or(invalid("COSE key without y"))?;
+            if value_i64(map_get(map, -1)) != Some(1) || x.len() != 32 || y.len() != 32
+            {
+                return Err(invalid("unsupported EC2 curve"));
+            }
+            let mut point = vec![0x04];
+            point.extend_from_slice(x);
+            point.extend_from_slice(y);
+            Ok((alg, point))
+        },
+        COSE_ALG_EDDSA => {
+            if value_i64(map_get(map, -1)) != Some(6) || x.len() != 32
+            {
+                return Err(invalid("unsupported OKP curve"));
+            }
+            Ok((alg, x.clone()))
+        },
+        _ => Err(invalid("unsupported algorithm")),
+    }
+}
+
+fn verify_signature(algorithm: i64, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), AuthError>
+{
+    match algorithm
+    {
+        COSE_ALG_ES256 => {
+            use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+            let key = VerifyingKey::from_sec1_bytes(public_key).map_err(|_| invalid("invalid public key"))?;
+            let signature = Signature::from_der(signature).map_err(|_| invalid("invalid signature"))?;
+            key.verify(message, &signature).map_err(|_| invalid("bad signature"))
+        },
+        COSE_ALG_EDDSA => {
+            use ed25519_dalek::{Signature, VerifyingKey};
+            let key_bytes: [u8; 32] = public_key.try_into().map_err(|_| invalid("invalid public key"))?;
+            let key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| invalid("invalid public key"))?;
+            let signature = Signature::from_slice(signature).map_err(|_| invalid("invalid signature"))?;
+            key.verify_strict(message, &signature).map_err(|_| invalid("bad signature"))
+        },
+        _ => Err(invalid("unsupported algorithm")),
+    }
+}
+
+/// Verifies a registration ceremony (`none` attestation) and returns the credential to store.
+pub fn verify_registration(rp: &RelyingParty, challenge: &str, response: &RegistrationResponse)
+                           -> Result<PasskeyCredential, AuthError>
+{
+    let client_data = decode(&response.response.client_data_json)?;
+    check_client_data(rp, &client_data, "webauthn.create", challenge)?;
+
+    let attestation: Value = ciborium::from_reader(decode(&response.response.attestation_object)?.as_slice())
+        .map_err(|_| invalid("invalid attestation object"))?;
+    let attestation = attestation.as_map().ok_or(invalid("invalid attestation object"))?;
+    let fmt = map_get_text(attestation, "fmt").and_then(|v| v.as_text()).ok_or(invalid("missing fmt"))?;
+    if fmt != "none"
+    {
+        return Err(invalid("unsupported attestation format"));
+    }
+    let auth_data = map_get_text(attestation, "authData").and_then(|v| v.as_bytes())
+                                                         .ok_or(invalid("missing authData"))?;
+
+    let auth_data = parse_authenticator_data(auth_data)?;
+    check_authenticator_data(rp, &auth_data)?;
+    let credential_id = auth_data.credential_id.as_ref().ok_or(invalid("missing attested credential"))?;
+    let cose_key = auth_data.cose_key.as_ref().ok_or(invalid("missing attested credential"))?;
+    let (algorithm, public_key) = parse_cose_key(cose_key)?;
+
+    let credential_id = BASE64URL_NOPAD.encode(credential_id);
+    if response.id.trim_end_matches('=') != credential_id
+    {
+        return Err(invalid("credential id mismatch"));
+    }
+
+    Ok(PasskeyCredential {
+        credential_id,
+        name: response.name.clone().unwrap_or_else(|| "Passkey".to_string()),
+        algorithm,
+        public_key: BASE64URL_NOPAD.encode(&public_key),
+        sign_count: auth_data.sign_count,
+        created_at: DateTime::now(),
+        last_used_at: None,
+    })
+}
+
+/// Verifies an assertion against a stored credential and returns the new signature counter.
+pub fn verify_authentication(rp: &RelyingParty, challenge: &str, credential: &PasskeyCredential,
+                             response: &AuthenticationResponse)
+                             -> Result<u32, AuthError>
+{
+    let client_data = decode(&response.response.client_data_json)?;
+    check_client_data(rp, &client_data, "webauthn.get", challenge)?;
+
+    let raw_auth_data = decode(&response.response.authenticator_data)?;
+    let auth_data = parse_authenticator_data(&raw_auth_data)?;
+    check_authenticator_data(rp, &auth_data)?;
+
+    let mut message = raw_auth_data.clone();
+    message.extend_from_slice(&Sha256::digest(&client_data));
+    verify_signature(credential.algorithm,
+                     &decode(&credential.public_key)?,
+                     &message,
+                     &decode(&response.response.signature)?)?;
+
+    // A counter that doesn't move forward means the authenticator may have been cloned.
+    if (auth_data.sign_count != 0 || credential.sign_count != 0) && auth_data.sign_count <= credential.sign_count
+    {
+        return Err(invalid("signature counter did not increase"));
+    }
+    Ok(auth_data.sign_count)
+}
@@ -0,0 +1,167 @@
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::auth::Auth;
+use crate::core::domain::authz::authz_policy::{AccessRequest, PolicyDecision, PolicySet};
+use crate::core::domain::authz::authz_resource::{Resource, ResourceGrant};
+use crate::core::domain::service_account::ServiceAccount;
+
+pub mod authz_error;
+pub mod authz_policy;
+pub mod authz_resource;
+pub mod authz_type;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect
+{
+    Allow,
+    Deny,
+}
+
+/// Why a decision came out the way it did.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionReason
+{
+    /// No live account or service account goes by that subject.
+    UnknownSubject,
+    /// The account is suspended or deactivated, or the service account is disabled.
+    InactiveSubject,
+    GrantedByRole,
+    /// Held by the account although its role doesn't grant it.
+    GrantedToUser,
+    /// Granted by the role but taken away from the account.
+    RevokedForUser,
+    /// Held only on some resources, this one among them.
+    ScopedGrant,
+    /// Held only on some resources, not this one.
+    OutOfScope,
+    NotGranted,
+    /// A policy allows what the permissions don't.
+    AllowedByPolicy,
+    /// A policy denies it, whatever the permissions say.
+    DeniedByPolicy,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Decision
+{
+    pub effect: Effect,
+    pub reason: DecisionReason,
+    /// The policy that decided, if one did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+}
+
+impl Decision
+{
+    pub fn allow(reason: DecisionReason) -> Self
+    {
+        Self { effect: Effect::Allow, reason, policy: None }
+    }
+
+    pub fn deny(reason: DecisionReason) -> Self
+    {
+        Self { effect: Effect::Deny, reason, policy: None }
+    }
+
+    pub fn is_allowed(&self) -> bool
+    {
+        self.effect == Effect::Allow
+    }
+}
+
+/// What decisions about one subject are made from. The permissions of the role come from the
+/// catalog; an account's own list is what counts, and where it differs from its role's the
+/// difference is a per-user override. Resource grants add permissions on some resources only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal
+{
+    pub subject:          String,
+    pub role:             Role,
+    pub role_permissions: Vec<u32>,
+    pub permissions:      Vec<u32>,
+    pub resource_grants:  Vec<ResourceGrant>,
+    pub active:           bool,
+}
+
+impl Principal
+{
+    pub fn user(auth: &Auth, role_permissions: Vec<u32>, now: DateTime) -> Self
+    {
+        Self { subject: auth.user_id.value().to_hex(),
+               role: auth.roles.clone(),
+               role_permissions,
+               permissions: auth.permissions.clone(),
+               resource_grants: auth.resource_grants.clone(),
+               active: auth.ensure_active(now).is_ok() }
+    }
+
+    /// Service accounts get exactly the permissions of their role.
+    pub fn service_account(service_account: &ServiceAccount, role_permissions: Vec<u32>) -> Self
+    {
+        Self { subject: service_account.client_id.clone(),
+               role: service_account.role.clone(),
+               permissions: role_permissions.clone(),
+               role_permissions,
+               resource_grants: Vec::new(),
+               active: service_account.ensure_enabled().is_ok() }
+    }
+
+    /// What is left to a token issued to an OAuth client: the granted scopes only.
+    pub fn limited_to(mut self, scopes: &[u32]) -> Self
+    {
+        self.role_permissions.retain(|permission| scopes.contains(permission));
+        self.permissions.retain(|permission| scopes.contains(permission));
+        self.resource_grants.retain(|grant| scopes.contains(&grant.permission));
+        self
+    }
+
+    /// The one evaluation behind both the in-process checks and the decision API. A permission
+    /// held outright covers every resource; a resource grant only the ones it matches, and none
+    /// when no resource is named.
+    pub fn decide(&self, permission: u32, resource: Option<&Resource>) -> Decision
+    {
+        if !self.active
+        {
+            return Decision::deny(DecisionReason::InactiveSubject);
+        }
+        let by_role = self.role_permissions.contains(&permission);
+        if self.permissions.contains(&permission)
+        {
+            return Decision::allow(if by_role { DecisionReason::GrantedByRole } else { DecisionReason::GrantedToUser });
+        }
+        if resource.is_some_and(|resource| self.resource_grants.iter().any(|grant| grant.covers(permission, resource)))
+        {
+            return Decision::allow(DecisionReason::ScopedGrant);
+        }
+        if self.resource_grants.iter().any(|grant| grant.permission == permission)
+        {
+            return Decision::deny(DecisionReason::OutOfScope);
+        }
+        Decision::deny(if by_role { DecisionReason::RevokedForUser } else { DecisionReason::NotGranted })
+    }
+}
+
+/// Decision for a subject that may not exist, with the policies on top of the permissions. It is
+/// deny-overrides throughout: a policy deny beats any permission, and a policy allow only fills
+/// in for permissions the subject lacks. Nothing is allowed to unknown or inactive subjects.
+pub fn decide(principal: Option<&Principal>, permission: u32, resource: Option<&Resource>, policies: &PolicySet) -> Decision
+{
+    let Some(principal) = principal else { return Decision::deny(DecisionReason::UnknownSubject) };
+    let decision = principal.decide(permission, resource);
+    if decision.reason == DecisionReason::InactiveSubject
+    {
+        return decision;
+    }
+    match policies.evaluate(&AccessRequest::new(principal, permission, resource))
+    {
+        PolicyDecision::Deny(policy) => Decision { policy: Some(policy), ..Decision::deny(DecisionReason::DeniedByPolicy) },
+        PolicyDecision::Allow(policy) if !decision.is_allowed() => {
+            Decision { policy: Some(policy), ..Decision::allow(DecisionReason::AllowedByPolicy) }
+        },
+        _ => decision,
+    }
+}
@@ -0,0 +1,24 @@
+use thiserror::Error;
+
+use crate::core::domain::oauth::oauth_error::OAuthError;
+
+#[derive(Error, Debug)]
+pub enum AuthzError {
+    #[error("Client authentication failed: {0}")]
+    Client(#[from] OAuthError),
+
+    #[error("A batch needs at least one check")]
+    EmptyBatch,
+
+    #[error("A batch holds at most {0} checks")]
+    BatchTooLarge(usize),
+
+    #[error("Permissions of the role could not be loaded")]
+    PermError,
+
+    #[error("Subject could not be loaded")]
+    SubjectError,
+
+    #[error("Policies could not be loaded")]
+    PolicyError,
+}
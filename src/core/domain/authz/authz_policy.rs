@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::core::domain::authz::authz_resource::Resource;
+use crate::core::domain::authz::{Effect, Principal};
+
+const ATTRIBUTE_ROOTS: [&str; 3] = ["subject", "action", "resource"];
+
+/// A rule from the policy catalog: when every condition holds for an action in `actions`, the
+/// policy applies and its effect counts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Policy
+{
+    pub id:          String,
+    #[serde(default)]
+    pub description: String,
+    pub effect:      Effect,
+    /// Permission ids the policy is about.
+    pub actions:     Vec<u32>,
+    #[serde(default)]
+    pub conditions:  Vec<Condition>,
+}
+
+/// A test on one attribute, named by its path: `subject.id`, `subject.role`,
+/// `subject.permissions`, `action`, `resource.type`, `resource.id` or any other
+/// `resource.<name>` the caller supplies. A missing attribute fails every test.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Condition
+{
+    Equals { attribute: String, value: Operand },
+    NotEquals { attribute: String, value: Operand },
+    In { attribute: String, values: Vec<Value> },
+    Contains { attribute: String, value: Operand },
+}
+
+/// A literal, or `{ "attribute": "<path>" }` to compare with another attribute.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Operand
+{
+    Attribute(AttributeRef),
+    Value(Value),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AttributeRef
+{
+    pub attribute: String,
+}
+
+impl Condition
+{
+    fn attributes(&self) -> Vec<&str>
+    {
+        let (attribute, value) = match self
+        {
+            Condition::Equals { attribute, value } | Condition::NotEquals { attribute, value } | Condition::Contains { attribute, value } => {
+                (attribute, Some(value))
+            },
+            Condition::In { attribute, .. } => (attribute, None),
+        };
+        match value
+        {
+            Some(Operand::Attribute(other)) => vec![attribute.as_str(), other.attribute.as_str()],
+            _ => vec![attribute.as_str()],
+        }
+    }
+
+    fn holds(&self, request: &AccessRequest) -> bool
+    {
+        match self
+        {
+            Condition::Equals { attribute, value } => both(request, attribute, value, |left, right| left == right),
+            Condition::NotEquals { attribute, value } => both(request, attribute, value, |left, right| left != right),
+            Condition::In { attribute, values } => request.attribute(attribute).is_some_and(|left| values.contains(left)),
+            Condition::Contains { attribute, value } => {
+                both(request, attribute, value, |left, right| left.as_array().is_some_and(|items| items.contains(right)))
+            },
+        }
+    }
+}
+
+fn both(request: &AccessRequest, attribute: &str, value: &Operand, test: impl Fn(&Value, &Value) -> bool) -> bool
+{
+    let right = match value
+    {
+        Operand::Attribute(other) => request.attribute(&other.attribute),
+        Operand::Value(value) => Some(value),
+    };
+    match (request.attribute(attribute), right)
+    {
+        (Some(left), Some(right)) => test(left, right),
+        _ => false,
+    }
+}
+
+impl Policy
+{
+    fn validate(&self) -> Result<(), String>
+    {
+        if self.id.trim().is_empty()
+        {
+            return Err("policy without id".to_string());
+        }
+        if self.actions.is_empty()
+        {
+            return Err(format!("policy {} has no actions", self.id));
+        }
+        let unknown = self.conditions
+                          .iter()
+                          .flat_map(Condition::attributes)
+                          .find(|path| !ATTRIBUTE_ROOTS.contains(&path.split('.').next().unwrap_or_default()));
+        match unknown
+        {
+            Some(path) => Err(format!("policy {} uses unknown attribute {}", self.id, path)),
+            None => Ok(()),
+        }
+    }
+
+    fn applies(&self, request: &AccessRequest) -> bool
+    {
+        self.actions.contains(&request.action) && self.conditions.iter().all(|condition| condition.holds(request))
+    }
+}
+
+/// The attributes a request is judged on.
+#[derive(Debug, Clone)]
+pub struct AccessRequest
+{
+    action:     u32,
+    attributes: Value,
+}
+
+impl AccessRequest
+{
+    pub fn new(principal: &Principal, action: u32, resource: Option<&Resource>) -> Self
+    {
+        let resource = resource.map(|resource| {
+            let mut attributes = resource.attributes.clone();
+            attributes.insert("type".to_string(), json!(resource.resource_type));
+            attributes.insert("id".to_string(), json!(resource.id));
+            Value::Object(attributes)
+        });
+        let attributes = json!({
+            "subject": {
+                "id": principal.subject,
+                "role": principal.role.to_string(),
+                "permissions": principal.permissions,
+            },
+            "action": action,
+            "resource": resource,
+        });
+        Self { action, attributes }
+    }
+
+    fn attribute(&self, path: &str) -> Option<&Value>
+    {
+        path.split('.')
+            .try_fold(&self.attributes, |value, key| value.get(key))
+            .filter(|value| !value.is_null())
+    }
+}
+
+/// Outcome of the policies alone, with the id of the deciding policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision
+{
+    NotApplicable,
+    Allow(String),
+    Deny(String),
+}
+
+/// The policy catalog, kept in id order so that evaluation doesn't depend on how the file or
+/// the collection happens to be ordered.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PolicySet
+{
+    policies: Vec<Policy>,
+}
+
+impl PolicySet
+{
+    /// Refuses catalogs with unnamed or repeated policies, policies without actions and
+    /// conditions on unknown attributes.
+    pub fn new(mut policies: Vec<Policy>) -> Result<Self, String>
+    {
+        let mut ids = HashSet::new();
+        for policy in &policies
+        {
+            policy.validate()?;
+            if !ids.insert(policy.id.as_str())
+            {
+                return Err(format!("policy {} is defined twice", policy.id));
+            }
+        }
+        policies.sort_by(|left, right| left.id.cmp(&right.id));
+        Ok(Self { policies })
+    }
+
+    pub fn from_json(catalog: &str) -> Result<Self, String>
+    {
+        let policies: Vec<Policy> = serde_json::from_str(catalog).map_err(|err| err.to_string())?;
+        Self::new(policies)
+    }
+
+    pub fn policies(&self) -> &[Policy]
+    {
+        &self.policies
+    }
+
+    /// Deny-overrides: any applicable deny wins, then any applicable allow. Ties go to the
+    /// first policy in id order.
+    pub fn evaluate(&self, request: &AccessRequest) -> PolicyDecision
+    {
+        let mut allow = None;
+        for policy in self.policies.iter().filter(|policy| policy.applies(request))
+        {
+            match policy.effect
+            {
+                Effect::Deny => return PolicyDecision::Deny(policy.id.clone()),
+                Effect::Allow => {
+                    allow.get_or_insert_with(|| policy.id.clone());
+                },
+            }
+        }
+        allow.map_or(PolicyDecision::NotApplicable, PolicyDecision::Allow)
+    }
+}
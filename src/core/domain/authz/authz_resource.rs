@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::utils::domains_ids::UserID;
+
+pub const USER_RESOURCE: &str = "user";
+const WILDCARD: char = '*';
+
+/// The object an action is about, such as `user` `64b7f0c2a1b2c3d4e5f60718`. Types other than
+/// `user` belong to the services asking through the decision API. Any other field is an
+/// attribute policies can test.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Resource
+{
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub id:            String,
+    #[serde(flatten)]
+    pub attributes:    Map<String, Value>,
+}
+
+impl Resource
+{
+    pub fn new(resource_type: &str, id: &str) -> Self
+    {
+        Self { resource_type: resource_type.to_string(), id: id.to_string(), attributes: Map::new() }
+    }
+
+    pub fn user(user_id: &UserID) -> Self
+    {
+        Self::new(USER_RESOURCE, &user_id.value().to_hex())
+    }
+
+    pub fn with_attribute(mut self, name: &str, value: impl Into<Value>) -> Self
+    {
+        self.attributes.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+/// A permission held only on some resources: those of `resource_type` whose id matches
+/// `resource`, an id or a pattern where `*` stands for any run of characters.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ResourceGrant
+{
+    pub permission:    u32,
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub resource:      String,
+}
+
+impl ResourceGrant
+{
+    pub fn validate(&self) -> Result<(), String>
+    {
+        if self.resource_type.trim().is_empty()
+        {
+            return Err("resource type is required".to_string());
+        }
+        if self.resource.trim().is_empty()
+        {
+            return Err("resource id or pattern is required".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn covers(&self, permission: u32, resource: &Resource) -> bool
+    {
+        self.permission == permission && self.resource_type == resource.resource_type && matches(&self.resource, &resource.id)
+    }
+}
+
+/// Glob match where `*` is the only special character.
+fn matches(pattern: &str, id: &str) -> bool
+{
+    let mut parts = pattern.split(WILDCARD);
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = id.strip_prefix(first) else { return false };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else { return rest.is_empty() };
+    for part in parts
+    {
+        match rest.find(part)
+        {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::authz::authz_resource::Resource;
+use crate::core::domain::authz::Decision;
+
+pub const MAX_BATCH_CHECKS: usize = 100;
+
+/// "May `subject` use `permission` on `resource`?" The subject is a user id or a service
+/// account client id. Without a resource only permissions held outright count.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AuthzCheck
+{
+    pub subject:    String,
+    pub permission: u32,
+    #[serde(default)]
+    pub resource:   Option<Resource>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AuthzBatch
+{
+    pub checks: Vec<AuthzCheck>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AuthzResult
+{
+    pub subject:    String,
+    pub permission: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource:   Option<Resource>,
+    #[serde(flatten)]
+    pub decision:   Decision,
+}
+
+impl AuthzResult
+{
+    pub fn new(check: AuthzCheck, decision: Decision) -> Self
+    {
+        Self { subject: check.subject, permission: check.permission, resource: check.resource, decision }
+    }
+}
+
+/// Results in the order of the checks.
+#[derive(Serialize, Debug, Clone)]
+pub struct AuthzBatchResult
+{
+    pub results: Vec<AuthzResult>,
+}
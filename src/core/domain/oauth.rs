@@ -0,0 +1,49 @@
+use crate::core::domain::oauth::oauth_error::OAuthError;
+use crate::core::domain::oauth::oauth_oidc::IdentityScopes;
+
+pub mod oauth_authorize;
+pub mod oauth_client;
+pub mod oauth_error;
+pub mod oauth_oidc;
+pub mod oauth_refresh;
+pub mod oauth_type;
+
+/// Permissions are the OAuth scopes: a space separated list of permission ids.
+pub fn parse_scope(scope: &str) -> Result<Vec<u32>, OAuthError>
+{
+    let mut scopes = scope.split_whitespace()
+                          .map(|scope| scope.parse().map_err(|_| OAuthError::InvalidScope(format!("unknown scope {}", scope))))
+                          .collect::<Result<Vec<u32>, _>>()?;
+    scopes.sort_unstable();
+    scopes.dedup();
+    Ok(scopes)
+}
+
+pub fn format_scope(scopes: &[u32]) -> String
+{
+    scopes.iter().map(u32::to_string).collect::<Vec<_>>().join(" ")
+}
+
+/// `scope` of a user's grant: the identity scopes followed by the permission ids.
+pub fn format_granted_scope(identity: &IdentityScopes, scopes: &[u32]) -> String
+{
+    identity.names()
+            .into_iter()
+            .map(str::to_string)
+            .chain(scopes.iter().map(u32::to_string))
+            .collect::<Vec<_>>()
+            .join(" ")
+}
+
+/// The granted scopes: everything the client holds when none were asked for, otherwise the
+/// requested ones, which must all be held.
+pub fn grant_scopes(requested: Option<&str>, held: &[u32]) -> Result<Vec<u32>, OAuthError>
+{
+    let Some(requested) = requested.filter(|requested| !requested.trim().is_empty()) else { return Ok(held.to_vec()) };
+    let scopes = parse_scope(requested)?;
+    match scopes.iter().find(|scope| !held.contains(scope))
+    {
+        Some(scope) => Err(OAuthError::InvalidScope(format!("scope {} is not granted to the client", scope))),
+        None => Ok(scopes),
+    }
+}
@@ -0,0 +1,225 @@
+use data_encoding::BASE64URL_NOPAD;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::domain::oauth::oauth_error::OAuthError;
+use crate::core::domain::oauth::oauth_oidc::IdentityScopes;
+use crate::utils::domains_ids::{AuthID, UserID};
+use crate::utils::secrets::{constant_time_eq, random_token, sha256_hex};
+
+pub const CODE_RESPONSE_TYPE: &str = "code";
+pub const PKCE_METHOD_S256: &str = "S256";
+
+/// Authorization codes are exchanged right after the redirect.
+const CODE_TTL_MS: i64 = 60 * 1000;
+
+/// Query of `GET /api/oauth/authorize`, echoed back by the login page in `POST /api/oauth/authorize`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthorizeRequest
+{
+    pub response_type:         String,
+    pub client_id:             String,
+    pub redirect_uri:          String,
+    pub scope:                 Option<String>,
+    pub state:                 Option<String>,
+    pub code_challenge:        Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub nonce:                 Option<String>,
+}
+
+impl AuthorizeRequest
+{
+    /// Checks what the client controls once the client and redirect URI are known to be valid.
+    /// PKCE is mandatory and only `S256` is accepted.
+    pub fn validate(&self) -> Result<(), OAuthError>
+    {
+        if self.response_type != CODE_RESPONSE_TYPE
+        {
+            return Err(OAuthError::UnsupportedResponseType(self.response_type.clone()));
+        }
+        let challenge = self.code_challenge
+                            .as_deref()
+                            .ok_or_else(|| OAuthError::InvalidRequest("code_challenge is required".to_string()))?;
+        if self.code_challenge_method.as_deref() != Some(PKCE_METHOD_S256)
+        {
+            return Err(OAuthError::InvalidRequest("code_challenge_method must be S256".to_string()));
+        }
+        if challenge.len() != 43 || BASE64URL_NOPAD.decode(challenge.as_bytes()).is_err()
+        {
+            return Err(OAuthError::InvalidRequest("malformed code_challenge".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The request as query parameters, to hand it on to the login page.
+    pub fn params(&self) -> Vec<(&'static str, Option<&str>)>
+    {
+        vec![("response_type", Some(self.response_type.as_str())),
+             ("client_id", Some(self.client_id.as_str())),
+             ("redirect_uri", Some(self.redirect_uri.as_str())),
+             ("scope", self.scope.as_deref()),
+             ("state", self.state.as_deref()),
+             ("code_challenge", self.code_challenge.as_deref()),
+             ("code_challenge_method", self.code_challenge_method.as_deref()),
+             ("nonce", self.nonce.as_deref())]
+    }
+
+    /// The client's redirect URI carrying `err`, for errors the client should see.
+    pub fn error_redirect(&self, err: &OAuthError) -> String
+    {
+        let description = err.to_string();
+        redirect_uri_with(&self.redirect_uri,
+                          &[("error", Some(err.code())), ("error_description", Some(&description)), ("state", self.state.as_deref())])
+    }
+}
+
+/// Body of `POST /api/oauth/authorize`, sent by the login page. The first call carries the
+/// user's credentials; when consent is needed, the answer comes in a second call with the
+/// `challenge_token` of the prompt instead of the credentials.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AuthorizeDecision
+{
+    #[serde(flatten)]
+    pub request:         AuthorizeRequest,
+    pub username:        Option<String>,
+    pub password:        Option<String>,
+    /// TOTP or recovery code, for accounts with 2FA.
+    pub code:            Option<String>,
+    pub challenge_token: Option<String>,
+    pub consent:         Option<bool>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ConsentPrompt
+{
+    pub client_name:     String,
+    pub scopes:          Vec<u32>,
+    pub challenge_token: String,
+    pub expires_in:      i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum AuthorizeOutcome
+{
+    /// Where the login page sends the browser: the client's redirect URI with a code or an error.
+    Redirect
+    {
+        redirect_to: String
+    },
+    ConsentRequired
+    {
+        consent_required: ConsentPrompt
+    },
+}
+
+/// What a user agreed to share with a client. Later requests within these scopes skip the prompt.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OAuthConsent
+{
+    pub _id:        Option<ObjectId>,
+    pub user_id:    UserID,
+    pub client_id:  String,
+    pub scopes:     Vec<u32>,
+    pub granted_at: DateTime,
+}
+
+impl OAuthConsent
+{
+    pub fn covers(&self, scopes: &[u32]) -> bool
+    {
+        scopes.iter().all(|scope| self.scopes.contains(scope))
+    }
+}
+
+/// A single use code bound to the client, redirect URI and PKCE challenge of its request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthorizationCode
+{
+    pub _id:            Option<ObjectId>,
+    pub code_hash:      String,
+    pub client_id:      String,
+    pub auth_id:        AuthID,
+    pub user_id:        UserID,
+    pub redirect_uri:   String,
+    pub scopes:         Vec<u32>,
+    pub code_challenge: String,
+    #[serde(default)]
+    pub identity:       IdentityScopes,
+    pub nonce:          Option<String>,
+    pub expires_at:     DateTime,
+}
+
+impl AuthorizationCode
+{
+    pub fn issue(request: &AuthorizeRequest, auth_id: AuthID, user_id: UserID, scopes: Vec<u32>) -> (Self, String)
+    {
+        let code = random_token(32);
+        let authorization_code = Self { _id: None,
+                                        code_hash: sha256_hex(&code),
+                                        client_id: request.client_id.clone(),
+                                        auth_id,
+                                        user_id,
+                                        redirect_uri: request.redirect_uri.clone(),
+                                        scopes,
+                                        code_challenge: request.code_challenge.clone().unwrap_or_default(),
+                                        identity: IdentityScopes::split(request.scope.as_deref()).0,
+                                        nonce: request.nonce.clone(),
+                                        expires_at: DateTime::from_millis(DateTime::now().timestamp_millis() + CODE_TTL_MS) };
+        (authorization_code, code)
+    }
+
+    /// The exchange must come from the same client, name the same redirect URI and prove
+    /// possession of the PKCE verifier.
+    pub fn redeem(&self, client_id: &str, redirect_uri: Option<&str>, code_verifier: Option<&str>) -> Result<(), OAuthError>
+    {
+        let invalid = |reason: &str| OAuthError::InvalidGrant(reason.to_string());
+        if self.client_id != client_id
+        {
+            return Err(invalid("code was issued to another client"));
+        }
+        if redirect_uri != Some(self.redirect_uri.as_str())
+        {
+            return Err(invalid("redirect_uri does not match the authorization request"));
+        }
+        let code_verifier = code_verifier.ok_or_else(|| OAuthError::InvalidRequest("code_verifier is required".to_string()))?;
+        if !verify_pkce(code_verifier, &self.code_challenge)
+        {
+            return Err(invalid("code_verifier does not match the code_challenge"));
+        }
+        Ok(())
+    }
+}
+
+/// RFC 7636 `S256`: the challenge is the unpadded base64url SHA-256 of the verifier, which is
+/// 43 to 128 unreserved characters.
+pub fn verify_pkce(code_verifier: &str, code_challenge: &str) -> bool
+{
+    let well_formed = (43..=128).contains(&code_verifier.len())
+                      && code_verifier.chars().all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c));
+    let computed = BASE64URL_NOPAD.encode(&Sha256::digest(code_verifier.as_bytes()));
+    well_formed && constant_time_eq(computed.as_bytes(), code_challenge.as_bytes())
+}
+
+/// `redirect_uri` with `params` appended to its query.
+pub fn redirect_uri_with(redirect_uri: &str, params: &[(&str, Option<&str>)]) -> String
+{
+    let query = params.iter()
+                      .filter_map(|(name, value)| value.map(|value| format!("{}={}", name, url_encode(value))))
+                      .collect::<Vec<_>>()
+                      .join("&");
+    let separator = if redirect_uri.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", redirect_uri, separator, query)
+}
+
+fn url_encode(value: &str) -> String
+{
+    value.bytes()
+         .map(|byte| match byte
+         {
+             b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+             _ => format!("%{:02X}", byte),
+         })
+         .collect()
+}
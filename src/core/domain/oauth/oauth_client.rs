@@ -0,0 +1,158 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::oauth::oauth_error::OAuthError;
+use crate::utils::secrets::{constant_time_eq, random_token, sha256_hex};
+
+/// An application that signs users in through the authorization code flow. Public clients
+/// (SPAs, mobile apps) have no secret and rely on PKCE alone; confidential ones also
+/// authenticate at the token endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OAuthClient
+{
+    pub _id:            Option<ObjectId>,
+    pub client_id:      String,
+    pub name:           String,
+    /// Compared exactly against the `redirect_uri` of each request.
+    pub redirect_uris:  Vec<String>,
+    /// Permissions the client may ask for; users still only get the ones they hold.
+    pub allowed_scopes: Vec<u32>,
+    pub secret_hash:    Option<String>,
+    /// Our own apps: users aren't asked for consent.
+    #[serde(default)]
+    pub first_party:    bool,
+    pub created_at:     DateTime,
+    pub disabled_at:    Option<DateTime>,
+}
+
+/// Body of `POST /api/oauth/clients`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct NewOAuthClient
+{
+    pub name:           String,
+    pub redirect_uris:  Vec<String>,
+    pub allowed_scopes: Vec<u32>,
+    #[serde(default)]
+    pub confidential:   bool,
+    #[serde(default)]
+    pub first_party:    bool,
+}
+
+impl OAuthClient
+{
+    /// Builds the client, with its secret when it is confidential.
+    pub fn register(new_client: NewOAuthClient) -> Result<(Self, Option<String>), OAuthError>
+    {
+        let name = new_client.name.trim().to_string();
+        if name.is_empty()
+        {
+            return Err(OAuthError::InvalidRequest("name is required".to_string()));
+        }
+        if new_client.redirect_uris.is_empty()
+        {
+            return Err(OAuthError::InvalidRequest("at least one redirect URI is required".to_string()));
+        }
+        if let Some(uri) = new_client.redirect_uris.iter().find(|uri| !valid_redirect_uri(uri))
+        {
+            return Err(OAuthError::InvalidRequest(format!("invalid redirect URI {}", uri)));
+        }
+
+        let secret = new_client.confidential.then(|| random_token(32));
+        let mut allowed_scopes = new_client.allowed_scopes;
+        allowed_scopes.sort_unstable();
+        allowed_scopes.dedup();
+        let client = Self { _id: None,
+                            client_id: random_token(16),
+                            name,
+                            redirect_uris: new_client.redirect_uris,
+                            allowed_scopes,
+                            secret_hash: secret.as_deref().map(sha256_hex),
+                            first_party: new_client.first_party,
+                            created_at: DateTime::now(),
+                            disabled_at: None };
+        Ok((client, secret))
+    }
+
+    pub fn is_confidential(&self) -> bool
+    {
+        self.secret_hash.is_some()
+    }
+
+    pub fn has_redirect_uri(&self, redirect_uri: &str) -> bool
+    {
+        self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+    }
+
+    /// Public clients must not send a secret; confidential ones must send theirs.
+    pub fn authenticate(&self, secret: Option<&str>) -> Result<(), OAuthError>
+    {
+        if self.disabled_at.is_some()
+        {
+            return Err(OAuthError::InvalidClient);
+        }
+        match (&self.secret_hash, secret)
+        {
+            (None, None) => Ok(()),
+            (Some(secret_hash), Some(secret)) if constant_time_eq(secret_hash.as_bytes(), sha256_hex(secret).as_bytes()) => Ok(()),
+            _ => Err(OAuthError::InvalidClient),
+        }
+    }
+}
+
+/// Absolute, without fragment, and either https, a loopback http address or an app's own
+/// scheme (`com.example.app:/callback`).
+pub fn valid_redirect_uri(uri: &str) -> bool
+{
+    let Some((scheme, rest)) = uri.split_once(':') else { return false };
+    if uri.contains('#') || rest.is_empty() || !scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+    {
+        return false;
+    }
+    match scheme
+    {
+        "https" => rest.starts_with("//") && rest.len() > 2,
+        "http" => ["//localhost", "//127.0.0.1", "//[::1]"].iter().any(|host| {
+            rest.strip_prefix(host).is_some_and(|tail| tail.is_empty() || tail.starts_with([':', '/', '?']))
+        }),
+        "javascript" | "data" | "file" => false,
+        _ => scheme.contains('.'),
+    }
+}
+
+/// Returned once on registration; `client_secret` can't be retrieved again.
+#[derive(Serialize, Debug, Clone)]
+pub struct RegisteredOAuthClient
+{
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    #[serde(flatten)]
+    pub client:        OAuthClientSummary,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OAuthClientSummary
+{
+    pub client_id:      String,
+    pub name:           String,
+    pub redirect_uris:  Vec<String>,
+    pub allowed_scopes: Vec<u32>,
+    pub confidential:   bool,
+    pub first_party:    bool,
+    pub created_at:     i64,
+    pub disabled_at:    Option<i64>,
+}
+
+impl From<&OAuthClient> for OAuthClientSummary
+{
+    fn from(client: &OAuthClient) -> Self
+    {
+        Self { client_id:      client.client_id.clone(),
+               name:           client.name.clone(),
+               redirect_uris:  client.redirect_uris.clone(),
+               allowed_scopes: client.allowed_scopes.clone(),
+               confidential:   client.is_confidential(),
+               first_party:    client.first_party,
+               created_at:     client.created_at.timestamp_millis(),
+               disabled_at:    client.disabled_at.map(|disabled_at| disabled_at.timestamp_millis()), }
+    }
+}
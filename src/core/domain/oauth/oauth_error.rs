@@ -0,0 +1,68 @@
+use thiserror::Error;
+
+use crate::core::domain::auth::auth_error::AuthError;
+
+/// Errors of the OAuth endpoints. `code` is the RFC 6749 error code sent to the client; the
+/// message goes in `error_description`.
+#[derive(Error, Debug)]
+pub enum OAuthError {
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    #[error("Client authentication failed")]
+    InvalidClient,
+
+    #[error("{0}")]
+    InvalidGrant(String),
+
+    #[error("The client isn't allowed to use this grant")]
+    UnauthorizedClient,
+
+    #[error("Unsupported grant type {0}")]
+    UnsupportedGrantType(String),
+
+    #[error("{0}")]
+    InvalidScope(String),
+
+    #[error("Unsupported response type {0}")]
+    UnsupportedResponseType(String),
+
+    #[error("The user denied the request")]
+    AccessDenied,
+
+    #[error("The access token is invalid, expired or revoked")]
+    InvalidToken,
+
+    #[error("Not has permission")]
+    NotHasPermission,
+
+    /// The user's login at the authorize step failed; reported to the login page, not the client.
+    #[error("{0}")]
+    Login(#[from] AuthError),
+
+    #[error("Internal server error")]
+    ServerError,
+
+    #[error("Mongo error: {0}")]
+    MongoError(#[from] mongodb::error::Error),
+}
+
+impl OAuthError
+{
+    pub fn code(&self) -> &'static str
+    {
+        match self
+        {
+            OAuthError::InvalidRequest(_) => "invalid_request",
+            OAuthError::InvalidClient => "invalid_client",
+            OAuthError::InvalidGrant(_) => "invalid_grant",
+            OAuthError::UnauthorizedClient => "unauthorized_client",
+            OAuthError::UnsupportedGrantType(_) => "unsupported_grant_type",
+            OAuthError::InvalidScope(_) => "invalid_scope",
+            OAuthError::UnsupportedResponseType(_) => "unsupported_response_type",
+            OAuthError::InvalidToken => "invalid_token",
+            OAuthError::AccessDenied | OAuthError::NotHasPermission | OAuthError::Login(_) => "access_denied",
+            OAuthError::ServerError | OAuthError::MongoError(_) => "server_error",
+        }
+    }
+}
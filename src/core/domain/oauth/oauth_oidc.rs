@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::oauth::oauth_authorize::{CODE_RESPONSE_TYPE, PKCE_METHOD_S256};
+use crate::core::domain::oauth::oauth_type::{AUTHORIZATION_CODE_GRANT, CLIENT_CREDENTIALS_GRANT, REFRESH_TOKEN_GRANT};
+use crate::core::domain::signing_key::SigningAlgorithm;
+use crate::core::domain::user::User;
+
+pub const OPENID_SCOPE: &str = "openid";
+pub const PROFILE_SCOPE: &str = "profile";
+pub const EMAIL_SCOPE: &str = "email";
+
+/// The OpenID Connect scopes of a request. They ask for claims about the user, not for
+/// permissions, so they travel apart from the permission ids.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IdentityScopes
+{
+    pub openid:  bool,
+    pub profile: bool,
+    pub email:   bool,
+}
+
+impl IdentityScopes
+{
+    /// Takes the identity scopes out of `scope`; what is left are the permission ids, or `None`
+    /// when nothing is left.
+    pub fn split(scope: Option<&str>) -> (Self, Option<String>)
+    {
+        let mut identity = Self::default();
+        let mut permissions = Vec::new();
+        for scope in scope.unwrap_or_default().split_whitespace()
+        {
+            match scope
+            {
+                OPENID_SCOPE => identity.openid = true,
+                PROFILE_SCOPE => identity.profile = true,
+                EMAIL_SCOPE => identity.email = true,
+                scope => permissions.push(scope),
+            }
+        }
+        // Without `openid` the request isn't an OpenID one and there are no claims to release.
+        if !identity.openid
+        {
+            identity = Self::default();
+        }
+        (identity, (!permissions.is_empty()).then(|| permissions.join(" ")))
+    }
+
+    /// The scopes held in both.
+    pub fn intersect(&self, other: &Self) -> Self
+    {
+        Self { openid:  self.openid && other.openid,
+               profile: self.profile && other.profile,
+               email:   self.email && other.email, }
+    }
+
+    pub fn names(&self) -> Vec<&'static str>
+    {
+        [(self.openid, OPENID_SCOPE), (self.profile, PROFILE_SCOPE), (self.email, EMAIL_SCOPE)].into_iter()
+                                                                                             .filter_map(|(held, name)| held.then_some(name))
+                                                                                             .collect()
+    }
+}
+
+/// Standard claims about the user, released according to the identity scopes. Served as is by
+/// the userinfo endpoint and embedded in ID tokens.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct UserInfo
+{
+    pub sub:                String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name:               Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email:              Option<String>,
+}
+
+impl UserInfo
+{
+    pub fn new(user: &User, identity: &IdentityScopes) -> Self
+    {
+        Self { sub:                user._id.as_ref().map(|id| id.value().to_hex()).unwrap_or_default(),
+               name:               identity.profile.then(|| user.name.clone()),
+               preferred_username: identity.profile.then(|| user.username.clone()),
+               email:              identity.email.then(|| user.email.clone()), }
+    }
+}
+
+/// Claims of an ID token (OpenID Connect Core §2).
+#[derive(Serialize, Debug, Clone)]
+pub struct IdTokenClaims
+{
+    pub iss:   String,
+    pub aud:   String,
+    pub iat:   i64,
+    pub exp:   i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(flatten)]
+    pub user:  UserInfo,
+}
+
+impl IdTokenClaims
+{
+    pub fn new(issuer: &str, client_id: &str, user: UserInfo, nonce: Option<String>, now_secs: i64, ttl_secs: i64) -> Self
+    {
+        Self { iss: issuer.to_string(), aud: client_id.to_string(), iat: now_secs, exp: now_secs + ttl_secs, nonce, user }
+    }
+}
+
+/// `/.well-known/openid-configuration` (OpenID Connect Discovery §3).
+#[derive(Serialize, Debug, Clone)]
+pub struct DiscoveryDocument
+{
+    pub issuer:                                String,
+    pub authorization_endpoint:                String,
+    pub token_endpoint:                        String,
+    pub userinfo_endpoint:                     String,
+    pub revocation_endpoint:                   String,
+    pub jwks_uri:                              String,
+    pub response_types_supported:              Vec<&'static str>,
+    pub grant_types_supported:                 Vec<&'static str>,
+    pub subject_types_supported:               Vec<&'static str>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub scopes_supported:                      Vec<&'static str>,
+    pub claims_supported:                      Vec<&'static str>,
+    pub token_endpoint_auth_methods_supported: Vec<&'static str>,
+    pub code_challenge_methods_supported:      Vec<&'static str>,
+}
+
+impl DiscoveryDocument
+{
+    pub fn new(issuer: &str) -> Self
+    {
+        let issuer = issuer.trim_end_matches('/');
+        let endpoint = |path: &str| format!("{}{}", issuer, path);
+        Self { issuer:                                issuer.to_string(),
+               authorization_endpoint:                endpoint("/api/oauth/authorize"),
+               token_endpoint:                        endpoint("/api/oauth/token"),
+               userinfo_endpoint:                     endpoint("/api/oauth/userinfo"),
+               revocation_endpoint:                   endpoint("/api/oauth/revoke"),
+               jwks_uri:                              endpoint("/.well-known/jwks.json"),
+               response_types_supported:              vec![CODE_RESPONSE_TYPE],
+               grant_types_supported:                 vec![AUTHORIZATION_CODE_GRANT, REFRESH_TOKEN_GRANT, CLIENT_CREDENTIALS_GRANT],
+               subject_types_supported:               vec!["public"],
+               id_token_signing_alg_values_supported: vec![SigningAlgorithm::RS256.to_string(), SigningAlgorithm::EdDSA.to_string()],
+               scopes_supported:                      vec![OPENID_SCOPE, PROFILE_SCOPE, EMAIL_SCOPE],
+               claims_supported:                      vec!["sub", "iss", "aud", "iat", "exp", "nonce", "name", "preferred_username", "email"],
+               token_endpoint_auth_methods_supported: vec!["client_secret_basic", "client_secret_post", "none"],
+               code_challenge_methods_supported:      vec![PKCE_METHOD_S256], }
+    }
+}
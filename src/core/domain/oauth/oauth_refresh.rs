@@ -0,0 +1,60 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::oauth::oauth_oidc::IdentityScopes;
+use crate::utils::domains_ids::{AuthID, UserID};
+use crate::utils::secrets::{random_token, sha256_hex};
+
+pub const REFRESH_TOKEN_PREFIX: &str = "rt_";
+
+/// Issued with every authorization code exchange and replaced on each use. It remembers the
+/// session of the access token issued with it so both end together.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RefreshToken
+{
+    pub _id:                Option<ObjectId>,
+    pub token_hash:         String,
+    pub client_id:          String,
+    pub auth_id:            AuthID,
+    pub user_id:            UserID,
+    pub scopes:             Vec<u32>,
+    #[serde(default)]
+    pub identity:           IdentityScopes,
+    pub session_token_hash: String,
+    pub created_at:         DateTime,
+    pub expires_at:         DateTime,
+    pub revoked_at:         Option<DateTime>,
+}
+
+impl RefreshToken
+{
+    pub fn issue(client_id: String,
+                 auth_id: AuthID,
+                 user_id: UserID,
+                 scopes: Vec<u32>,
+                 identity: IdentityScopes,
+                 session_token_hash: String,
+                 ttl_secs: i64)
+                 -> (Self, String)
+    {
+        let token = format!("{}{}", REFRESH_TOKEN_PREFIX, random_token(32));
+        let now = DateTime::now();
+        let refresh_token = Self { _id: None,
+                                   token_hash: sha256_hex(&token),
+                                   client_id,
+                                   auth_id,
+                                   user_id,
+                                   scopes,
+                                   identity,
+                                   session_token_hash,
+                                   created_at: now,
+                                   expires_at: DateTime::from_millis(now.timestamp_millis() + ttl_secs * 1000),
+                                   revoked_at: None };
+        (refresh_token, token)
+    }
+
+    pub fn is_usable(&self, now: DateTime) -> bool
+    {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+pub const CLIENT_CREDENTIALS_GRANT: &str = "client_credentials";
+pub const AUTHORIZATION_CODE_GRANT: &str = "authorization_code";
+pub const REFRESH_TOKEN_GRANT: &str = "refresh_token";
+
+/// Form body of `POST /api/oauth/token`. The client may authenticate here
+/// (`client_secret_post`) or with HTTP Basic (`client_secret_basic`).
+#[derive(Deserialize, Debug, Clone)]
+pub struct TokenRequest
+{
+    pub grant_type:    String,
+    pub client_id:     Option<String>,
+    pub client_secret: Option<String>,
+    pub scope:         Option<String>,
+    pub code:          Option<String>,
+    pub redirect_uri:  Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+}
+
+/// Form body of `POST /api/oauth/revoke` (RFC 7009).
+#[derive(Deserialize, Debug, Clone)]
+pub struct RevokeRequest
+{
+    pub token:         String,
+    pub client_id:     Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// Client id and secret, from whichever place the client sent them. Public clients only send
+/// their id.
+#[derive(Debug, Clone)]
+pub struct ClientCredentials
+{
+    pub client_id:     String,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TokenResponse
+{
+    pub access_token:  String,
+    pub token_type:    &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in:    Option<i64>,
+    pub scope:         String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Only when the `openid` scope was granted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token:      Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OAuthErrorBody
+{
+    pub error:             &'static str,
+    pub error_description: String,
+}
@@ -4,6 +4,7 @@ use crate::data::access::perms_repo::MongoPermRepo;
 use crate::utils::domains_ids::PermID;
 
 pub mod perm_cat;
+pub mod perm_hierarchy;
 pub mod perm_repo;
 pub mod perm_type;
 pub mod perm_error;
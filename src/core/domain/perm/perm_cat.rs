@@ -4,5 +4,8 @@ pub const UPDATE_USER: u32 = 3;
 pub const DELETE_USER: u32 = 4;
 pub const UPDATE_USER_ADMINISTRATION: u32 = 5;
 pub const CHANGE_ROLE: u32 = 6;
+pub const READ_AUDIT: u32 = 7;
+pub const MANAGE_SERVICE_ACCOUNTS: u32 = 8;
+pub const MANAGE_OAUTH_CLIENTS: u32 = 9;
 
 
@@ -39,5 +39,8 @@ pub enum PermError
     PermDocNotUpdated,
 
     #[error("Permission Document Isn't Created")]
-    PermDocumentNotCreated
+    PermDocumentNotCreated,
+
+    #[error("Invalid role hierarchy: {0}")]
+    InvalidRoleHierarchy(String),
 }
\ No newline at end of file
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::perm::perm_type::PermsRelationship;
+
+/// The role catalog with inheritance resolved: a role holds its own permissions and those of
+/// every role it inherits from, directly or through other roles.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoleHierarchy
+{
+    permissions: HashMap<Role, Vec<u32>>,
+}
+
+/// A role as the catalog declares it. A role listed more than once gets the union of its
+/// entries, as it always did.
+#[derive(Default)]
+struct Declared
+{
+    perms:   Vec<u32>,
+    parents: Vec<Role>,
+}
+
+impl RoleHierarchy
+{
+    /// Refuses catalogs where a role inherits from a role the catalog doesn't define, or ends
+    /// up inheriting from itself.
+    pub fn new(relationships: &[PermsRelationship]) -> Result<Self, String>
+    {
+        let mut declared: HashMap<&Role, Declared> = HashMap::new();
+        for relationship in relationships
+        {
+            let role = declared.entry(&relationship.role).or_default();
+            role.perms.extend(&relationship.perms);
+            role.parents.extend(relationship.parents.iter().cloned());
+        }
+        for relationship in relationships
+        {
+            if let Some(parent) = relationship.parents.iter().find(|parent| !declared.contains_key(parent))
+            {
+                return Err(format!("role {} inherits from {}, which isn't in the catalog", relationship.role, parent));
+            }
+        }
+
+        let mut permissions = HashMap::new();
+        for relationship in relationships
+        {
+            resolve(&relationship.role, &declared, &mut Vec::new(), &mut permissions)?;
+        }
+        Ok(Self { permissions })
+    }
+
+    /// Effective permissions of `role`, its own and the inherited ones, in ascending order.
+    pub fn permissions(&self, role: &Role) -> Option<&[u32]>
+    {
+        self.permissions.get(role).map(Vec::as_slice)
+    }
+
+    pub fn into_permissions(self) -> HashMap<Role, Vec<u32>>
+    {
+        self.permissions
+    }
+}
+
+fn resolve(role: &Role, declared: &HashMap<&Role, Declared>, path: &mut Vec<Role>, resolved: &mut HashMap<Role, Vec<u32>>)
+           -> Result<Vec<u32>, String>
+{
+    if let Some(perms) = resolved.get(role)
+    {
+        return Ok(perms.clone());
+    }
+    if let Some(start) = path.iter().position(|seen| seen == role)
+    {
+        let cycle: Vec<String> = path[start..].iter().chain([role]).map(Role::to_string).collect();
+        return Err(format!("roles inherit from each other: {}", cycle.join(" -> ")));
+    }
+    let Some(own) = declared.get(role) else { return Ok(Vec::new()) };
+
+    path.push(role.clone());
+    let mut perms = own.perms.clone();
+    for parent in &own.parents
+    {
+        perms.extend(resolve(parent, declared, path, resolved)?);
+    }
+    path.pop();
+    perms.sort_unstable();
+    perms.dedup();
+
+    resolved.insert(role.clone(), perms.clone());
+    Ok(perms)
+}
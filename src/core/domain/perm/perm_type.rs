@@ -2,9 +2,13 @@ use serde::{Deserialize, Serialize};
 
 use crate::core::domain::auth::auth_type::Role;
 
+/// A role of the catalog. `perms` are the role's own permissions; it also inherits those of
+/// its `parents`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PermsRelationship
 {
-    pub role:  Role,
-    pub perms: Vec<u32>,
+    pub role:    Role,
+    pub perms:   Vec<u32>,
+    #[serde(default)]
+    pub parents: Vec<Role>,
 }
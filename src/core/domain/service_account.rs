@@ -0,0 +1,133 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::auth::auth_type::{Claims, Role};
+use crate::core::domain::service_account::service_account_error::ServiceAccountError;
+use crate::utils::secrets::{constant_time_eq, random_token, sha256_hex};
+
+pub mod service_account_error;
+pub mod service_account_token;
+pub mod service_account_type;
+
+pub const CLIENT_ID_PREFIX: &str = "svc_";
+
+/// A non-human identity for backend to backend calls. It authenticates with its client id and
+/// one of its secrets, and gets the permissions of its role.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceAccount
+{
+    pub _id:         Option<ObjectId>,
+    pub client_id:   String,
+    pub name:        String,
+    pub description: String,
+    pub role:        Role,
+    pub secrets:     Vec<ClientSecret>,
+    pub created_at:  DateTime,
+    pub disabled_at: Option<DateTime>,
+}
+
+/// Only the hash is stored. A secret without `expires_at` is the current one; rotating gives the
+/// previous one an expiry so callers can switch over.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientSecret
+{
+    pub id:          String,
+    pub secret_hash: String,
+    pub created_at:  DateTime,
+    pub expires_at:  Option<DateTime>,
+}
+
+impl ClientSecret
+{
+    fn generate(now: DateTime) -> (Self, String)
+    {
+        let secret = random_token(32);
+        let client_secret = Self { id: random_token(8), secret_hash: sha256_hex(&secret), created_at: now, expires_at: None };
+        (client_secret, secret)
+    }
+
+    pub fn is_active(&self, now: DateTime) -> bool
+    {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+impl ServiceAccount
+{
+    /// Builds the account and its first secret.
+    pub fn new(name: String, description: String, role: Role) -> (Self, String)
+    {
+        let now = DateTime::now();
+        let (client_secret, secret) = ClientSecret::generate(now);
+        let service_account = Self { _id: None,
+                                     client_id: format!("{}{}", CLIENT_ID_PREFIX, random_token(12)),
+                                     name,
+                                     description,
+                                     role,
+                                     secrets: vec![client_secret],
+                                     created_at: now,
+                                     disabled_at: None };
+        (service_account, secret)
+    }
+
+    /// Adds a new current secret. The one it replaces stays valid for `overlap_ms`; any other
+    /// secret is dropped, so at most two are ever active.
+    pub fn rotate_secret(&mut self, now: DateTime, overlap_ms: i64) -> String
+    {
+        let overlap_end = DateTime::from_millis(now.timestamp_millis() + overlap_ms);
+        let previous = self.secrets
+                           .iter()
+                           .filter(|client_secret| client_secret.is_active(now))
+                           .max_by_key(|client_secret| client_secret.created_at)
+                           .cloned()
+                           .map(|client_secret| ClientSecret {
+                               expires_at: Some(client_secret.expires_at.map_or(overlap_end, |expires_at| expires_at.min(overlap_end))),
+                               ..client_secret
+                           });
+
+        let (client_secret, secret) = ClientSecret::generate(now);
+        self.secrets = previous.into_iter().chain(std::iter::once(client_secret)).collect();
+        secret
+    }
+
+    /// Ends a secret before its overlap runs out. The last active secret can't be revoked;
+    /// disable the account instead.
+    pub fn revoke_secret(&mut self, secret_id: &str, now: DateTime) -> Result<(), ServiceAccountError>
+    {
+        let position = self.secrets
+                           .iter()
+                           .position(|client_secret| client_secret.id == secret_id && client_secret.is_active(now))
+                           .ok_or(ServiceAccountError::SecretNotFound)?;
+        if self.secrets.iter().filter(|client_secret| client_secret.is_active(now)).count() < 2
+        {
+            return Err(ServiceAccountError::LastSecret);
+        }
+        self.secrets.remove(position);
+        Ok(())
+    }
+
+    pub fn verify_secret(&self, secret: &str, now: DateTime) -> bool
+    {
+        let secret_hash = sha256_hex(secret);
+        self.secrets
+            .iter()
+            .filter(|client_secret| client_secret.is_active(now))
+            .any(|client_secret| constant_time_eq(client_secret.secret_hash.as_bytes(), secret_hash.as_bytes()))
+    }
+
+    pub fn ensure_enabled(&self) -> Result<(), ServiceAccountError>
+    {
+        match self.disabled_at
+        {
+            Some(_) => Err(ServiceAccountError::Disabled),
+            None => Ok(()),
+        }
+    }
+
+    /// Claims for a token issued to the account. The client id stands in for the user id, so it
+    /// is what `sub` and audit entries show.
+    pub fn token_claims(&self, permissions: Vec<u32>) -> Claims
+    {
+        Claims::new(self.client_id.clone(), self.role.clone(), permissions)
+    }
+}
@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ServiceAccountError {
+    #[error("Not has permission")]
+    NotHasPermission,
+
+    #[error("Service account not found")]
+    NotFound,
+
+    #[error("Service account is disabled")]
+    Disabled,
+
+    #[error("Client secret not found")]
+    SecretNotFound,
+
+    #[error("The last active client secret can't be revoked")]
+    LastSecret,
+
+    #[error("Invalid service account: {0}")]
+    Invalid(String),
+
+    #[error("Service account document isn't valid")]
+    DocumentError,
+
+    #[error("Permissions of the role could not be loaded")]
+    PermError,
+
+    #[error("Mongo error: {0}")]
+    MongoError(#[from] mongodb::error::Error),
+}
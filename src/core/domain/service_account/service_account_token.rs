@@ -0,0 +1,38 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::secrets::sha256_hex;
+
+/// Record of a token issued through the client credentials grant. The token itself is a
+/// `perms` JWT; the record is what lets introspection vouch for it and notice a disabled
+/// account. A TTL index drops it once the token has expired.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceAccountToken
+{
+    pub _id:        Option<ObjectId>,
+    pub token_hash: String,
+    pub client_id:  String,
+    pub scopes:     Vec<u32>,
+    pub issued_at:  DateTime,
+    pub expires_at: DateTime,
+}
+
+impl ServiceAccountToken
+{
+    /// `exp` is the token's own expiry, in unix seconds.
+    pub fn new(token: &str, client_id: String, scopes: Vec<u32>, exp: i64) -> Self
+    {
+        Self { _id: None,
+               token_hash: sha256_hex(token),
+               client_id,
+               scopes,
+               issued_at: DateTime::now(),
+               expires_at: DateTime::from_millis(exp * 1000) }
+    }
+
+    /// Scopes the account's role still grants.
+    pub fn effective_permissions(&self, role_permissions: &[u32]) -> Vec<u32>
+    {
+        self.scopes.iter().copied().filter(|scope| role_permissions.contains(scope)).collect()
+    }
+}
@@ -0,0 +1,69 @@
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::service_account::ServiceAccount;
+
+/// Body of `POST /api/service-accounts`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct NewServiceAccount
+{
+    pub name:        String,
+    #[serde(default)]
+    pub description: String,
+    pub role:        Role,
+}
+
+/// Returned when an account is created or its secret rotated; `client_secret` can't be retrieved again.
+#[derive(Serialize, Debug, Clone)]
+pub struct IssuedClientSecret
+{
+    pub client_secret: String,
+    #[serde(flatten)]
+    pub account:       ServiceAccountSummary,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ClientSecretSummary
+{
+    pub id:         String,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+/// An account without its secret hashes; expired secrets are left out.
+#[derive(Serialize, Debug, Clone)]
+pub struct ServiceAccountSummary
+{
+    pub client_id:   String,
+    pub name:        String,
+    pub description: String,
+    pub role:        Role,
+    pub secrets:     Vec<ClientSecretSummary>,
+    pub created_at:  i64,
+    pub disabled_at: Option<i64>,
+}
+
+impl From<&ServiceAccount> for ServiceAccountSummary
+{
+    fn from(service_account: &ServiceAccount) -> Self
+    {
+        let now = DateTime::now();
+        let secrets = service_account.secrets
+                                     .iter()
+                                     .filter(|client_secret| client_secret.is_active(now))
+                                     .map(|client_secret| ClientSecretSummary {
+                                         id:         client_secret.id.clone(),
+                                         created_at: client_secret.created_at.timestamp_millis(),
+                                         expires_at: client_secret.expires_at.map(|expires_at| expires_at.timestamp_millis()),
+                                     })
+                                     .collect();
+        Self { client_id: service_account.client_id.clone(),
+               name: service_account.name.clone(),
+               description: service_account.description.clone(),
+               role: service_account.role.clone(),
+               secrets,
+               created_at: service_account.created_at.timestamp_millis(),
+               disabled_at: service_account.disabled_at.map(|disabled_at| disabled_at.timestamp_millis()) }
+    }
+}
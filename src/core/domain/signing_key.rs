@@ -0,0 +1,358 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use data_encoding::{BASE64, BASE64URL_NOPAD};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use rand::rngs::OsRng;
+use rsa::pkcs1v15;
+use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey};
+use rsa::signature::{SignatureEncoding, Signer as _, Verifier as _};
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, RsaPrivateKey, RsaPublicKey};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::domain::signing_key::signing_key_error::SigningKeyError;
+use crate::utils::secrets::random_bytes;
+
+pub mod signing_key_error;
+
+const RSA_BITS: usize = 2048;
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningAlgorithm
+{
+    RS256,
+    EdDSA,
+}
+
+impl fmt::Display for SigningAlgorithm
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            SigningAlgorithm::RS256 => write!(f, "RS256"),
+            SigningAlgorithm::EdDSA => write!(f, "EdDSA"),
+        }
+    }
+}
+
+impl FromStr for SigningAlgorithm
+{
+    type Err = SigningKeyError;
+
+    fn from_str(alg: &str) -> Result<Self, Self::Err>
+    {
+        match alg
+        {
+            "RS256" => Ok(SigningAlgorithm::RS256),
+            "EdDSA" => Ok(SigningAlgorithm::EdDSA),
+            alg => Err(SigningKeyError::UnsupportedAlgorithm(alg.to_string())),
+        }
+    }
+}
+
+/// The key new tokens are signed with, or one rotated out that is still published so tokens
+/// it signed keep verifying until they expire.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningKeyStatus
+{
+    Active,
+    Previous,
+}
+
+/// A signing key as stored: the public half as a JWK, the private half encrypted with
+/// `SIGNING_KEY_ENCRYPTION_KEY`. A TTL index drops it at `expires_at`, which is only set once
+/// the key is rotated out.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SigningKey
+{
+    pub _id:         Option<ObjectId>,
+    pub kid:         String,
+    pub alg:         SigningAlgorithm,
+    pub status:      SigningKeyStatus,
+    pub public_jwk:  Jwk,
+    /// base64 of the AES-GCM nonce followed by the ciphertext, bound to `kid`.
+    pub private_key: String,
+    pub created_at:  DateTime,
+    pub expires_at:  Option<DateTime>,
+}
+
+impl SigningKey
+{
+    /// A new active key of `alg`, with its private half encrypted by `cipher`.
+    pub fn generate(alg: SigningAlgorithm, cipher: &KeyCipher) -> Result<(Self, KeyPair), SigningKeyError>
+    {
+        let key_pair = KeyPair::generate(alg)?;
+        let public_jwk = key_pair.public_jwk();
+        let private_key = cipher.encrypt(&public_jwk.kid, &key_pair.private_der()?)?;
+        let signing_key = Self { _id: None,
+                                 kid: public_jwk.kid.clone(),
+                                 alg,
+                                 status: SigningKeyStatus::Active,
+                                 public_jwk,
+                                 private_key,
+                                 created_at: DateTime::now(),
+                                 expires_at: None };
+        Ok((signing_key, key_pair))
+    }
+
+    pub fn key_pair(&self, cipher: &KeyCipher) -> Result<KeyPair, SigningKeyError>
+    {
+        let der = cipher.decrypt(&self.kid, &self.private_key)?;
+        KeyPair::from_der(self.alg, &der).ok_or_else(|| SigningKeyError::DecryptionFailed(self.kid.clone()))
+    }
+
+    /// Due for rotation once it has been active for `rotation_ms`.
+    pub fn is_due(&self, now: DateTime, rotation_ms: i64) -> bool
+    {
+        now.timestamp_millis() - self.created_at.timestamp_millis() >= rotation_ms
+    }
+}
+
+/// A private key ready to sign.
+pub enum KeyPair
+{
+    Rsa(Box<RsaPrivateKey>),
+    Ed25519(Box<ed25519_dalek::SigningKey>),
+}
+
+impl KeyPair
+{
+    pub fn generate(alg: SigningAlgorithm) -> Result<Self, SigningKeyError>
+    {
+        match alg
+        {
+            SigningAlgorithm::RS256 => {
+                let key = RsaPrivateKey::new(&mut OsRng, RSA_BITS).map_err(|_| SigningKeyError::GenerationFailed)?;
+                Ok(KeyPair::Rsa(Box::new(key)))
+            },
+            SigningAlgorithm::EdDSA => {
+                let seed: [u8; 32] = random_bytes(32).try_into().map_err(|_| SigningKeyError::GenerationFailed)?;
+                Ok(KeyPair::Ed25519(Box::new(ed25519_dalek::SigningKey::from_bytes(&seed))))
+            },
+        }
+    }
+
+    pub fn alg(&self) -> SigningAlgorithm
+    {
+        match self
+        {
+            KeyPair::Rsa(_) => SigningAlgorithm::RS256,
+            KeyPair::Ed25519(_) => SigningAlgorithm::EdDSA,
+        }
+    }
+
+    /// PKCS#8 DER for RSA, the 32 byte seed for Ed25519.
+    fn private_der(&self) -> Result<Vec<u8>, SigningKeyError>
+    {
+        match self
+        {
+            KeyPair::Rsa(key) => Ok(key.to_pkcs8_der().map_err(|_| SigningKeyError::GenerationFailed)?.as_bytes().to_vec()),
+            KeyPair::Ed25519(key) => Ok(key.to_bytes().to_vec()),
+        }
+    }
+
+    fn from_der(alg: SigningAlgorithm, der: &[u8]) -> Option<Self>
+    {
+        match alg
+        {
+            SigningAlgorithm::RS256 => RsaPrivateKey::from_pkcs8_der(der).ok().map(|key| KeyPair::Rsa(Box::new(key))),
+            SigningAlgorithm::EdDSA => {
+                let seed: [u8; 32] = der.try_into().ok()?;
+                Some(KeyPair::Ed25519(Box::new(ed25519_dalek::SigningKey::from_bytes(&seed))))
+            },
+        }
+    }
+
+    pub fn public_jwk(&self) -> Jwk
+    {
+        match self
+        {
+            KeyPair::Rsa(key) => Jwk::rsa(&RsaPublicKey::from(key.as_ref())),
+            KeyPair::Ed25519(key) => Jwk::ed25519(&key.verifying_key()),
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8>
+    {
+        match self
+        {
+            KeyPair::Rsa(key) => pkcs1v15::SigningKey::<Sha256>::new(key.as_ref().clone()).sign(message).to_vec(),
+            KeyPair::Ed25519(key) => key.sign(message).to_bytes().to_vec(),
+        }
+    }
+
+    /// Compact JWS of `claims`, with `kid` in the header so verifiers pick the right key
+    /// from the JWKS during a rotation.
+    pub fn sign_jwt<T: Serialize>(&self, claims: &T) -> Result<String, SigningKeyError>
+    {
+        let header = serde_json::json!({ "alg": self.alg().to_string(), "typ": "JWT", "kid": self.public_jwk().kid });
+        let encode = |value: Vec<u8>| BASE64URL_NOPAD.encode(&value);
+        let header = serde_json::to_vec(&header).map_err(|_| SigningKeyError::SigningFailed)?;
+        let claims = serde_json::to_vec(claims).map_err(|_| SigningKeyError::SigningFailed)?;
+        let signing_input = format!("{}.{}", encode(header), encode(claims));
+        Ok(format!("{}.{}", signing_input, encode(self.sign(signing_input.as_bytes()))))
+    }
+}
+
+/// Public key as published in the JWKS (RFC 7517), identified by its RFC 7638 thumbprint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Jwk
+{
+    pub kty:     String,
+    pub kid:     String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    pub alg:     String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n:       Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e:       Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv:     Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x:       Option<String>,
+}
+
+impl Jwk
+{
+    fn rsa(key: &RsaPublicKey) -> Self
+    {
+        let n = BASE64URL_NOPAD.encode(&key.n().to_bytes_be());
+        let e = BASE64URL_NOPAD.encode(&key.e().to_bytes_be());
+        // Required members in lexicographic order, without whitespace.
+        let kid = thumbprint(&format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, e, n));
+        Self { kty: "RSA".to_string(),
+               kid,
+               key_use: "sig".to_string(),
+               alg: SigningAlgorithm::RS256.to_string(),
+               n: Some(n),
+               e: Some(e),
+               crv: None,
+               x: None }
+    }
+
+    fn ed25519(key: &ed25519_dalek::VerifyingKey) -> Self
+    {
+        let x = BASE64URL_NOPAD.encode(key.as_bytes());
+        let kid = thumbprint(&format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, x));
+        Self { kty: "OKP".to_string(),
+               kid,
+               key_use: "sig".to_string(),
+               alg: SigningAlgorithm::EdDSA.to_string(),
+               n: None,
+               e: None,
+               crv: Some("Ed25519".to_string()),
+               x: Some(x) }
+    }
+
+    /// Whether `signature` over `message` was made with the private half of this key.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool
+    {
+        let decode = |value: &Option<String>| value.as_ref().and_then(|value| BASE64URL_NOPAD.decode(value.as_bytes()).ok());
+        match self.alg.parse()
+        {
+            Ok(SigningAlgorithm::RS256) => {
+                let (Some(n), Some(e)) = (decode(&self.n), decode(&self.e)) else { return false };
+                let Ok(key) = RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e)) else { return false };
+                let Ok(signature) = pkcs1v15::Signature::try_from(signature) else { return false };
+                pkcs1v15::VerifyingKey::<Sha256>::new(key).verify(message, &signature).is_ok()
+            },
+            Ok(SigningAlgorithm::EdDSA) => {
+                let Some(Ok(x)) = decode(&self.x).map(<[u8; 32]>::try_from) else { return false };
+                let Ok(key) = ed25519_dalek::VerifyingKey::from_bytes(&x) else { return false };
+                let Ok(signature) = ed25519_dalek::Signature::from_slice(signature) else { return false };
+                key.verify(message, &signature).is_ok()
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JwtHeader
+{
+    alg: String,
+    kid: String,
+}
+
+/// Claims of a compact JWS signed by one of `keys`, picked by the `kid` in its header. The
+/// header's `alg` has to be the key's own, so a token can't choose how it is checked.
+pub fn verify_jwt<T: DeserializeOwned>(token: &str, keys: &[Jwk]) -> Option<T>
+{
+    let (signing_input, signature) = token.rsplit_once('.')?;
+    let (header, claims) = signing_input.split_once('.')?;
+    let decode = |value: &str| BASE64URL_NOPAD.decode(value.as_bytes()).ok();
+    let header: JwtHeader = serde_json::from_slice(&decode(header)?).ok()?;
+    let key = keys.iter().find(|key| key.kid == header.kid && key.alg == header.alg)?;
+    if !key.verify(signing_input.as_bytes(), &decode(signature)?)
+    {
+        return None;
+    }
+    serde_json::from_slice(&decode(claims)?).ok()
+}
+
+fn thumbprint(canonical_jwk: &str) -> String
+{
+    BASE64URL_NOPAD.encode(&Sha256::digest(canonical_jwk.as_bytes()))
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Jwks
+{
+    pub keys: Vec<Jwk>,
+}
+
+/// AES-256-GCM with the key from `SIGNING_KEY_ENCRYPTION_KEY` (base64 of 32 bytes). Each
+/// ciphertext is bound to its `kid`, so private keys can't be swapped between records.
+pub struct KeyCipher
+{
+    cipher: Aes256Gcm,
+}
+
+impl KeyCipher
+{
+    pub fn new(key: [u8; 32]) -> Self
+    {
+        Self { cipher: Aes256Gcm::new(&key.into()) }
+    }
+
+    pub fn from_env() -> Result<Self, SigningKeyError>
+    {
+        let encoded = env::var("SIGNING_KEY_ENCRYPTION_KEY").map_err(|_| SigningKeyError::MissingEncryptionKey)?;
+        let key: [u8; 32] = BASE64.decode(encoded.trim().as_bytes())
+                                  .ok()
+                                  .and_then(|key| key.try_into().ok())
+                                  .ok_or(SigningKeyError::MissingEncryptionKey)?;
+        Ok(Self::new(key))
+    }
+
+    pub fn encrypt(&self, kid: &str, plaintext: &[u8]) -> Result<String, SigningKeyError>
+    {
+        let nonce = random_bytes(NONCE_LEN);
+        let ciphertext = self.cipher
+                             .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: kid.as_bytes() })
+                             .map_err(|_| SigningKeyError::GenerationFailed)?;
+        Ok(BASE64.encode(&[nonce, ciphertext].concat()))
+    }
+
+    pub fn decrypt(&self, kid: &str, encrypted: &str) -> Result<Vec<u8>, SigningKeyError>
+    {
+        let failed = || SigningKeyError::DecryptionFailed(kid.to_string());
+        let bytes = BASE64.decode(encrypted.as_bytes()).map_err(|_| failed())?;
+        if bytes.len() <= NONCE_LEN
+        {
+            return Err(failed());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: kid.as_bytes() })
+            .map_err(|_| failed())
+    }
+}
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SigningKeyError {
+    #[error("SIGNING_KEY_ENCRYPTION_KEY missing or invalid")]
+    MissingEncryptionKey,
+
+    #[error("Unsupported signing algorithm {0}")]
+    UnsupportedAlgorithm(String),
+
+    #[error("Signing key could not be generated")]
+    GenerationFailed,
+
+    #[error("Private key of {0} could not be decrypted")]
+    DecryptionFailed(String),
+
+    #[error("Token could not be signed")]
+    SigningFailed,
+
+    #[error("Signing key document isn't valid")]
+    DocumentError,
+
+    #[error("Mongo error: {0}")]
+    MongoError(#[from] mongodb::error::Error),
+}
@@ -1,11 +1,17 @@
 use serde::{Deserialize, Serialize};
 use crate::core::domain::user::user_error::UserError;
+use crate::core::domain::user::user_status::AccountStatus;
 use crate::core::domain::user::user_type::NewUser;
 use crate::data::access::user_repo::MongoUserRepo;
 use crate::utils::domains_ids::UserID;
 
 pub mod user_repo;
 pub mod user_type;
+pub mod user_deletion;
+pub mod user_privacy;
+pub mod user_query;
+pub mod user_status;
+pub mod user_transfer;
 
 pub mod user_error;
 
@@ -18,6 +24,8 @@ pub struct User
     pub username: String,
     pub email:    String,
     pub name:     String,
+    #[serde(default)]
+    pub status:   AccountStatus,
 }
 
 #[derive(Clone)]
@@ -38,6 +46,7 @@ impl <'a> UserEntity<'a>
                username: new_user.username,
                email:    new_user.email,
                name:     new_user.name,
+               status:   AccountStatus::Active,
            }
        }
    }
@@ -0,0 +1,113 @@
+use mongodb::bson::{doc, DateTime, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::user::User;
+use crate::core::domain::user::user_error::UserError;
+use crate::utils::env::env_or;
+
+/// How long soft-deleted accounts stay restorable and how often the purge job runs.
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy
+{
+    pub retention_secs:      i64,
+    pub purge_interval_secs: u64,
+}
+
+impl RetentionPolicy
+{
+    pub fn from_env() -> Self
+    {
+        Self { retention_secs:      env_or("USER_RETENTION_DAYS", 30_i64).saturating_mul(24 * 60 * 60),
+               purge_interval_secs: env_or("USER_PURGE_INTERVAL_SECS", 3600), }
+    }
+
+    /// Accounts deleted before this instant are past the window.
+    pub fn cutoff(&self, now: DateTime) -> DateTime
+    {
+        DateTime::from_millis(now.timestamp_millis() - self.retention_secs.saturating_mul(1000))
+    }
+}
+
+/// Username and email a soft-deleted account held. While it is deleted the stored ones are
+/// replaced by its `tombstone`, so the unique indexes let them be registered again; a restore
+/// puts them back as long as nobody did.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeletedIdentity
+{
+    pub username: String,
+    pub email:    String,
+}
+
+/// A soft-deleted user as stored: the regular fields, with tombstoned identifiers, plus the
+/// deletion marker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeletedUser
+{
+    #[serde(flatten)]
+    pub user:             User,
+    pub deleted_at:       DateTime,
+    pub deleted_by:       Option<String>,
+    pub deleted_identity: DeletedIdentity,
+}
+
+/// Stands in for the username and email of a deleted document. It is unique per document and
+/// neither a valid username nor a valid email, so it never blocks a registration.
+pub fn tombstone(id: &str) -> String
+{
+    format!("@deleted:{}", id)
+}
+
+/// Pipeline update that soft-deletes a user or auth document, moving its identifiers aside.
+pub fn soft_delete_update(tombstone: &str, now: DateTime, deleted_by: Option<String>) -> Vec<Document>
+{
+    vec![doc! {
+        "$set": {
+            "deleted_at": now,
+            "deleted_by": { "$literal": deleted_by },
+            "deleted_identity": { "username": "$username", "email": "$email" },
+            "username": { "$literal": tombstone },
+            "email": { "$literal": tombstone },
+        }
+    }]
+}
+
+/// Pipeline update that undoes `soft_delete_update`.
+pub fn restore_update() -> Vec<Document>
+{
+    vec![doc! {
+             "$set": {
+                 "username": "$deleted_identity.username",
+                 "email": "$deleted_identity.email",
+             }
+         },
+         doc! { "$unset": ["deleted_at", "deleted_by", "deleted_identity"] }]
+}
+
+impl DeletedUser
+{
+    /// The user as it was before the deletion.
+    pub fn into_user(self) -> User
+    {
+        User { username: self.deleted_identity.username, email: self.deleted_identity.email, ..self.user }
+    }
+
+    /// Refuses the restore once the retention window is over, or when a live account took the
+    /// user's email or username after the deletion.
+    pub fn check_restorable(&self, policy: &RetentionPolicy, now: DateTime, email_taken: bool, username_taken: bool)
+                            -> Result<(), UserError>
+    {
+        if self.deleted_at < policy.cutoff(now)
+        {
+            return Err(UserError::RetentionExpired);
+        }
+        if email_taken
+        {
+            return Err(UserError::EmailIsUsed);
+        }
+        if username_taken
+        {
+            return Err(UserError::AlreadyExists);
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
 use thiserror::Error;
+
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::user::user_status::AccountStatus;
 pub type UserResult<T> = Result<T, UserError>;
 
 #[derive(Error, Debug)]
@@ -26,6 +29,9 @@ pub enum UserError {
     
     #[error("Incorrect Format Email")]
     IncorrectFormatEmail,
+
+    #[error("Invalid username")]
+    InvalidUsername,
     
     #[error("Email is used")]   
     EmailIsUsed,
@@ -49,6 +55,39 @@ pub enum UserError {
     AuthError,
     
     #[error("Not has permission")]
-    NotHasPermission
+    NotHasPermission,
+
+    #[error("Unsupported password hash algorithm: {0}")]
+    UnsupportedPasswordHash(String),
+
+    #[error("Password hash doesn't match the {0} format")]
+    InvalidPasswordHash(String),
+
+    #[error("Import record exceeds the maximum size")]
+    ImportRecordTooLarge,
+
+    #[error("Import body couldn't be read")]
+    ImportReadError,
+
+    #[error("Invalid import row: {0}")]
+    InvalidImportRow(String),
+
+    #[error("Export encoding error")]
+    ExportEncodingError,
+
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("Status change not allowed from {0}")]
+    InvalidStatusTransition(AccountStatus),
+
+    #[error("Retention period expired, user can't be restored")]
+    RetentionExpired,
+
+    #[error("{0}")]
+    LoginRejected(AuthError),
+
+    #[error("Invalid resource grant: {0}")]
+    InvalidResourceGrant(String),
 
 }
\ No newline at end of file
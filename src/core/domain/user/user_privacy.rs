@@ -0,0 +1,123 @@
+use serde::Serialize;
+
+use crate::core::domain::audit::audit_query::AuditRecord;
+use crate::core::domain::auth::auth_access_token::AccessTokenSummary;
+use crate::core::domain::auth::auth_lockout::LoginAttempt;
+use crate::core::domain::auth::auth_session::SessionSummary;
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::auth::auth_webauthn::PasskeySummary;
+use crate::core::domain::auth::Auth;
+use crate::core::domain::oauth::oauth_authorize::OAuthConsent;
+use crate::core::domain::user::user_status::AccountStatus;
+use crate::core::domain::user::User;
+use crate::utils::domains_ids::UserID;
+
+/// Everything stored about one user, as returned to a data subject access request. Secrets
+/// (password hash, TOTP seed, recovery code hashes, passkey public keys, token hashes) are left
+/// out.
+#[derive(Serialize, Debug, Clone)]
+pub struct DataExport
+{
+    pub generated_at:   i64,
+    pub user:           User,
+    pub account:        Option<AccountExport>,
+    pub login_attempts: Vec<LoginAttemptExport>,
+    pub sessions:       Vec<SessionSummary>,
+    pub access_tokens:  Vec<AccessTokenSummary>,
+    pub oauth_consents: Vec<ConsentExport>,
+    /// Audit entries the user acted in or was the target of.
+    pub audit_entries:  Vec<AuditRecord>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AccountExport
+{
+    pub username:            String,
+    pub email:               String,
+    pub role:                Role,
+    pub permissions:         Vec<u32>,
+    pub status:              AccountStatus,
+    pub suspension_reason:   Option<String>,
+    pub suspended_until:     Option<i64>,
+    pub two_factor_enabled:  bool,
+    pub recovery_codes_left: usize,
+    pub passkeys:            Vec<PasskeySummary>,
+}
+
+impl From<&Auth> for AccountExport
+{
+    fn from(auth: &Auth) -> Self
+    {
+        Self { username:            auth.username.clone(),
+               email:               auth.email.clone(),
+               role:                auth.roles.clone(),
+               permissions:         auth.permissions.clone(),
+               status:              auth.status,
+               suspension_reason:   auth.suspension.as_ref().map(|suspension| suspension.reason.clone()),
+               suspended_until:     auth.suspension
+                                        .as_ref()
+                                        .and_then(|suspension| suspension.until)
+                                        .map(|until| until.timestamp_millis()),
+               two_factor_enabled:  auth.two_factor.as_ref().is_some_and(|two_factor| two_factor.enabled),
+               recovery_codes_left: auth.two_factor.as_ref().map_or(0, |two_factor| two_factor.recovery_codes.len()),
+               passkeys:            auth.passkeys.iter().map(PasskeySummary::from).collect(), }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LoginAttemptExport
+{
+    pub key:             String,
+    pub failures:        u32,
+    pub last_failure_at: i64,
+    pub locked_until:    Option<i64>,
+}
+
+impl From<LoginAttempt> for LoginAttemptExport
+{
+    fn from(attempt: LoginAttempt) -> Self
+    {
+        Self { key:             attempt.key,
+               failures:        attempt.failures,
+               last_failure_at: attempt.last_failure_at.timestamp_millis(),
+               locked_until:    attempt.locked_until.map(|until| until.timestamp_millis()), }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ConsentExport
+{
+    pub client_id:  String,
+    pub scopes:     Vec<u32>,
+    pub granted_at: i64,
+}
+
+impl From<OAuthConsent> for ConsentExport
+{
+    fn from(consent: OAuthConsent) -> Self
+    {
+        Self { client_id:  consent.client_id,
+               scopes:     consent.scopes,
+               granted_at: consent.granted_at.timestamp_millis(), }
+    }
+}
+
+/// Placeholder identifiers written over the PII of an erased user. They are derived from the id
+/// so references from audit records still resolve to the same, now anonymous, account.
+pub struct ErasedIdentity
+{
+    pub username: String,
+    pub email:    String,
+    pub name:     String,
+}
+
+impl ErasedIdentity
+{
+    pub fn for_user(user_id: &UserID) -> Self
+    {
+        let id = user_id.value().to_hex();
+        Self { username: format!("erased-{}", id),
+               email:    format!("erased-{}@erased.invalid", id),
+               name:     String::new(), }
+    }
+}
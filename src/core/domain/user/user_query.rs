@@ -0,0 +1,265 @@
+use data_encoding::BASE64URL_NOPAD;
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::user::user_error::UserError;
+use crate::core::domain::user::user_status::AccountStatus;
+use crate::utils::domains_ids::UserID;
+use crate::utils::identifiers::regex_escape;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 200;
+pub const DEFAULT_SEARCH_LIMIT: u32 = 20;
+pub const MAX_SEARCH_LIMIT: u32 = 100;
+
+/// Fields a listing can return besides `_id`; `role` comes from the `auth` collection.
+pub const USER_LIST_FIELDS: [&str; 5] = ["username", "email", "name", "status", "role"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserSortField
+{
+    #[default]
+    Id,
+    Username,
+    Email,
+    Name,
+}
+
+impl UserSortField
+{
+    pub fn field(&self) -> &'static str
+    {
+        match self
+        {
+            UserSortField::Id => "_id",
+            UserSortField::Username => "username",
+            UserSortField::Email => "email",
+            UserSortField::Name => "name",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder
+{
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query string of `GET /api/users/all`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserListQuery
+{
+    pub cursor:          Option<String>,
+    pub limit:           Option<u32>,
+    pub username_prefix: Option<String>,
+    pub email_prefix:    Option<String>,
+    pub role:            Option<Role>,
+    pub status:          Option<AccountStatus>,
+    #[serde(default)]
+    pub sort:            UserSortField,
+    #[serde(default)]
+    pub order:           SortOrder,
+    /// Comma separated subset of `USER_LIST_FIELDS`; all of them when absent.
+    pub fields:          Option<String>,
+    #[serde(default)]
+    pub include_total:   bool,
+}
+
+impl UserListQuery
+{
+    pub fn page_size(&self) -> u32
+    {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn projected_fields(&self) -> Result<Vec<&'static str>, UserError>
+    {
+        let Some(fields) = &self.fields else { return Ok(USER_LIST_FIELDS.to_vec()) };
+        fields.split(',')
+              .map(str::trim)
+              .filter(|field| !field.is_empty())
+              .map(|field| {
+                  USER_LIST_FIELDS.iter()
+                                  .find(|known| **known == field)
+                                  .copied()
+                                  .ok_or_else(|| UserError::InvalidQuery(format!("unknown field {}", field)))
+              })
+              .collect()
+    }
+
+    /// Whether the `auth` collection has to be joined in.
+    pub fn needs_role(&self, fields: &[&str]) -> bool
+    {
+        self.role.is_some() || fields.contains(&"role")
+    }
+
+    /// Filters on the `users` collection itself; the role filter is applied after the join.
+    pub fn user_filter(&self) -> Document
+    {
+        let mut filter = Document::new();
+        if let Some(prefix) = &self.username_prefix
+        {
+            filter.insert("username", prefix_regex(&prefix.trim().to_lowercase()));
+        }
+        if let Some(prefix) = &self.email_prefix
+        {
+            filter.insert("email", prefix_regex(&prefix.trim().to_lowercase()));
+        }
+        if let Some(status) = &self.status
+        {
+            filter.insert("status", status.to_string());
+        }
+        filter
+    }
+
+    /// Keyset condition that resumes right after `cursor` in the requested order.
+    pub fn cursor_filter(&self, cursor: &PageCursor) -> Document
+    {
+        let op = match self.order
+        {
+            SortOrder::Asc => "$gt",
+            SortOrder::Desc => "$lt",
+        };
+        match (&self.sort, &cursor.value)
+        {
+            (UserSortField::Id, _) | (_, None) => doc! { "_id": { op: cursor.id } },
+            (sort, Some(value)) => doc! { "$or": [
+                { sort.field(): { op: value } },
+                { sort.field(): value, "_id": { op: cursor.id } },
+            ] },
+        }
+    }
+
+    pub fn sort(&self) -> Document
+    {
+        let direction = match self.order
+        {
+            SortOrder::Asc => 1,
+            SortOrder::Desc => -1,
+        };
+        let mut sort = doc! { self.sort.field(): direction };
+        if self.sort != UserSortField::Id
+        {
+            sort.insert("_id", direction);
+        }
+        sort
+    }
+}
+
+/// Anchored, escaped regex so a prefix match can still use the field's index.
+fn prefix_regex(prefix: &str) -> Bson
+{
+    Bson::RegularExpression(Regex { pattern: format!("^{}", regex_escape(prefix)), options: String::new() })
+}
+
+/// Users whose username, email or name starts with `terms`, ignoring case, leaving out those
+/// already found by the text search.
+pub fn search_prefix_filter(terms: &str, found: Vec<Bson>) -> Document
+{
+    let prefix = Regex { pattern: format!("^{}", regex_escape(terms)), options: "i".to_string() };
+    doc! {
+        "_id": { "$nin": found },
+        "$or": [{ "username": prefix.clone() }, { "email": prefix.clone() }, { "name": prefix }],
+    }
+}
+
+/// Position after the last item of a page: its `_id` plus the sort key when sorting by another field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageCursor
+{
+    pub id:    ObjectId,
+    pub value: Option<String>,
+}
+
+impl PageCursor
+{
+    pub fn encode(&self) -> String
+    {
+        let json = serde_json::json!({ "id": self.id.to_hex(), "value": self.value });
+        BASE64URL_NOPAD.encode(json.to_string().as_bytes())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, UserError>
+    {
+        #[derive(Deserialize)]
+        struct RawCursor
+        {
+            id:    String,
+            value: Option<String>,
+        }
+
+        let invalid = || UserError::InvalidQuery("invalid cursor".to_string());
+        let bytes = BASE64URL_NOPAD.decode(cursor.as_bytes()).map_err(|_| invalid())?;
+        let raw: RawCursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+        Ok(Self { id: ObjectId::parse_str(&raw.id).map_err(|_| invalid())?, value: raw.value })
+    }
+}
+
+/// One user in a listing; fields left out by the projection are omitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserListItem
+{
+    pub _id:      UserID,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email:    Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name:     Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status:   Option<AccountStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role:     Option<Role>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserPage
+{
+    pub items:       Vec<UserListItem>,
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total:       Option<u64>,
+}
+
+/// Query string of `GET /api/users/search`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserSearchQuery
+{
+    pub q:     String,
+    pub limit: Option<u32>,
+}
+
+impl UserSearchQuery
+{
+    pub fn limit(&self) -> u32
+    {
+        self.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT)
+    }
+
+    /// Trimmed search text; an empty one is rejected rather than matching nothing.
+    pub fn terms(&self) -> Result<&str, UserError>
+    {
+        let terms = self.q.trim();
+        if terms.is_empty()
+        {
+            return Err(UserError::InvalidQuery("empty search".to_string()));
+        }
+        Ok(terms)
+    }
+}
+
+/// A search result with its text relevance; higher scores come first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserSearchHit
+{
+    pub _id:      UserID,
+    pub username: String,
+    pub email:    String,
+    pub name:     String,
+    pub score:    f64,
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::user::user_error::UserError;
+
+/// Lifecycle of an account. Stored as `"Active"`, `"Suspended"` or `"Deactivated"` on both the
+/// `users` and `auth` documents.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AccountStatus
+{
+    #[default]
+    Active,
+    Suspended,
+    Deactivated,
+}
+
+impl fmt::Display for AccountStatus
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", match self
+        {
+            AccountStatus::Active => "Active",
+            AccountStatus::Suspended => "Suspended",
+            AccountStatus::Deactivated => "Deactivated",
+        })
+    }
+}
+
+/// Why and until when an account is suspended; kept on the `auth` document only.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Suspension
+{
+    pub reason:       String,
+    /// `None` suspends until an admin activates the account again.
+    pub until:        Option<DateTime>,
+    pub suspended_at: DateTime,
+}
+
+impl Suspension
+{
+    pub fn is_over(&self, now: DateTime) -> bool
+    {
+        self.until.is_some_and(|until| until <= now)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StatusChange
+{
+    /// Lifts a suspension.
+    Activate,
+    Suspend { reason: String, until: Option<DateTime> },
+    Deactivate,
+    /// Brings back a deactivated account.
+    Reactivate,
+}
+
+impl AccountStatus
+{
+    pub fn apply(&self, change: &StatusChange) -> Result<AccountStatus, UserError>
+    {
+        match (self, change)
+        {
+            (AccountStatus::Suspended, StatusChange::Activate) => Ok(AccountStatus::Active),
+            (AccountStatus::Active | AccountStatus::Suspended, StatusChange::Suspend { .. }) => Ok(AccountStatus::Suspended),
+            (AccountStatus::Active | AccountStatus::Suspended, StatusChange::Deactivate) => Ok(AccountStatus::Deactivated),
+            (AccountStatus::Deactivated, StatusChange::Reactivate) => Ok(AccountStatus::Active),
+            _ => Err(UserError::InvalidStatusTransition(*self)),
+        }
+    }
+}
+
+impl StatusChange
+{
+    pub fn suspension(&self) -> Option<Suspension>
+    {
+        match self
+        {
+            StatusChange::Suspend { reason, until } => {
+                Some(Suspension { reason: reason.clone(), until: *until, suspended_at: DateTime::now() })
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SuspendRequest
+{
+    pub reason: String,
+    /// Unix seconds; omitted for an open-ended suspension.
+    pub until:  Option<i64>,
+}
+
+impl From<SuspendRequest> for StatusChange
+{
+    fn from(request: SuspendRequest) -> Self
+    {
+        StatusChange::Suspend { reason: request.reason,
+                                until:  request.until.map(|secs| DateTime::from_millis(secs.saturating_mul(1000))), }
+    }
+}
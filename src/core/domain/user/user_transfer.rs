@@ -0,0 +1,201 @@
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::user::user_error::UserError;
+
+/// Longest record the importer buffers; guards against a body without line breaks.
+pub const MAX_RECORD_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat
+{
+    Csv,
+    Ndjson,
+}
+
+impl TransferFormat
+{
+    /// Falls back to NDJSON for anything that isn't `text/csv`.
+    pub fn from_content_type(content_type: &str) -> Self
+    {
+        if content_type.trim_start().starts_with("text/csv")
+        {
+            TransferFormat::Csv
+        }
+        else
+        {
+            TransferFormat::Ndjson
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str
+    {
+        match self
+        {
+            TransferFormat::Csv => "text/csv; charset=utf-8",
+            TransferFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery
+{
+    pub format:  Option<TransferFormat>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery
+{
+    pub format: Option<TransferFormat>,
+}
+
+/// One row of a bulk import. CSV files need a header naming these columns; `role` may be empty.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportUser
+{
+    pub username: String,
+    pub email:    String,
+    pub name:     String,
+    pub password: String,
+    #[serde(default)]
+    pub role:     Option<Role>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedUser
+{
+    pub id:       String,
+    pub username: String,
+    pub email:    String,
+    pub name:     String,
+    pub role:     Option<Role>,
+}
+
+/// Cuts a byte stream into records as chunks arrive. Line breaks inside quoted CSV fields don't
+/// end a record.
+pub struct RecordSplitter
+{
+    format:    TransferFormat,
+    buffer:    Vec<u8>,
+    in_quotes: bool,
+}
+
+impl RecordSplitter
+{
+    pub fn new(format: TransferFormat) -> Self
+    {
+        Self { format, buffer: Vec::new(), in_quotes: false }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>, UserError>
+    {
+        let mut records = Vec::new();
+        for &byte in chunk
+        {
+            if self.format == TransferFormat::Csv && byte == b'"'
+            {
+                self.in_quotes = !self.in_quotes;
+            }
+            if byte == b'\n' && !self.in_quotes
+            {
+                records.push(std::mem::take(&mut self.buffer));
+                continue;
+            }
+            self.buffer.push(byte);
+            if self.buffer.len() > MAX_RECORD_BYTES
+            {
+                return Err(UserError::ImportRecordTooLarge);
+            }
+        }
+        Ok(records)
+    }
+
+    /// The last record when the body doesn't end with a line break.
+    pub fn finish(self) -> Option<Vec<u8>>
+    {
+        (!self.buffer.is_empty()).then_some(self.buffer)
+    }
+}
+
+/// Turns records into rows. For CSV the first non-blank record is taken as the header.
+pub struct RecordParser
+{
+    format:  TransferFormat,
+    headers: Option<StringRecord>,
+}
+
+impl RecordParser
+{
+    pub fn new(format: TransferFormat) -> Self
+    {
+        Self { format, headers: None }
+    }
+
+    /// `None` for blank records and the CSV header.
+    pub fn parse(&mut self, record: &[u8]) -> Option<Result<ImportUser, String>>
+    {
+        let record = record.strip_suffix(b"\r").unwrap_or(record);
+        if record.iter().all(u8::is_ascii_whitespace)
+        {
+            return None;
+        }
+
+        match self.format
+        {
+            TransferFormat::Ndjson => Some(serde_json::from_slice(record).map_err(|err| err.to_string())),
+            TransferFormat::Csv => {
+                let fields = match Self::csv_fields(record)
+                {
+                    Ok(fields) => fields,
+                    Err(err) => return Some(Err(err)),
+                };
+                match &self.headers
+                {
+                    None => {
+                        self.headers = Some(fields.iter().map(str::trim).collect());
+                        None
+                    },
+                    Some(headers) => Some(fields.deserialize(Some(headers)).map_err(|err| err.to_string())),
+                }
+            },
+        }
+    }
+
+    fn csv_fields(record: &[u8]) -> Result<StringRecord, String>
+    {
+        ReaderBuilder::new().has_headers(false)
+                            .from_reader(record)
+                            .records()
+                            .next()
+                            .unwrap_or_else(|| Ok(StringRecord::new()))
+                            .map_err(|err| err.to_string())
+    }
+}
+
+pub fn csv_header() -> &'static [u8]
+{
+    b"id,username,email,name,role\n"
+}
+
+/// One exported user as a CSV line or an NDJSON line, trailing line break included.
+pub fn encode(format: TransferFormat, user: &ExportedUser) -> Result<Vec<u8>, UserError>
+{
+    match format
+    {
+        TransferFormat::Ndjson => {
+            let mut line = serde_json::to_vec(user).map_err(|_| UserError::ExportEncodingError)?;
+            line.push(b'\n');
+            Ok(line)
+        },
+        TransferFormat::Csv => {
+            let mut writer = WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+            writer.serialize(user).map_err(|_| UserError::ExportEncodingError)?;
+            writer.into_inner().map_err(|_| UserError::ExportEncodingError)
+        },
+    }
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::core::domain::auth::auth_type::Role;
+use crate::utils::domains_ids::UserID;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NewUser
 {
@@ -15,4 +18,59 @@ pub struct UserData {
     pub email: String,
     pub password: String,
     pub name: String,
-}
\ No newline at end of file
+}
+
+/// Account exported from another system, password hash included as stored there.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LegacyUser
+{
+    pub username:      String,
+    pub email:         String,
+    pub name:          String,
+    pub password_hash: String,
+    /// `pbkdf2_sha256`, `sha512_crypt`, `md5`, `bcrypt` or `argon2id`.
+    pub algorithm:     String,
+    /// Only needed when the source keeps the salt apart from the hash (salted MD5).
+    #[serde(default)]
+    pub salt:          Option<String>,
+    #[serde(default)]
+    pub role:          Option<Role>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRowResult
+{
+    pub row:      usize,
+    pub username: String,
+    pub user_id:  Option<UserID>,
+    pub error:    Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport
+{
+    /// Nothing was written; `imported` counts the rows that would have been.
+    pub dry_run:  bool,
+    pub imported: usize,
+    pub failed:   usize,
+    pub rows:     Vec<ImportRowResult>,
+}
+
+impl ImportReport
+{
+    pub fn push<E: ToString>(&mut self, row: usize, username: String, result: Result<Option<UserID>, E>)
+    {
+        let (user_id, error) = match result
+        {
+            Ok(user_id) => {
+                self.imported += 1;
+                (user_id, None)
+            },
+            Err(err) => {
+                self.failed += 1;
+                (None, Some(err.to_string()))
+            },
+        };
+        self.rows.push(ImportRowResult { row, username, user_id, error });
+    }
+}
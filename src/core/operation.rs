@@ -1,5 +1,20 @@
+pub mod access_token_ops;
+pub mod audit_ops;
 pub mod auth_ops;
+pub mod authz_ops;
 pub mod perms_ops;
 pub mod user_ops;
 pub mod catalogs_ops;
+pub mod introspection_ops;
+pub mod oauth_client_ops;
+pub mod oauth_ops;
+pub mod oidc_ops;
+pub mod passkey_ops;
+pub mod privacy_ops;
+pub mod retention_ops;
+pub mod service_account_ops;
+pub mod session_ops;
+pub mod signing_key_ops;
+pub mod token_ops;
+pub mod two_factor_ops;
 
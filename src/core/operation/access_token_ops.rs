@@ -0,0 +1,127 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::auth::auth_access_token::{
+    AccessToken,
+    AccessTokenSummary,
+    CreateAccessToken,
+    CreatedAccessToken,
+    RevokeAccessToken,
+    expiry_from_secs,
+};
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_session::{touch_cutoff, touch_interval_secs};
+use crate::core::domain::auth::auth_type::AccountProof;
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::auth_ops::AuthOps;
+use crate::data::access::auth_repo::MongoAuthRepo;
+use crate::utils::secrets::sha256_hex;
+
+const MAX_NAME_LEN: usize = 100;
+/// Method recorded on failed proofs at the access token endpoints.
+const ACCESS_TOKEN_MANAGEMENT: &str = "access_tokens";
+
+pub struct AccessTokenOps<'a>
+{
+    repo:     &'a MongoAuthRepo,
+    auth_ops: AuthOps<'a>,
+    context:  &'a Context,
+}
+
+impl<'a> AccessTokenOps<'a>
+{
+    pub fn new(repo: &'a MongoAuthRepo, context: &'a Context) -> Self
+    {
+        Self { repo, auth_ops: AuthOps::new(repo, context), context }
+    }
+
+    /// Scopes must be permissions the owner holds right now.
+    pub async fn create(&self, request: CreateAccessToken, origin: AuditOrigin) -> Result<CreatedAccessToken, AuthError>
+    {
+        let auth = self.auth_ops.verify_owner(request.proof, ACCESS_TOKEN_MANAGEMENT, &origin).await?;
+        let auth_id = auth._id.clone().ok_or(AuthError::AuthNotFound)?;
+
+        let name = request.name.trim().to_string();
+        if name.is_empty() || name.len() > MAX_NAME_LEN
+        {
+            return Err(AuthError::InvalidAccessToken(format!("name must be 1 to {} characters", MAX_NAME_LEN)));
+        }
+        if request.scopes.is_empty()
+        {
+            return Err(AuthError::InvalidAccessToken("at least one scope is required".to_string()));
+        }
+        if let Some(scope) = request.scopes.iter().find(|scope| !auth.permissions.contains(scope))
+        {
+            return Err(AuthError::InvalidAccessToken(format!("scope {} is not held by the account", scope)));
+        }
+        let expires_at = request.expires_at
+                                .map(|secs| expiry_from_secs(secs, DateTime::now()))
+                                .transpose()?;
+
+        let mut scopes = request.scopes;
+        scopes.sort_unstable();
+        scopes.dedup();
+        let (access_token, token) = AccessToken::issue(auth_id, auth.user_id.clone(), name, scopes, expires_at);
+        let access_token = self.context.access_token_repo.create(access_token).await?;
+
+        let summary = AccessTokenSummary::from(&access_token);
+        let entry = AuditEntry::new(AuditAction::AccessTokenCreated, &origin).with_actor(&auth.user_id)
+                                                                             .with_target(&auth.user_id)
+                                                                             .with_detail(format!("token {} ({})", summary.id, summary.name));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(CreatedAccessToken { token, summary })
+    }
+
+    pub async fn list(&self, proof: AccountProof, origin: AuditOrigin) -> Result<Vec<AccessTokenSummary>, AuthError>
+    {
+        let auth = self.auth_ops.verify_owner(proof, ACCESS_TOKEN_MANAGEMENT, &origin).await?;
+        let auth_id = auth._id.ok_or(AuthError::AuthNotFound)?;
+        let access_tokens = self.context.access_token_repo.fetch_by_auth_id(auth_id).await?;
+        Ok(access_tokens.iter().map(AccessTokenSummary::from).collect())
+    }
+
+    pub async fn revoke(&self, request: RevokeAccessToken, origin: AuditOrigin) -> Result<(), AuthError>
+    {
+        let auth = self.auth_ops.verify_owner(request.proof, ACCESS_TOKEN_MANAGEMENT, &origin).await?;
+        let auth_id = auth._id.clone().ok_or(AuthError::AuthNotFound)?;
+        let token_id = ObjectId::parse_str(&request.token_id).map_err(|_| AuthError::AccessTokenNotFound)?;
+
+        if !self.context.access_token_repo.revoke(token_id, auth_id).await?
+        {
+            return Err(AuthError::AccessTokenNotFound);
+        }
+
+        let entry = AuditEntry::new(AuditAction::AccessTokenRevoked, &origin).with_actor(&auth.user_id)
+                                                                             .with_target(&auth.user_id)
+                                                                             .with_detail(format!("token {}", token_id.to_hex()));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(())
+    }
+
+    /// Trades a personal access token for a login token carrying only the token's effective
+    /// permissions, so `TokenOps::has_permission` enforces the scopes. Called by
+    /// `session_guard`; the login token only lives for the request and never reaches the client.
+    pub async fn exchange(&self, token: &str) -> Result<String, AuthError>
+    {
+        let now = DateTime::now();
+        let access_token = self.context
+                               .access_token_repo
+                               .fetch_by_token_hash(&sha256_hex(token))
+                               .await?;
+        access_token.ensure_usable(now)?;
+
+        let mut auth = self.repo.fetch_by_id(access_token.auth_id.clone()).await?;
+        auth.ensure_active(now)?;
+        auth.permissions = access_token.effective_permissions(&auth.permissions);
+
+        let issued = self.auth_ops.issue_token(&auth).await?;
+        let interval_secs = touch_interval_secs();
+        if let Some(id) = access_token._id.filter(|_| access_token.needs_touch(now, interval_secs))
+        {
+            self.context.access_token_repo.touch(id, now, touch_cutoff(now, interval_secs)).await?;
+        }
+        Ok(issued.token)
+    }
+}
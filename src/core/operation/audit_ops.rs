@@ -0,0 +1,137 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::HttpRequest;
+use futures_util::StreamExt;
+use mongodb::bson::DateTime;
+use tracing::{error, info};
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_chain::{BrokenLink, ChainReport, ChainVerifier, Checkpoint, CheckpointSigner, CheckpointVerifier};
+use crate::core::domain::audit::audit_error::AuditError;
+use crate::core::domain::audit::audit_query::{AuditPage, AuditQuery};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::perm::perm_cat::READ_AUDIT;
+use crate::core::operation::token_ops::TokenOps;
+use crate::utils::env::env_or;
+
+pub struct AuditOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> AuditOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    /// Appends the entry to `audit_log` and mirrors it on the `audit` log target. A failed write
+    /// is logged but never fails the operation being audited.
+    pub async fn record(&self, entry: AuditEntry)
+    {
+        info!(target: "audit", "{} by {:?} on {:?}", entry.action, entry.actor, entry.target);
+        if let Err(err) = self.context.audit_repo.append(&entry).await
+        {
+            error!(target: "audit", "audit entry {} could not be stored: {}", entry.action, err);
+        }
+    }
+
+    pub async fn load_entries(&self, req: HttpRequest, query: AuditQuery) -> Result<AuditPage, AuditError>
+    {
+        if !TokenOps::new(self.context).has_permission(&req, READ_AUDIT).await
+        {
+            return Err(AuditError::NotHasPermission);
+        }
+
+        self.context.audit_repo.fetch_page(&query).await
+    }
+
+    /// Walks the whole chain and reports the first entry that was altered, removed or inserted
+    /// outside the service, including checkpoints whose signature doesn't hold.
+    pub async fn verify_chain(&self, req: HttpRequest) -> Result<ChainReport, AuditError>
+    {
+        if !TokenOps::new(self.context).has_permission(&req, READ_AUDIT).await
+        {
+            return Err(AuditError::NotHasPermission);
+        }
+
+        let checkpoints = self.context.audit_checkpoint_repo.fetch_all().await?;
+        let mut forged = None;
+        let mut trusted = Vec::with_capacity(checkpoints.len());
+        if !checkpoints.is_empty()
+        {
+            let verifier = CheckpointVerifier::from_env().ok_or(AuditError::VerifyingKeyMissing)?;
+            for checkpoint in checkpoints
+            {
+                if verifier.verify(&checkpoint)
+                {
+                    trusted.push(checkpoint);
+                }
+                else if forged.is_none()
+                {
+                    forged = Some(BrokenLink { seq:      checkpoint.seq,
+                                               entry_id: None,
+                                               reason:   format!("checkpoint at seq {} has an invalid signature", checkpoint.seq), });
+                }
+            }
+        }
+
+        let mut verifier = ChainVerifier::new(trusted);
+        let mut broken = None;
+        let mut entries = self.context.audit_repo.stream_chain().await?;
+        while let Some(entry) = entries.next().await
+        {
+            if let Err(link) = verifier.push(&entry?)
+            {
+                broken = Some(link);
+                break;
+            }
+        }
+
+        let broken = [broken, forged].into_iter().flatten().min_by_key(|link| link.seq);
+        Ok(verifier.finish(broken))
+    }
+
+    /// Signs the current head unless the latest checkpoint already covers it.
+    pub async fn write_checkpoint(&self, signer: &CheckpointSigner) -> Result<Option<Checkpoint>, AuditError>
+    {
+        let Some(head) = self.context.audit_repo.fetch_head().await? else { return Ok(None) };
+        let latest = self.context.audit_checkpoint_repo.fetch_latest().await?;
+        if latest.is_some_and(|latest| latest.seq >= head.seq)
+        {
+            return Ok(None);
+        }
+
+        let checkpoint = signer.sign(head.seq, head.hash, DateTime::now());
+        self.context.audit_checkpoint_repo.create(&checkpoint).await?;
+        Ok(Some(checkpoint))
+    }
+}
+
+/// Signs a checkpoint every `AUDIT_CHECKPOINT_INTERVAL_SECS` for the life of the server. Without
+/// `AUDIT_SIGNING_KEY` the chain is still kept but no checkpoint is written.
+pub fn spawn_checkpoint_task(context: Arc<Context>)
+{
+    let Some(signer) = CheckpointSigner::from_env() else
+    {
+        error!(target: "audit", "AUDIT_SIGNING_KEY missing or invalid, audit checkpoints disabled");
+        return;
+    };
+    info!(target: "audit", "audit checkpoints signed with key {}, verified with AUDIT_VERIFYING_KEY={}", signer.key_id(), signer.public_key());
+    let interval_secs: u64 = env_or("AUDIT_CHECKPOINT_INTERVAL_SECS", 3600);
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(interval_secs.max(60)));
+        loop
+        {
+            interval.tick().await;
+            match AuditOps::new(&context).write_checkpoint(&signer).await
+            {
+                Ok(Some(checkpoint)) => info!(target: "audit", "audit checkpoint signed at seq {}", checkpoint.seq),
+                Ok(None) => {},
+                Err(err) => error!(target: "audit", "audit checkpoint failed: {}", err),
+            }
+        }
+    });
+}
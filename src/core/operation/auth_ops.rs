@@ -1,48 +1,424 @@
-use bcrypt::verify;
-use perms::Token;
+use actix_web::HttpRequest;
+use mongodb::bson::DateTime;
+use tracing::{error, warn};
 use crate::{
-    core::domain::auth::{auth_type::AuthLogin},
+    core::domain::auth::{auth_type::{AccountProof, AuthLogin, ChallengePurpose, LoginChallenge, LoginOutcome, PendingLogin, Token, TwoFactorLogin, UnlockRequest}},
 };
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
 use crate::core::domain::auth::{Auth, AuthEntity};
 use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_lockout::{LockoutPolicy, LoginAttemptKey};
+use crate::core::domain::auth::auth_password::Passwords;
+use crate::core::domain::auth::auth_totp::TwoFactor;
+use crate::core::domain::perm::perm_cat::UPDATE_USER_ADMINISTRATION;
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::authz_ops::AuthzOps;
+use crate::core::operation::session_ops::SessionOps;
+use crate::core::operation::token_ops::TokenOps;
 use crate::data::access::auth_repo::MongoAuthRepo;
+use crate::utils::identifiers::{new_username, normalize_email, normalize_identifier};
+use crate::utils::secrets::{random_token, sha256_hex};
+
+const LOGIN_CHALLENGE_SECS: i64 = 300;
 
 pub struct AuthOps<'a>
 {
-    repo: &'a MongoAuthRepo,
+    repo:    &'a MongoAuthRepo,
+    context: &'a Context,
 }
 
 impl<'a> AuthOps<'a>
 {
-    pub fn new(repo: &'a MongoAuthRepo) -> Self  {Self {repo}}
-    
-    pub async fn create_auth(&self, auth: Auth) -> Result<Auth, AuthError>
+    pub fn new(repo: &'a MongoAuthRepo, context: &'a Context) -> Self  {Self {repo, context}}
+
+    pub async fn create_auth(&self, mut auth: Auth) -> Result<Auth, AuthError>
     {
+        auth.username = new_username(&auth.username).ok_or(AuthError::InvalidUsername)?;
+        auth.email = normalize_email(&auth.email).ok_or(AuthError::IncorrectFormatEmail)?;
         if self.repo.fetch_by_username(auth.clone().username).await.is_ok() {
             return Err(AuthError::AlreadyUsernameExists)
         }
         if self.repo.fetch_by_email(auth.clone().email).await.is_ok() {
-            return Err(AuthError::AlreadyEmailExists)       
+            return Err(AuthError::AlreadyEmailExists)
         }
         let auth_entity = AuthEntity::new(auth.clone(), self.repo).await;
         let auth = auth_entity.create().await?;
         Ok(auth)
     }
-    
-    pub async fn do_login (&self, auth_login: AuthLogin ) -> Result<Token, AuthError>
+
+    pub async fn do_login (&self, auth_login: AuthLogin, origin: AuditOrigin) -> Result<LoginOutcome, AuthError>
+    {
+        let identifier = auth_login.username.clone();
+        let auth = match self.verify_credentials(auth_login, origin.ip.clone()).await
+        {
+            Ok(auth) => auth,
+            Err(err) => {
+                let auth = self.repo.fetch_by_identifier(&identifier).await.ok();
+                self.audit_login_failed(&origin, "password", auth.as_ref(), &err).await;
+                return Err(err);
+            }
+        };
+
+        if auth.two_factor.as_ref().is_some_and(|two_factor| two_factor.enabled)
+        {
+            let challenge = self.create_challenge(&auth, ChallengePurpose::TwoFactor).await?;
+            return Ok(LoginOutcome::TwoFactorRequired(challenge));
+        }
+        if self.context.two_factor_policy_repo.is_required(&auth.roles).await?
+        {
+            let err = AuthError::TwoFactorEnrollmentRequired;
+            self.audit_login_failed(&origin, "password", Some(&auth), &err).await;
+            return Err(err);
+        }
+
+        Ok(LoginOutcome::Token(self.complete_login(auth, "password", &origin).await?))
+    }
+
+    /// Second login step for accounts with 2FA: exchanges the challenge token plus a TOTP or
+    /// recovery code for the real token.
+    pub async fn do_login_two_factor(&self, login: TwoFactorLogin, origin: AuditOrigin) -> Result<Token, AuthError>
+    {
+        let pending = match self.context.login_challenge_repo.take(sha256_hex(&login.challenge_token)).await
+        {
+            Ok(pending) if pending.purpose == ChallengePurpose::TwoFactor => pending,
+            Ok(_) => {
+                let err = AuthError::ChallengeNotFound;
+                self.audit_login_failed(&origin, "totp", None, &err).await;
+                return Err(err);
+            }
+            Err(err) => {
+                self.audit_login_failed(&origin, "totp", None, &err).await;
+                return Err(err);
+            }
+        };
+        let auth = self.repo.fetch_by_id(pending.auth_id).await?;
+
+        if let Err(err) = self.verify_two_factor(&auth, &login.code, origin.ip.clone()).await
+        {
+            self.audit_login_failed(&origin, "totp", Some(&auth), &err).await;
+            return Err(err);
+        }
+        self.complete_login(auth, "totp", &origin).await
+    }
+
+    /// Password login for flows that can't hand out a challenge token, such as the OAuth authorize
+    /// step: an enrolled second factor has to come along with the password.
+    pub async fn verify_login(&self, auth_login: AuthLogin, code: Option<&str>, method: &str, origin: &AuditOrigin)
+                              -> Result<Auth, AuthError>
+    {
+        let identifier = auth_login.username.clone();
+        let auth = match self.verify_credentials(auth_login, origin.ip.clone()).await
+        {
+            Ok(auth) => auth,
+            Err(err) => {
+                let auth = self.repo.fetch_by_identifier(&identifier).await.ok();
+                self.audit_login_failed(origin, method, auth.as_ref(), &err).await;
+                return Err(err);
+            }
+        };
+
+        let checked = if auth.two_factor.as_ref().is_some_and(|two_factor| two_factor.enabled)
+        {
+            match code
+            {
+                Some(code) => self.verify_two_factor(&auth, code, origin.ip.clone()).await,
+                None => return Err(AuthError::TwoFactorRequired),
+            }
+        }
+        else if self.context.two_factor_policy_repo.is_required(&auth.roles).await?
+        {
+            Err(AuthError::TwoFactorEnrollmentRequired)
+        }
+        else
+        {
+            Ok(())
+        };
+
+        if let Err(err) = checked
+        {
+            self.audit_login_failed(origin, method, Some(&auth), &err).await;
+            return Err(err);
+        }
+        self.clear_attempts(&auth).await?;
+        Ok(auth)
+    }
+
+    /// Self-service changes to an account take the same proof as logging in to it, so a leaked
+    /// password alone can't add a way in or take anything out of an account with 2FA.
+    pub async fn verify_owner(&self, proof: AccountProof, method: &str, origin: &AuditOrigin) -> Result<Auth, AuthError>
+    {
+        let auth_login = AuthLogin { username: proof.username, password: proof.password };
+        self.verify_login(auth_login, proof.code.as_deref(), method, origin).await
+    }
+
+    /// A wrong code counts like a wrong password, under the account and the client address.
+    async fn verify_two_factor(&self, auth: &Auth, code: &str, client_ip: Option<String>) -> Result<(), AuthError>
+    {
+        auth.ensure_active(DateTime::now())?;
+
+        let keys = Self::attempt_keys(auth, client_ip);
+        self.check_attempts(&keys).await?;
+
+        let mut two_factor = auth.two_factor.clone().ok_or(AuthError::TwoFactorNotEnrolled)?;
+        if let Some(step) = two_factor.verify_code(code, DateTime::now().timestamp_millis() / 1000)
+        {
+            two_factor.last_used_step = Some(step);
+        }
+        else if !two_factor.consume_recovery_code(code)
+        {
+            self.register_failure(&keys, &LockoutPolicy::from_env()).await?;
+            return Err(AuthError::InvalidTwoFactorCode);
+        }
+
+        self.save_two_factor(auth, Some(two_factor)).await?;
+        Ok(())
+    }
+
+    pub fn attempt_keys(auth: &Auth, client_ip: Option<String>) -> Vec<LoginAttemptKey>
+    {
+        let mut keys = vec![LoginAttemptKey::Account(auth.username.clone())];
+        if let Some(ip) = client_ip
+        {
+            keys.push(LoginAttemptKey::Ip(ip));
+        }
+        keys
+    }
+
+    /// Clears the account counter once everything a login needs has checked out. Only the
+    /// account counter is cleared; the IP counter keeps throttling credential stuffing from a
+    /// client that also owns a valid account.
+    pub async fn clear_attempts(&self, auth: &Auth) -> Result<(), AuthError>
+    {
+        self.context.login_attempt_repo.reset(&LoginAttemptKey::Account(auth.username.clone())).await?;
+        Ok(())
+    }
+
+    /// Issues the token for a login that passed every check and opens its session.
+    pub async fn complete_login(&self, auth: Auth, method: &str, origin: &AuditOrigin) -> Result<Token, AuthError>
+    {
+        self.clear_attempts(&auth).await?;
+        let token = self.issue_token(&auth).await?;
+        SessionOps::new(self.context).start(&auth, &token, method, origin).await?;
+        self.audit_login_succeeded(origin, &auth, method).await;
+        Ok(token)
+    }
+
+    /// The account logging in is both actor and target.
+    async fn audit_login_succeeded(&self, origin: &AuditOrigin, auth: &Auth, method: &str)
+    {
+        let entry = AuditEntry::new(AuditAction::LoginSucceeded, origin).with_actor(&auth.user_id)
+                                                                        .with_target(&auth.user_id)
+                                                                        .with_detail(method);
+        AuditOps::new(self.context).record(entry).await;
+    }
+
+    /// `auth` is the account the attempt resolved to, if any. Only its id is recorded: the chain
+    /// can't be edited, so usernames and emails, typed or stored, stay out of it.
+    pub async fn audit_login_failed(&self, origin: &AuditOrigin, method: &str, auth: Option<&Auth>, err: &AuthError)
+    {
+        let mut entry = AuditEntry::new(AuditAction::LoginFailed, origin).with_detail(format!("{} ({})", method, err));
+        if let Some(auth) = auth
+        {
+            entry = entry.with_actor(&auth.user_id).with_target(&auth.user_id);
+        }
+        AuditOps::new(self.context).record(entry).await;
+    }
+
+    /// Checks the identifier (username or email) and password, applying the lockout and backoff
+    /// rules on failure. A correct password doesn't clear the counters: with a second factor
+    /// still to check, that would let the password reset the throttle on guessing codes. Callers
+    /// clear them with `clear_attempts` once the whole proof is in.
+    pub async fn verify_credentials(&self, auth_login: AuthLogin, client_ip: Option<String>) -> Result<Auth, AuthError>
     {
-        let auth = self.repo.fetch_by_username(auth_login.clone().username).await?;
+        let policy = LockoutPolicy::from_env();
+        let found = self.repo.fetch_by_identifier(&auth_login.username).await;
+
+        // Attempts are counted per account whichever identifier was used to reach it.
+        let account = match &found
+        {
+            Ok(auth) => auth.username.clone(),
+            Err(_) => normalize_identifier(&auth_login.username),
+        };
+        let mut keys = vec![LoginAttemptKey::Account(account)];
+        if let Some(ip) = client_ip
+        {
+            keys.push(LoginAttemptKey::Ip(ip));
+        }
+        self.check_attempts(&keys).await?;
 
-        let is_password_valid = verify(auth_login.password, &auth.password)
+        let auth = match found
+        {
+            Ok(auth) => auth,
+            Err(err) => {
+                self.register_failure(&keys, &policy).await?;
+                return Err(err);
+            }
+        };
+
+        let passwords = Passwords::from_env();
+        let is_password_valid = passwords.verify_async(&auth_login.password, &auth.password)
+            .await
             .map_err(|_| AuthError::IncorrectPassword)?;
 
         if !is_password_valid {
+            self.register_failure(&keys, &policy).await?;
             return Err(AuthError::IncorrectPassword);
         }
-        
-        let auth_perms: perms::Auth = auth.clone().into(); 
 
-        let token = Token::new(auth_perms).map_err(|_| AuthError::PermLibError)?;
-        Ok(token)
+        auth.ensure_active(DateTime::now())?;
+
+        if passwords.needs_rehash(&auth.password)
+        {
+            return Ok(self.rehash_password(auth, &auth_login.password, &passwords).await);
+        }
+        Ok(auth)
+    }
+
+    /// Moves the stored hash to the preferred scheme and parameters. The login already succeeded,
+    /// so a failure here is only logged.
+    async fn rehash_password(&self, auth: Auth, password: &str, passwords: &Passwords) -> Auth
+    {
+        let rehashed = match (passwords.hash_async(password).await, auth._id.clone())
+        {
+            (Ok(hash), Some(auth_id)) => {
+                let mut auth_entity = AuthEntity::new(auth.clone(), self.repo).await;
+                auth_entity.update_id(auth_id).await;
+                auth_entity.update_password(hash).await;
+                auth_entity.save().await
+            },
+            _ => Err(AuthError::HashPasswordError),
+        };
+
+        match rehashed
+        {
+            Ok(auth) => auth,
+            Err(err) => {
+                warn!("password rehash failed for {}: {}", auth.username, err);
+                auth
+            },
+        }
+    }
+
+    pub async fn save_two_factor(&self, auth: &Auth, two_factor: Option<TwoFactor>) -> Result<Auth, AuthError>
+    {
+        let auth_id = auth._id.clone().ok_or(AuthError::AuthNotFound)?;
+        let mut auth_entity = AuthEntity::new(auth.clone(), self.repo).await;
+        auth_entity.update_id(auth_id).await;
+        auth_entity.update_two_factor(two_factor).await;
+        auth_entity.save().await
+    }
+
+    /// Clearing an account's counter counts as administering that account, so grants and
+    /// policies on it apply.
+    pub async fn unlock(&self, req: HttpRequest, unlock: UnlockRequest) -> Result<(), AuthError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        let found = match &unlock.username
+        {
+            Some(identifier) => self.repo.fetch_by_identifier(identifier).await.ok(),
+            None => None,
+        };
+        let authz = AuthzOps::new(self.context);
+        let allowed = match &found
+        {
+            Some(auth) => authz.allows_on(req, UPDATE_USER_ADMINISTRATION, &auth.user_id).await,
+            None => authz.allows(req, UPDATE_USER_ADMINISTRATION, None).await,
+        };
+        if !allowed
+        {
+            return Err(AuthError::Unauthorized);
+        }
+
+        let mut keys = Vec::new();
+        if let Some(identifier) = unlock.username
+        {
+            // Same key `verify_credentials` counts under, whichever identifier is typed.
+            let account = match found
+            {
+                Some(auth) => auth.username,
+                None => normalize_identifier(&identifier),
+            };
+            keys.push(LoginAttemptKey::Account(account));
+        }
+        if let Some(ip) = unlock.ip
+        {
+            keys.push(LoginAttemptKey::Ip(ip));
+        }
+
+        for key in keys
+        {
+            if self.context.login_attempt_repo.reset(&key).await?
+            {
+                let entry = AuditEntry::new(AuditAction::LockoutCleared, &origin).with_target(key.as_key());
+                AuditOps::new(self.context).record(entry).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Login token carrying the account's role and permissions, signed with the active key.
+    pub async fn issue_token(&self, auth: &Auth) -> Result<Token, AuthError>
+    {
+        TokenOps::new(self.context).issue(&auth.token_claims())
+                                   .await
+                                   .map_err(|err| {
+                                       error!("login token for {} not signed: {}", auth.username, err);
+                                       AuthError::FailToCreateToken
+                                   })
+    }
+
+    pub async fn create_challenge(&self, auth: &Auth, purpose: ChallengePurpose) -> Result<LoginChallenge, AuthError>
+    {
+        let challenge_token = random_token(32);
+        let expires_at = DateTime::from_millis(DateTime::now().timestamp_millis() + LOGIN_CHALLENGE_SECS * 1000);
+        self.context
+            .login_challenge_repo
+            .create(PendingLogin {
+                _id: None,
+                token_hash: sha256_hex(&challenge_token),
+                auth_id: auth._id.clone().ok_or(AuthError::AuthNotFound)?,
+                expires_at,
+                purpose,
+            })
+            .await?;
+
+        Ok(LoginChallenge { challenge_token, expires_in: LOGIN_CHALLENGE_SECS })
+    }
+
+    pub async fn check_attempts(&self, keys: &[LoginAttemptKey]) -> Result<(), AuthError>
+    {
+        let now = DateTime::now();
+        for key in keys
+        {
+            if let Some(attempt) = self.context.login_attempt_repo.fetch(key).await?
+            {
+                if attempt.is_locked(now)
+                {
+                    return Err(AuthError::AccountLocked);
+                }
+                if attempt.is_throttled(now)
+                {
+                    return Err(AuthError::TooManyAttempts);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn register_failure(&self, keys: &[LoginAttemptKey], policy: &LockoutPolicy) -> Result<(), AuthError>
+    {
+        for key in keys
+        {
+            let attempt = self.context.login_attempt_repo.register_failure(key, policy).await?;
+            if attempt.failures >= policy.threshold(key)
+            {
+                warn!(target: "audit",
+                      "login lockout for {} after {} failed attempts until {:?}",
+                      key.as_key(), attempt.failures, attempt.locked_until);
+            }
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use actix_web::HttpRequest;
+use mongodb::bson::DateTime;
+use tracing::error;
+
+use crate::context::Context;
+use crate::core::domain::auth::auth_caller::bearer_token;
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::authz::authz_error::AuthzError;
+use crate::core::domain::authz::authz_policy::PolicySet;
+use crate::core::domain::authz::authz_resource::Resource;
+use crate::core::domain::authz::authz_type::{AuthzBatch, AuthzBatchResult, AuthzCheck, AuthzResult, MAX_BATCH_CHECKS};
+use crate::core::domain::authz::{decide, DecisionReason, Principal};
+use crate::core::domain::oauth::oauth_type::ClientCredentials;
+use crate::core::domain::perm::perm_error::PermError;
+use crate::core::domain::perm::perm_repo::PermRepo;
+use crate::core::domain::service_account::service_account_error::ServiceAccountError;
+use crate::core::domain::service_account::CLIENT_ID_PREFIX;
+use crate::core::operation::oauth_ops::OAuthOps;
+use crate::core::operation::token_ops::TokenOps;
+use crate::data::catalog_importer::MongoCatalogRepo;
+use crate::utils::domains_ids::UserID;
+use crate::utils::secrets::sha256_hex;
+
+/// Authorization decisions, for this service's own operations and for the other services
+/// through the decision API. Both go through `Principal::decide` on the stored state of the
+/// subject, so a permission taken away counts before the subject's tokens expire.
+pub struct AuthzOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> AuthzOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    /// Callers authenticate like at the introspection endpoint.
+    pub async fn check(&self, credentials: Option<ClientCredentials>, check: AuthzCheck) -> Result<AuthzResult, AuthzError>
+    {
+        OAuthOps::new(self.context).authenticate_resource_server(credentials).await?;
+        let principal = self.principal(&check.subject).await?;
+        let decision = decide(principal.as_ref(), check.permission, check.resource.as_ref(), &self.policies().await?);
+        Ok(AuthzResult::new(check, decision))
+    }
+
+    /// Each subject is loaded once however many checks name it.
+    pub async fn check_batch(&self, credentials: Option<ClientCredentials>, batch: AuthzBatch) -> Result<AuthzBatchResult, AuthzError>
+    {
+        if batch.checks.is_empty()
+        {
+            return Err(AuthzError::EmptyBatch);
+        }
+        if batch.checks.len() > MAX_BATCH_CHECKS
+        {
+            return Err(AuthzError::BatchTooLarge(MAX_BATCH_CHECKS));
+        }
+        OAuthOps::new(self.context).authenticate_resource_server(credentials).await?;
+
+        let policies = self.policies().await?;
+        let mut principals: HashMap<String, Option<Principal>> = HashMap::new();
+        let mut results = Vec::with_capacity(batch.checks.len());
+        for check in batch.checks
+        {
+            if !principals.contains_key(&check.subject)
+            {
+                let principal = self.principal(&check.subject).await?;
+                principals.insert(check.subject.clone(), principal);
+            }
+            let decision = decide(principals[&check.subject].as_ref(), check.permission, check.resource.as_ref(), &policies);
+            results.push(AuthzResult::new(check, decision));
+        }
+        Ok(AuthzBatchResult { results })
+    }
+
+    /// In-process check: the request's token has to carry `permission` and its owner has to
+    /// be allowed it now. Resource grants and policies aren't in tokens, so a token without the
+    /// permission still gets through when one of them allows it; the stored record of the token
+    /// is what proves it was issued here. Tokens issued to OAuth clients never go beyond their
+    /// scopes, and tokens without a record (exchanged personal access tokens) are judged on the
+    /// token alone. A lookup failure denies.
+    pub async fn allows(&self, req: HttpRequest, permission: u32, resource: Option<&Resource>) -> bool
+    {
+        let caller = self.caller(&req).await;
+        let carried = TokenOps::new(self.context).has_permission(&req, permission).await;
+        let caller = match caller
+        {
+            Ok(Some(caller)) => caller,
+            Ok(None) => return carried,
+            Err(err) => {
+                error!("authorization check failed: {}", err);
+                return false;
+            },
+        };
+        let policies = match self.policies().await
+        {
+            Ok(policies) => policies,
+            Err(err) => {
+                error!("authorization check failed: {}", err);
+                return false;
+            },
+        };
+        let decision = decide(Some(&caller.principal), permission, resource, &policies);
+        let beyond_token = matches!(decision.reason, DecisionReason::ScopedGrant | DecisionReason::AllowedByPolicy);
+        decision.is_allowed() && (carried || (beyond_token && !caller.delegated))
+    }
+
+    /// `allows` on one user: resource grants on the user count, and the user's role goes along
+    /// for policies to test.
+    pub async fn allows_on(&self, req: HttpRequest, permission: u32, user_id: &UserID) -> bool
+    {
+        let mut resource = Resource::user(user_id);
+        if let Ok(auth) = self.context.auth_repo.fetch_by_user_id(user_id.clone()).await
+        {
+            resource = resource.with_attribute("role", auth.roles.to_string());
+        }
+        self.allows(req, permission, Some(&resource)).await
+    }
+
+    /// The principal behind a user id or a service account client id.
+    async fn principal(&self, subject: &str) -> Result<Option<Principal>, AuthzError>
+    {
+        if subject.starts_with(CLIENT_ID_PREFIX)
+        {
+            return self.service_account(subject).await;
+        }
+        let Ok(user_id) = UserID::parse_str(subject) else { return Ok(None) };
+        let Ok(auth) = self.context.auth_repo.fetch_by_user_id(user_id).await else { return Ok(None) };
+        let role_permissions = self.role_permissions(&auth.roles).await?;
+        Ok(Some(Principal::user(&auth, role_permissions, DateTime::now())))
+    }
+
+    async fn service_account(&self, client_id: &str) -> Result<Option<Principal>, AuthzError>
+    {
+        let service_account = match self.context.service_account_repo.fetch_by_client_id(client_id).await
+        {
+            Ok(service_account) => service_account,
+            Err(ServiceAccountError::NotFound) => return Ok(None),
+            Err(_) => return Err(AuthzError::SubjectError),
+        };
+        let role_permissions = self.role_permissions(&service_account.role).await?;
+        Ok(Some(Principal::service_account(&service_account, role_permissions)))
+    }
+
+    /// The owner of the request's token, found through its session or its service account
+    /// token record.
+    async fn caller(&self, req: &HttpRequest) -> Result<Option<Caller>, AuthzError>
+    {
+        let Some(token) = bearer_token(req) else { return Ok(None) };
+        let token_hash = sha256_hex(&token);
+        let session = self.context
+                          .session_repo
+                          .fetch_by_token_hash(&token_hash)
+                          .await
+                          .map_err(|_| AuthzError::SubjectError)?;
+        if let Some(session) = session
+        {
+            let subject = session.user_id.value().to_hex();
+            if session.revoked_at.is_some()
+            {
+                return Ok(Some(Caller { principal: gone(subject), delegated: false }));
+            }
+            // A token whose account is gone can't vouch for anything.
+            let principal = self.principal(&subject).await?.unwrap_or_else(|| gone(subject));
+            let grant = self.context
+                            .oauth_refresh_token_repo
+                            .fetch_by_session_token_hash(&token_hash)
+                            .await
+                            .map_err(|_| AuthzError::SubjectError)?;
+            return Ok(Some(match grant
+            {
+                Some(refresh) => Caller { principal: principal.limited_to(&refresh.scopes), delegated: true },
+                None => Caller { principal, delegated: false },
+            }));
+        }
+        let record = self.context
+                         .service_account_token_repo
+                         .fetch_by_token_hash(&token_hash)
+                         .await
+                         .map_err(|_| AuthzError::SubjectError)?;
+        match record
+        {
+            Some(record) => {
+                let principal = self.service_account(&record.client_id).await?.unwrap_or_else(|| gone(record.client_id));
+                Ok(Some(Caller { principal, delegated: false }))
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn policies(&self) -> Result<PolicySet, AuthzError>
+    {
+        MongoCatalogRepo::new(self.context.client.as_ref().clone()).fetch_policies()
+                                                                   .await
+                                                                   .map_err(|_| AuthzError::PolicyError)
+    }
+
+    /// Permissions the catalog gives `role`; none when the role isn't in it.
+    async fn role_permissions(&self, role: &Role) -> Result<Vec<u32>, AuthzError>
+    {
+        match self.context.perm_repo.charge_permissions(role.to_string(), self.context).await
+        {
+            Ok(permissions) => Ok(permissions),
+            Err(PermError::PermNotFound) => Ok(Vec::new()),
+            Err(_) => Err(AuthzError::PermError),
+        }
+    }
+}
+
+/// The owner of a request's token. A delegated token was issued to an OAuth client and only
+/// carries the scopes the user granted it.
+struct Caller
+{
+    principal: Principal,
+    delegated: bool,
+}
+
+fn gone(subject: String) -> Principal
+{
+    Principal { subject,
+                role: Role::Visitor,
+                role_permissions: Vec::new(),
+                permissions: Vec::new(),
+                resource_grants: Vec::new(),
+                active: false }
+}
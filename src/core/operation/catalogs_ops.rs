@@ -1,4 +1,10 @@
+use serde_json::json;
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditDiff, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
 use crate::core::domain::auth::AuthEntity;
+use crate::core::operation::audit_ops::AuditOps;
 use crate::data::access::auth_repo::MongoAuthRepo;
 use crate::data::catalog_importer::MongoCatalogRepo;
 use crate::error::{ServiceError, ServiceResult};
@@ -8,50 +14,65 @@ pub struct CatalogsOps<'a>
 {
     repo: &'a MongoCatalogRepo,
     auth_repo: &'a MongoAuthRepo,
+    context: &'a Context,
 
 }
 impl <'a>CatalogsOps<'a>
 {
-    pub fn new(repo: &'a MongoCatalogRepo, auth_repo: &'a MongoAuthRepo
+    pub fn new(repo: &'a MongoCatalogRepo, auth_repo: &'a MongoAuthRepo, context: &'a Context
     ) -> Self
     {
-        Self {repo, auth_repo }
+        Self {repo, auth_repo, context }
     }
     
-    pub async fn sync_catalogs(&self) -> ServiceResult<()>
+    /// Both catalogs are read and checked before either is written, so a bad one leaves
+    /// everything as it was.
+    pub async fn sync_catalogs(&self, origin: AuditOrigin) -> ServiceResult<()>
     {
         let importer = self.repo;
-        
-        importer.import_perm_relationships().await?;
-        self.update_perms_in_users().await?;
-        println!("Import Catalog Permissions");
+        let relationships = importer.load_perm_relationships()?;
+        let policies = importer.load_policies()?;
+
+        let before = importer.fetch_perm_relationships().await.unwrap_or_default();
+        importer.import_perm_relationships(relationships).await?;
+        let after = importer.fetch_perm_relationships().await?;
+        let entry = AuditEntry::new(AuditAction::CatalogImported, &origin).with_target("relationship")
+                                                                          .with_diff(AuditDiff::between(&before, &after));
+        AuditOps::new(self.context).record(entry).await;
+
+        let policies_before = importer.fetch_policies().await.unwrap_or_default();
+        importer.import_policies(&policies).await?;
+        let policies_after = importer.fetch_policies().await?;
+        let diff = AuditDiff::between(&json!({ "policies": policies_before.policies() }), &json!({ "policies": policies_after.policies() }));
+        let entry = AuditEntry::new(AuditAction::CatalogImported, &origin).with_target("policies")
+                                                                          .with_diff(diff);
+        AuditOps::new(self.context).record(entry).await;
+
+        self.update_perms_in_users(&origin).await?;
         Ok(())
     }
     
-    async fn update_perms_in_users(&self) -> ServiceResult<()>
+    async fn update_perms_in_users(&self, origin: &AuditOrigin) -> ServiceResult<()>
     {
         let relationships = self.repo.fetch_perm_relationships().await?;
         let auths = self.auth_repo.fetch_all().await.map_err(|_| ServiceError::FetchUserError)?;
         for auth in auths
         {
             let mut auth_entity = AuthEntity::new(auth.clone(), self.auth_repo).await;
-            
-
-            if let Some(perms) = relationships.get(&auth.roles) {
-                if auth.permissions != *perms {
-                    auth_entity.update_id(auth._id.unwrap()).await;
-                    auth_entity.update_permissions(perms.clone()).await;
-                    auth_entity.save().await.map_err(|_| {
-                        ServiceError::UpdateUserError
-                    })?;
-                }
-            } else {
-                if !auth.permissions.is_empty() {
-                    auth_entity.update_permissions(Vec::new()).await;
-                    auth_entity.save().await.map_err(|_| {
-                        ServiceError::UpdateUserError
-                    })?;
-                }
+            let perms = relationships.get(&auth.roles).cloned().unwrap_or_default();
+
+            if auth.permissions != perms {
+                auth_entity.update_id(auth._id.clone().unwrap()).await;
+                auth_entity.update_permissions(perms.clone()).await;
+                auth_entity.save().await.map_err(|_| {
+                    ServiceError::UpdateUserError
+                })?;
+
+                let diff = AuditDiff::between(&json!({ "permissions": auth.permissions }), &json!({ "permissions": perms }));
+                let entry = AuditEntry::new(AuditAction::PermissionsChanged, origin).with_target(&auth.user_id)
+                                                                                    .with_diff(diff)
+                                                                                    .with_detail(format!("role {}", auth.roles));
+                AuditOps::new(self.context).record(entry).await;
             }
         }
         
@@ -59,4 +80,4 @@ impl <'a>CatalogsOps<'a>
     }
 
 
-}
\ No newline at end of file
+}
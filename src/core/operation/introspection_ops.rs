@@ -0,0 +1,218 @@
+use mongodb::bson::DateTime;
+use tracing::error;
+
+use crate::context::Context;
+use crate::core::domain::auth::auth_access_token::ACCESS_TOKEN_PREFIX;
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_introspection::{Introspection, IntrospectionRequest, TokenDetails};
+use crate::core::domain::auth::Auth;
+use crate::core::domain::oauth::oauth_error::OAuthError;
+use crate::core::domain::oauth::oauth_oidc::IdentityScopes;
+use crate::core::domain::oauth::oauth_refresh::REFRESH_TOKEN_PREFIX;
+use crate::core::domain::oauth::oauth_type::ClientCredentials;
+use crate::core::domain::oauth::{format_granted_scope, format_scope};
+use crate::core::operation::oauth_ops::OAuthOps;
+use crate::core::operation::service_account_ops::ServiceAccountOps;
+use crate::core::operation::token_ops::TokenOps;
+use crate::utils::domains_ids::AuthID;
+use crate::utils::env::env_or;
+use crate::utils::secrets::sha256_hex;
+
+const BEARER_TOKEN_TYPE: &str = "Bearer";
+const DEFAULT_CACHE_SECS: i64 = 30;
+
+/// Token introspection (RFC 7662) for the other services, so they don't have to check
+/// signatures and revocations themselves. Every answer is checked against the stored records:
+/// revoked sessions and tokens, suspended or deleted accounts and disabled service accounts
+/// all come back inactive.
+pub struct IntrospectionOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> IntrospectionOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    pub async fn introspect(&self, request: IntrospectionRequest, credentials: Option<ClientCredentials>)
+                            -> Result<Introspection, OAuthError>
+    {
+        OAuthOps::new(self.context).authenticate_resource_server(credentials).await?;
+
+        let now = DateTime::now();
+        let details = if request.token.starts_with(ACCESS_TOKEN_PREFIX)
+        {
+            self.personal_access_token(&request.token, now).await?
+        }
+        else if request.token.starts_with(REFRESH_TOKEN_PREFIX)
+        {
+            self.refresh_token(&request.token, now).await?
+        }
+        else
+        {
+            self.bearer_token(&request.token, now).await?
+        };
+        Ok(details.map_or_else(Introspection::inactive, Introspection::active))
+    }
+
+    /// Seconds the caller may cache `introspection` for, from `INTROSPECTION_CACHE_SECS`.
+    pub fn max_age(&self, introspection: &Introspection) -> i64
+    {
+        introspection.max_age(DateTime::now().timestamp_millis() / 1000, env_or("INTROSPECTION_CACHE_SECS", DEFAULT_CACHE_SECS))
+    }
+
+    async fn personal_access_token(&self, token: &str, now: DateTime) -> Result<Option<TokenDetails>, OAuthError>
+    {
+        let Some(access_token) = known(self.context.access_token_repo.fetch_by_token_hash(&sha256_hex(token)).await)?
+        else
+        {
+            return Ok(None);
+        };
+        if access_token.ensure_usable(now).is_err()
+        {
+            return Ok(None);
+        }
+        let Some(auth) = self.active_auth(access_token.auth_id.clone(), now).await?
+        else
+        {
+            return Ok(None);
+        };
+        let permissions = access_token.effective_permissions(&auth.permissions);
+        Ok(Some(TokenDetails { scope: format_scope(&permissions),
+                               permissions,
+                               exp: access_token.expires_at.map(|expires_at| expires_at.timestamp_millis() / 1000),
+                               client_id: None,
+                               token_type: Some(BEARER_TOKEN_TYPE),
+                               ..user_details(&auth) }))
+    }
+
+    async fn refresh_token(&self, token: &str, now: DateTime) -> Result<Option<TokenDetails>, OAuthError>
+    {
+        let Some(refresh) = self.context
+                                .oauth_refresh_token_repo
+                                .fetch_by_token_hash(&sha256_hex(token))
+                                .await?
+                                .filter(|refresh| refresh.is_usable(now))
+        else
+        {
+            return Ok(None);
+        };
+        let Some(auth) = self.active_auth(refresh.auth_id.clone(), now).await?
+        else
+        {
+            return Ok(None);
+        };
+        let permissions = held(&refresh.scopes, &auth.permissions);
+        Ok(Some(TokenDetails { scope: format_granted_scope(&refresh.identity, &permissions),
+                               permissions,
+                               exp: Some(refresh.expires_at.timestamp_millis() / 1000),
+                               client_id: Some(refresh.client_id),
+                               token_type: None,
+                               ..user_details(&auth) }))
+    }
+
+    /// Login, OAuth and service account tokens are JWTs signed with a published key. Besides
+    /// the signature, only tokens with a record of their issue are vouched for: a session for
+    /// users, a token record for service accounts.
+    async fn bearer_token(&self, token: &str, now: DateTime) -> Result<Option<TokenDetails>, OAuthError>
+    {
+        let claims = TokenOps::new(self.context).verify(token)
+                                                .await
+                                                .map_err(|err| {
+                                                    error!("token not checked: {}", err);
+                                                    OAuthError::ServerError
+                                                })?;
+        let Some(exp) = claims.map(|claims| claims.exp) else { return Ok(None) };
+        let token_hash = sha256_hex(token);
+
+        let session = self.context
+                          .session_repo
+                          .fetch_by_token_hash(&token_hash)
+                          .await
+                          .map_err(|_| OAuthError::ServerError)?;
+        if let Some(session) = session
+        {
+            if session.revoked_at.is_some()
+            {
+                return Ok(None);
+            }
+            let Some(auth) = self.active_auth(session.auth_id, now).await? else { return Ok(None) };
+            // Tokens issued to an OAuth client carry the scopes of its grant.
+            let grant = self.context.oauth_refresh_token_repo.fetch_by_session_token_hash(&token_hash).await?;
+            let (identity, permissions, client_id) = match grant
+            {
+                Some(refresh) => (refresh.identity, held(&refresh.scopes, &auth.permissions), Some(refresh.client_id)),
+                None => (IdentityScopes::default(), auth.permissions.clone(), None),
+            };
+            return Ok(Some(TokenDetails { scope: format_granted_scope(&identity, &permissions),
+                                          permissions,
+                                          exp: Some(exp),
+                                          client_id,
+                                          token_type: Some(BEARER_TOKEN_TYPE),
+                                          ..user_details(&auth) }));
+        }
+
+        let record = self.context
+                         .service_account_token_repo
+                         .fetch_by_token_hash(&token_hash)
+                         .await
+                         .map_err(|_| OAuthError::ServerError)?;
+        let Some(record) = record else { return Ok(None) };
+        let service_account = match self.context.service_account_repo.fetch_by_client_id(&record.client_id).await
+        {
+            Ok(service_account) if service_account.ensure_enabled().is_ok() => service_account,
+            _ => return Ok(None),
+        };
+        let role_permissions = ServiceAccountOps::new(self.context).role_permissions(&service_account.role)
+                                                                     .await
+                                                                     .map_err(|_| OAuthError::ServerError)?;
+        let permissions = record.effective_permissions(&role_permissions);
+        Ok(Some(TokenDetails { sub: service_account.client_id.clone(),
+                               username: service_account.name,
+                               role: service_account.role.to_string(),
+                               scope: format_scope(&permissions),
+                               permissions,
+                               exp: Some(exp),
+                               client_id: Some(service_account.client_id),
+                               token_type: Some(BEARER_TOKEN_TYPE) }))
+    }
+
+    /// The account behind a token, if it can still use it.
+    async fn active_auth(&self, auth_id: AuthID, now: DateTime) -> Result<Option<Auth>, OAuthError>
+    {
+        let auth = known(self.context.auth_repo.fetch_by_id(auth_id).await)?;
+        Ok(auth.filter(|auth| auth.ensure_active(now).is_ok()))
+    }
+}
+
+fn user_details(auth: &Auth) -> TokenDetails
+{
+    TokenDetails { sub:         auth.user_id.value().to_hex(),
+                   username:    auth.username.clone(),
+                   role:        auth.roles.to_string(),
+                   permissions: Vec::new(),
+                   scope:       String::new(),
+                   exp:         None,
+                   client_id:   None,
+                   token_type:  None, }
+}
+
+/// Granted scopes the account still holds.
+fn held(scopes: &[u32], permissions: &[u32]) -> Vec<u32>
+{
+    scopes.iter().copied().filter(|scope| permissions.contains(scope)).collect()
+}
+
+/// A lookup miss is an inactive token; only a database failure is an error.
+fn known<T>(result: Result<T, AuthError>) -> Result<Option<T>, OAuthError>
+{
+    match result
+    {
+        Ok(value) => Ok(Some(value)),
+        Err(AuthError::MongoError(_)) => Err(OAuthError::ServerError),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,66 @@
+use actix_web::HttpRequest;
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::oauth::format_scope;
+use crate::core::domain::oauth::oauth_client::{NewOAuthClient, OAuthClient, OAuthClientSummary, RegisteredOAuthClient};
+use crate::core::domain::oauth::oauth_error::OAuthError;
+use crate::core::domain::perm::perm_cat::MANAGE_OAUTH_CLIENTS;
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::token_ops::TokenOps;
+
+/// Registration of the applications allowed to use the authorization code flow.
+pub struct OAuthClientOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> OAuthClientOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    pub async fn register(&self, req: HttpRequest, new_client: NewOAuthClient) -> Result<RegisteredOAuthClient, OAuthError>
+    {
+        let origin = self.authorize(req).await?;
+        let (client, client_secret) = OAuthClient::register(new_client)?;
+        let client = self.context.oauth_client_repo.create(client).await?;
+
+        let entry = AuditEntry::new(AuditAction::OAuthClientRegistered, &origin).with_target(&client.client_id)
+                                                                               .with_detail(format!("{} ({})", client.name, format_scope(&client.allowed_scopes)));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(RegisteredOAuthClient { client_secret, client: OAuthClientSummary::from(&client) })
+    }
+
+    pub async fn list(&self, req: HttpRequest) -> Result<Vec<OAuthClientSummary>, OAuthError>
+    {
+        self.authorize(req).await?;
+        let clients = self.context.oauth_client_repo.fetch_all().await?;
+        Ok(clients.iter().map(OAuthClientSummary::from).collect())
+    }
+
+    /// A disabled client can't start new authorizations or redeem codes and refresh tokens.
+    pub async fn disable(&self, req: HttpRequest, client_id: String) -> Result<(), OAuthError>
+    {
+        let origin = self.authorize(req).await?;
+        if self.context.oauth_client_repo.disable(&client_id).await?
+        {
+            let entry = AuditEntry::new(AuditAction::OAuthClientDisabled, &origin).with_target(&client_id);
+            AuditOps::new(self.context).record(entry).await;
+        }
+        Ok(())
+    }
+
+    async fn authorize(&self, req: HttpRequest) -> Result<AuditOrigin, OAuthError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !TokenOps::new(self.context).has_permission(&req, MANAGE_OAUTH_CLIENTS).await
+        {
+            return Err(OAuthError::NotHasPermission);
+        }
+        Ok(origin)
+    }
+}
@@ -0,0 +1,464 @@
+use std::env;
+
+use mongodb::bson::DateTime;
+use tracing::error;
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::auth::auth_caller::token_expiry;
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_type::{AuthLogin, ChallengePurpose};
+use crate::core::domain::auth::Auth;
+use crate::core::domain::oauth::oauth_authorize::{
+    redirect_uri_with,
+    AuthorizationCode,
+    AuthorizeDecision,
+    AuthorizeOutcome,
+    AuthorizeRequest,
+    ConsentPrompt,
+};
+use crate::core::domain::oauth::oauth_client::OAuthClient;
+use crate::core::domain::oauth::oauth_error::OAuthError;
+use crate::core::domain::oauth::oauth_oidc::IdentityScopes;
+use crate::core::domain::oauth::oauth_refresh::{RefreshToken, REFRESH_TOKEN_PREFIX};
+use crate::core::domain::oauth::oauth_type::{
+    ClientCredentials,
+    RevokeRequest,
+    TokenRequest,
+    TokenResponse,
+    AUTHORIZATION_CODE_GRANT,
+    CLIENT_CREDENTIALS_GRANT,
+    REFRESH_TOKEN_GRANT,
+};
+use crate::core::domain::oauth::{format_granted_scope, format_scope, grant_scopes};
+use crate::core::domain::service_account::service_account_token::ServiceAccountToken;
+use crate::core::domain::service_account::{ServiceAccount, CLIENT_ID_PREFIX};
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::auth_ops::AuthOps;
+use crate::core::operation::oidc_ops::OidcOps;
+use crate::core::operation::service_account_ops::ServiceAccountOps;
+use crate::core::operation::token_ops::TokenOps;
+use crate::utils::domains_ids::AuthID;
+use crate::utils::env::env_or;
+use crate::utils::secrets::sha256_hex;
+
+const CLIENT_CREDENTIALS_METHOD: &str = "client_credentials";
+const DEFAULT_REFRESH_TOKEN_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Session method of tokens issued to an OAuth client; it is what ties an access token to the
+/// client allowed to revoke it.
+pub fn oauth_session_method(client_id: &str) -> String
+{
+    format!("oauth:{}", client_id)
+}
+
+pub struct OAuthOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> OAuthOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    /// `GET /api/oauth/authorize`: sends the browser to the login page (`OAUTH_LOGIN_URL`) with
+    /// the request, or back to the client with an error. An unknown client or redirect URI is
+    /// returned as an error instead, since redirecting there would be an open redirect.
+    pub async fn authorize_redirect(&self, request: AuthorizeRequest) -> Result<String, OAuthError>
+    {
+        let client = self.authorize_client(&request).await?;
+        if let Err(err) = request.validate().and_then(|_| self.requested_scopes(&client, &request))
+        {
+            return Ok(request.error_redirect(&err));
+        }
+        let login_url = env::var("OAUTH_LOGIN_URL").map_err(|_| {
+                                                       error!("OAUTH_LOGIN_URL is not set");
+                                                       OAuthError::ServerError
+                                                   })?;
+        Ok(redirect_uri_with(&login_url, &request.params()))
+    }
+
+    /// `POST /api/oauth/authorize`: logs the user in through `AuthOps`, asks for consent when the
+    /// client is third party and the user hasn't granted these scopes yet, and issues the code.
+    pub async fn authorize(&self, decision: AuthorizeDecision, origin: AuditOrigin) -> Result<AuthorizeOutcome, OAuthError>
+    {
+        let request = &decision.request;
+        let client = self.authorize_client(request).await?;
+        let requested = match request.validate().and_then(|_| self.requested_scopes(&client, request))
+        {
+            Ok(requested) => requested,
+            Err(err) => return Ok(AuthorizeOutcome::Redirect { redirect_to: request.error_redirect(&err) }),
+        };
+
+        let auth = self.authenticate_user(&decision, &origin).await?;
+        let auth_id = auth._id.clone().ok_or(OAuthError::ServerError)?;
+        let scopes: Vec<u32> = requested.into_iter().filter(|scope| auth.permissions.contains(scope)).collect();
+
+        if decision.consent == Some(false)
+        {
+            return Ok(AuthorizeOutcome::Redirect { redirect_to: request.error_redirect(&OAuthError::AccessDenied) });
+        }
+        if !client.first_party
+        {
+            let consent = self.context.oauth_consent_repo.fetch(auth.user_id.clone(), &client.client_id).await?;
+            if !consent.is_some_and(|consent| consent.covers(&scopes))
+            {
+                if decision.consent != Some(true)
+                {
+                    return self.consent_prompt(&client, &auth, scopes).await;
+                }
+                self.context.oauth_consent_repo.grant(auth.user_id.clone(), &client.client_id, &scopes).await?;
+                let entry = AuditEntry::new(AuditAction::OAuthConsentGranted, &origin).with_actor(&auth.user_id)
+                                                                                      .with_target(&auth.user_id)
+                                                                                      .with_detail(format!("{}: {}", client.client_id, format_scope(&scopes)));
+                AuditOps::new(self.context).record(entry).await;
+            }
+        }
+
+        let (authorization_code, code) = AuthorizationCode::issue(request, auth_id, auth.user_id.clone(), scopes);
+        self.context.oauth_code_repo.create(authorization_code).await?;
+        let redirect_to = redirect_uri_with(&request.redirect_uri, &[("code", Some(&code)), ("state", request.state.as_deref())]);
+        Ok(AuthorizeOutcome::Redirect { redirect_to })
+    }
+
+    /// `POST /api/oauth/token`. `credentials` are the client's, already taken from the Basic
+    /// header or the form.
+    pub async fn token(&self, request: TokenRequest, credentials: Option<ClientCredentials>, origin: AuditOrigin)
+                       -> Result<TokenResponse, OAuthError>
+    {
+        let credentials = credentials.ok_or(OAuthError::InvalidClient)?;
+        match request.grant_type.as_str()
+        {
+            CLIENT_CREDENTIALS_GRANT => self.client_credentials(credentials, request.scope.as_deref(), &origin).await,
+            AUTHORIZATION_CODE_GRANT => self.authorization_code(request, credentials, &origin).await,
+            REFRESH_TOKEN_GRANT => self.refresh_token(request, credentials, &origin).await,
+            grant_type => Err(OAuthError::UnsupportedGrantType(grant_type.to_string())),
+        }
+    }
+
+    /// Token revocation (RFC 7009). Refresh tokens end together with the access token issued
+    /// alongside; tokens that are unknown or belong to another client are ignored.
+    pub async fn revoke(&self, request: RevokeRequest, credentials: Option<ClientCredentials>, origin: AuditOrigin)
+                        -> Result<(), OAuthError>
+    {
+        let client = self.authenticate_client(credentials.ok_or(OAuthError::InvalidClient)?).await?;
+        let token_hash = sha256_hex(&request.token);
+        let session_repo = &self.context.session_repo;
+
+        let revoked_for = if request.token.starts_with(REFRESH_TOKEN_PREFIX)
+        {
+            match self.context.oauth_refresh_token_repo.fetch_by_token_hash(&token_hash).await?
+            {
+                Some(refresh) if refresh.client_id == client.client_id => {
+                    if let Some(id) = refresh._id
+                    {
+                        self.context.oauth_refresh_token_repo.revoke(id).await?;
+                    }
+                    session_repo.revoke_by_token_hash(&refresh.session_token_hash).await.map_err(|_| OAuthError::ServerError)?;
+                    Some(refresh.user_id)
+                },
+                _ => None,
+            }
+        }
+        else
+        {
+            match session_repo.fetch_by_token_hash(&token_hash).await.map_err(|_| OAuthError::ServerError)?
+            {
+                Some(session) if session.method == oauth_session_method(&client.client_id) => {
+                    session_repo.revoke_by_token_hash(&token_hash).await.map_err(|_| OAuthError::ServerError)?;
+                    Some(session.user_id)
+                },
+                _ => None,
+            }
+        };
+
+        if let Some(user_id) = revoked_for
+        {
+            let entry = AuditEntry::new(AuditAction::OAuthTokenRevoked, &origin).with_actor(&client.client_id)
+                                                                                .with_target(&user_id);
+            AuditOps::new(self.context).record(entry).await;
+        }
+        Ok(())
+    }
+
+    /// Callers of the introspection endpoint: service accounts and confidential clients. Public
+    /// clients can't keep a secret, so anyone could pass for them.
+    pub async fn authenticate_resource_server(&self, credentials: Option<ClientCredentials>) -> Result<String, OAuthError>
+    {
+        let credentials = credentials.ok_or(OAuthError::InvalidClient)?;
+        if credentials.client_id.starts_with(CLIENT_ID_PREFIX)
+        {
+            return Ok(self.authenticate_service_account(&credentials).await?.client_id);
+        }
+        let client = self.authenticate_client(credentials).await?;
+        if !client.is_confidential()
+        {
+            return Err(OAuthError::InvalidClient);
+        }
+        Ok(client.client_id)
+    }
+
+    /// Client credentials grant (RFC 6749 §4.4): the service account gets a token carrying its
+    /// role's permissions, or the subset it asked for in `scope`.
+    async fn client_credentials(&self, credentials: ClientCredentials, scope: Option<&str>, origin: &AuditOrigin)
+                                -> Result<TokenResponse, OAuthError>
+    {
+        let service_account = match self.authenticate_service_account(&credentials).await
+        {
+            Ok(service_account) => service_account,
+            Err(err) => {
+                self.audit_failure(origin, &credentials.client_id, &err).await;
+                return Err(err);
+            },
+        };
+
+        let held = ServiceAccountOps::new(self.context).role_permissions(&service_account.role)
+                                                         .await
+                                                         .map_err(|_| OAuthError::ServerError)?;
+        let scopes = grant_scopes(scope, &held)?;
+        let claims = service_account.token_claims(scopes.clone());
+        let access_token = TokenOps::new(self.context).issue(&claims)
+                                                      .await
+                                                      .map_err(|err| {
+                                                          error!("token for {} not signed: {}", service_account.client_id, err);
+                                                          OAuthError::ServerError
+                                                      })?
+                                                      .token;
+        // Recorded so introspection can vouch for it.
+        let record = ServiceAccountToken::new(&access_token, service_account.client_id.clone(), scopes.clone(), claims.exp);
+        self.context
+            .service_account_token_repo
+            .create(record)
+            .await
+            .map_err(|_| OAuthError::ServerError)?;
+
+        let entry = AuditEntry::new(AuditAction::LoginSucceeded, origin).with_actor(&service_account.client_id)
+                                                                        .with_target(&service_account.client_id)
+                                                                        .with_detail(CLIENT_CREDENTIALS_METHOD);
+        AuditOps::new(self.context).record(entry).await;
+
+        Ok(TokenResponse { expires_in: expires_in(&access_token),
+                           access_token,
+                           token_type: "Bearer",
+                           scope: format_scope(&scopes),
+                           refresh_token: None,
+                           id_token: None })
+    }
+
+    /// Authorization code grant (RFC 6749 §4.1) with the PKCE verifier of RFC 7636.
+    async fn authorization_code(&self, request: TokenRequest, credentials: ClientCredentials, origin: &AuditOrigin)
+                                -> Result<TokenResponse, OAuthError>
+    {
+        let client = self.authenticate_client(credentials).await?;
+        let code = request.code.ok_or_else(|| OAuthError::InvalidRequest("code is required".to_string()))?;
+        let authorization_code = self.context.oauth_code_repo.take(&sha256_hex(&code)).await?;
+        authorization_code.redeem(&client.client_id, request.redirect_uri.as_deref(), request.code_verifier.as_deref())?;
+        self.issue_user_tokens(&client,
+                               authorization_code.auth_id,
+                               authorization_code.scopes,
+                               authorization_code.identity,
+                               authorization_code.nonce,
+                               origin)
+            .await
+    }
+
+    /// Refresh tokens are single use: each refresh replaces it and ends the previous access token.
+    async fn refresh_token(&self, request: TokenRequest, credentials: ClientCredentials, origin: &AuditOrigin)
+                           -> Result<TokenResponse, OAuthError>
+    {
+        let client = self.authenticate_client(credentials).await?;
+        let token = request.refresh_token.ok_or_else(|| OAuthError::InvalidRequest("refresh_token is required".to_string()))?;
+        let invalid = || OAuthError::InvalidGrant("invalid or expired refresh token".to_string());
+
+        let refresh = self.context
+                          .oauth_refresh_token_repo
+                          .fetch_by_token_hash(&sha256_hex(&token))
+                          .await?
+                          .filter(|refresh| refresh.client_id == client.client_id && refresh.is_usable(DateTime::now()))
+                          .ok_or_else(invalid)?;
+        // Narrowing on refresh applies to the identity scopes too; without `scope` all are kept.
+        let (identity, permissions) = IdentityScopes::split(request.scope.as_deref());
+        let identity = match request.scope
+        {
+            Some(_) => identity.intersect(&refresh.identity),
+            None => refresh.identity,
+        };
+        let scopes = grant_scopes(permissions.as_deref(), &refresh.scopes)?;
+        if !self.context.oauth_refresh_token_repo.revoke(refresh._id.ok_or(OAuthError::ServerError)?).await?
+        {
+            return Err(invalid());
+        }
+        self.context
+            .session_repo
+            .revoke_by_token_hash(&refresh.session_token_hash)
+            .await
+            .map_err(|_| OAuthError::ServerError)?;
+
+        self.issue_user_tokens(&client, refresh.auth_id, scopes, identity, None, origin).await
+    }
+
+    /// Access token and refresh token for a user, plus an ID token for OpenID requests. The
+    /// access token is a regular login token, limited to the granted scopes the user still
+    /// holds, with its own session.
+    async fn issue_user_tokens(&self,
+                               client: &OAuthClient,
+                               auth_id: AuthID,
+                               scopes: Vec<u32>,
+                               identity: IdentityScopes,
+                               nonce: Option<String>,
+                               origin: &AuditOrigin)
+                               -> Result<TokenResponse, OAuthError>
+    {
+        let auth_repo = self.context.get_auth_repo();
+        let mut auth = auth_repo.fetch_by_id(auth_id.clone())
+                                .await
+                                .map_err(|_| OAuthError::InvalidGrant("account not found".to_string()))?;
+        auth.ensure_active(DateTime::now()).map_err(|err| OAuthError::InvalidGrant(err.to_string()))?;
+
+        let scopes: Vec<u32> = scopes.into_iter()
+                                     .filter(|scope| auth.permissions.contains(scope) && client.allowed_scopes.contains(scope))
+                                     .collect();
+        auth.permissions = scopes.clone();
+        let user_id = auth.user_id.clone();
+        let token = AuthOps::new(&auth_repo, self.context).complete_login(auth, &oauth_session_method(&client.client_id), origin)
+                                                          .await
+                                                          .map_err(|_| OAuthError::ServerError)?;
+        let access_token = token.token;
+
+        let ttl_secs = env_or("OAUTH_REFRESH_TOKEN_TTL_SECS", DEFAULT_REFRESH_TOKEN_TTL_SECS);
+        let (refresh, refresh_token) = RefreshToken::issue(client.client_id.clone(),
+                                                           auth_id,
+                                                           user_id.clone(),
+                                                           scopes.clone(),
+                                                           identity,
+                                                           sha256_hex(&access_token),
+                                                           ttl_secs);
+        self.context.oauth_refresh_token_repo.create(refresh).await?;
+
+        let id_token = if identity.openid
+        {
+            Some(OidcOps::new(self.context).id_token(&client.client_id, user_id, &identity, nonce).await?)
+        }
+        else
+        {
+            None
+        };
+
+        Ok(TokenResponse { expires_in: expires_in(&access_token),
+                           access_token,
+                           token_type: "Bearer",
+                           scope: format_granted_scope(&identity, &scopes),
+                           refresh_token: Some(refresh_token),
+                           id_token })
+    }
+
+    /// Permission scopes of an authorize request: what it asks for, or everything the client may
+    /// ask for. Identity scopes are left to the code, which reads them from the request.
+    fn requested_scopes(&self, client: &OAuthClient, request: &AuthorizeRequest) -> Result<Vec<u32>, OAuthError>
+    {
+        let (_, permissions) = IdentityScopes::split(request.scope.as_deref());
+        grant_scopes(permissions.as_deref(), &client.allowed_scopes)
+    }
+
+    async fn authorize_client(&self, request: &AuthorizeRequest) -> Result<OAuthClient, OAuthError>
+    {
+        let client = self.context
+                         .oauth_client_repo
+                         .fetch_by_client_id(&request.client_id)
+                         .await?
+                         .filter(|client| client.disabled_at.is_none())
+                         .ok_or_else(|| OAuthError::InvalidRequest("unknown client".to_string()))?;
+        if !client.has_redirect_uri(&request.redirect_uri)
+        {
+            return Err(OAuthError::InvalidRequest("redirect_uri is not registered for the client".to_string()));
+        }
+        Ok(client)
+    }
+
+    /// The user behind an authorize call: credentials on the first call, the consent prompt's
+    /// challenge on the second.
+    async fn authenticate_user(&self, decision: &AuthorizeDecision, origin: &AuditOrigin) -> Result<Auth, OAuthError>
+    {
+        let auth_repo = self.context.get_auth_repo();
+        if let Some(challenge_token) = &decision.challenge_token
+        {
+            let pending = self.context
+                              .login_challenge_repo
+                              .take(sha256_hex(challenge_token))
+                              .await?;
+            if pending.purpose != ChallengePurpose::OAuthConsent
+            {
+                return Err(AuthError::ChallengeNotFound.into());
+            }
+            let auth = auth_repo.fetch_by_id(pending.auth_id).await?;
+            auth.ensure_active(DateTime::now())?;
+            return Ok(auth);
+        }
+
+        let (Some(username), Some(password)) = (&decision.username, &decision.password) else
+        {
+            return Err(AuthError::InvalidCredentials.into());
+        };
+        let auth_login = AuthLogin { username: username.clone(), password: password.clone() };
+        let auth = AuthOps::new(&auth_repo, self.context).verify_login(auth_login, decision.code.as_deref(), AUTHORIZATION_CODE_GRANT, origin)
+                                                         .await?;
+        Ok(auth)
+    }
+
+    async fn consent_prompt(&self, client: &OAuthClient, auth: &Auth, scopes: Vec<u32>) -> Result<AuthorizeOutcome, OAuthError>
+    {
+        let auth_repo = self.context.get_auth_repo();
+        let challenge = AuthOps::new(&auth_repo, self.context).create_challenge(auth, ChallengePurpose::OAuthConsent)
+                                                              .await?;
+        let consent_required = ConsentPrompt { client_name: client.name.clone(),
+                                               scopes,
+                                               challenge_token: challenge.challenge_token,
+                                               expires_in: challenge.expires_in };
+        Ok(AuthorizeOutcome::ConsentRequired { consent_required })
+    }
+
+    async fn authenticate_client(&self, credentials: ClientCredentials) -> Result<OAuthClient, OAuthError>
+    {
+        let client = self.context
+                         .oauth_client_repo
+                         .fetch_by_client_id(&credentials.client_id)
+                         .await?
+                         .ok_or(OAuthError::InvalidClient)?;
+        client.authenticate(credentials.client_secret.as_deref())?;
+        Ok(client)
+    }
+
+    /// Unknown client, wrong or expired secret and disabled account all look the same to the caller.
+    async fn authenticate_service_account(&self, credentials: &ClientCredentials) -> Result<ServiceAccount, OAuthError>
+    {
+        let service_account = self.context
+                                  .service_account_repo
+                                  .fetch_by_client_id(&credentials.client_id)
+                                  .await
+                                  .map_err(|_| OAuthError::InvalidClient)?;
+        let secret_valid = credentials.client_secret
+                                      .as_deref()
+                                      .is_some_and(|secret| service_account.verify_secret(secret, DateTime::now()));
+        if service_account.ensure_enabled().is_err() || !secret_valid
+        {
+            return Err(OAuthError::InvalidClient);
+        }
+        Ok(service_account)
+    }
+
+    async fn audit_failure(&self, origin: &AuditOrigin, client_id: &str, err: &OAuthError)
+    {
+        let entry = AuditEntry::new(AuditAction::LoginFailed, origin).with_target(client_id)
+                                                                     .with_detail(format!("{}: {} ({})", CLIENT_CREDENTIALS_METHOD, client_id, err));
+        AuditOps::new(self.context).record(entry).await;
+    }
+}
+
+fn expires_in(access_token: &str) -> Option<i64>
+{
+    token_expiry(access_token).map(|exp| exp - DateTime::now().timestamp_millis() / 1000)
+}
@@ -0,0 +1,110 @@
+use std::env;
+
+use actix_web::HttpRequest;
+use mongodb::bson::DateTime;
+use tracing::error;
+
+use crate::context::Context;
+use crate::core::domain::auth::auth_caller::{bearer_token, token_expiry};
+use crate::core::domain::oauth::oauth_error::OAuthError;
+use crate::core::domain::oauth::oauth_oidc::{DiscoveryDocument, IdTokenClaims, IdentityScopes, UserInfo};
+use crate::core::domain::signing_key::signing_key_error::SigningKeyError;
+use crate::core::domain::signing_key::Jwks;
+use crate::core::operation::oauth_ops::oauth_session_method;
+use crate::core::operation::signing_key_ops::SigningKeyOps;
+use crate::utils::domains_ids::UserID;
+use crate::utils::env::env_or;
+use crate::utils::secrets::sha256_hex;
+
+const DEFAULT_ID_TOKEN_TTL_SECS: i64 = 60 * 60;
+
+/// OpenID Connect on top of the OAuth endpoints: discovery, signing keys, ID tokens and userinfo.
+pub struct OidcOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> OidcOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    pub fn discovery(&self) -> Result<DiscoveryDocument, OAuthError>
+    {
+        Ok(DiscoveryDocument::new(&issuer()?))
+    }
+
+    pub async fn jwks(&self) -> Result<Jwks, OAuthError>
+    {
+        SigningKeyOps::new(self.context).jwks().await.map_err(signing_failure)
+    }
+
+    /// ID token for `client_id`, with the claims its identity scopes release.
+    pub async fn id_token(&self, client_id: &str, user_id: UserID, identity: &IdentityScopes, nonce: Option<String>)
+                          -> Result<String, OAuthError>
+    {
+        let user = self.context
+                       .user_repo
+                       .fetch_by_id(user_id)
+                       .await
+                       .map_err(|_| OAuthError::InvalidGrant("account not found".to_string()))?;
+        let ttl_secs = env_or("OIDC_ID_TOKEN_TTL_SECS", DEFAULT_ID_TOKEN_TTL_SECS);
+        let claims = IdTokenClaims::new(&issuer()?,
+                                        client_id,
+                                        UserInfo::new(&user, identity),
+                                        nonce,
+                                        DateTime::now().timestamp_millis() / 1000,
+                                        ttl_secs);
+        SigningKeyOps::new(self.context).sign_jwt(&claims).await.map_err(signing_failure)
+    }
+
+    /// Claims about the owner of the request's access token. Only tokens issued to an OAuth
+    /// client with the `openid` scope qualify; their session must still be live.
+    pub async fn userinfo(&self, req: &HttpRequest) -> Result<UserInfo, OAuthError>
+    {
+        let token = bearer_token(req).ok_or(OAuthError::InvalidToken)?;
+        if token_expiry(&token).is_none_or(|exp| exp <= DateTime::now().timestamp_millis() / 1000)
+        {
+            return Err(OAuthError::InvalidToken);
+        }
+        let token_hash = sha256_hex(&token);
+        let session = self.context
+                          .session_repo
+                          .fetch_by_token_hash(&token_hash)
+                          .await
+                          .map_err(|_| OAuthError::ServerError)?
+                          .filter(|session| session.revoked_at.is_none())
+                          .ok_or(OAuthError::InvalidToken)?;
+        let refresh = self.context
+                          .oauth_refresh_token_repo
+                          .fetch_by_session_token_hash(&token_hash)
+                          .await?
+                          .filter(|refresh| refresh.identity.openid && session.method == oauth_session_method(&refresh.client_id))
+                          .ok_or(OAuthError::InvalidToken)?;
+
+        let user = self.context
+                       .user_repo
+                       .fetch_by_id(session.user_id)
+                       .await
+                       .map_err(|_| OAuthError::InvalidToken)?;
+        Ok(UserInfo::new(&user, &refresh.identity))
+    }
+}
+
+/// `OIDC_ISSUER`: the public base URL of this service, as clients see it.
+fn issuer() -> Result<String, OAuthError>
+{
+    env::var("OIDC_ISSUER").map(|issuer| issuer.trim_end_matches('/').to_string())
+                           .map_err(|_| {
+                               error!("OIDC_ISSUER is not set");
+                               OAuthError::ServerError
+                           })
+}
+
+fn signing_failure(err: SigningKeyError) -> OAuthError
+{
+    error!("signing keys unavailable: {}", err);
+    OAuthError::ServerError
+}
@@ -0,0 +1,194 @@
+use mongodb::bson::DateTime;
+use tracing::{info, warn};
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::AuditOrigin;
+use crate::core::domain::auth::Auth;
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_lockout::{LockoutPolicy, LoginAttemptKey};
+use crate::core::domain::auth::auth_type::{AccountProof, Token};
+use crate::core::domain::auth::auth_webauthn::{
+    client_challenge,
+    creation_options,
+    request_options,
+    verify_authentication,
+    verify_registration,
+    AuthenticationResponse,
+    Ceremony,
+    CreationOptions,
+    PasskeySummary,
+    RegistrationResponse,
+    RelyingParty,
+    RequestOptions,
+    UserEntity,
+    WebauthnChallenge,
+};
+use crate::core::operation::auth_ops::AuthOps;
+use crate::data::access::auth_repo::MongoAuthRepo;
+use crate::utils::domains_ids::AuthID;
+use crate::utils::secrets::random_token;
+
+const CEREMONY_SECS: i64 = 300;
+/// Method recorded on failed proofs at the passkey management endpoints.
+const PASSKEY_MANAGEMENT: &str = "passkeys";
+
+pub struct PasskeyOps<'a>
+{
+    repo:     &'a MongoAuthRepo,
+    auth_ops: AuthOps<'a>,
+    context:  &'a Context,
+    rp:       RelyingParty,
+}
+
+impl<'a> PasskeyOps<'a>
+{
+    pub fn new(repo: &'a MongoAuthRepo, context: &'a Context) -> Self
+    {
+        Self { repo, auth_ops: AuthOps::new(repo, context), context, rp: RelyingParty::from_env() }
+    }
+
+    /// Registration takes the full login proof: with the password alone, whoever learnt it could
+    /// add their own passkey and skip the second factor from then on.
+    pub async fn start_registration(&self, proof: AccountProof, origin: AuditOrigin)
+                                    -> Result<CreationOptions, AuthError>
+    {
+        let auth = self.auth_ops.verify_owner(proof, PASSKEY_MANAGEMENT, &origin).await?;
+        let auth_id = auth._id.clone().ok_or(AuthError::AuthNotFound)?;
+
+        let challenge = self.create_challenge(Ceremony::Registration, Some(auth_id.clone())).await?;
+        let user = UserEntity {
+            id:           data_encoding::BASE64URL_NOPAD.encode(auth.user_id.to_string().as_bytes()),
+            name:         auth.username.clone(),
+            display_name: auth.username.clone(),
+        };
+        Ok(creation_options(&self.rp, user, &challenge, &auth.passkeys))
+    }
+
+    pub async fn finish_registration(&self, response: RegistrationResponse) -> Result<PasskeySummary, AuthError>
+    {
+        let challenge = client_challenge(&response.response.client_data_json)?;
+        let pending = self.context
+                          .webauthn_challenge_repo
+                          .take(challenge.clone(), Ceremony::Registration)
+                          .await?;
+        let auth = self.repo
+                       .fetch_by_id(pending.auth_id.ok_or(AuthError::ChallengeNotFound)?)
+                       .await?;
+
+        let credential = verify_registration(&self.rp, &challenge, &response)?;
+        if self.repo.fetch_by_credential_id(credential.credential_id.clone()).await.is_ok()
+        {
+            return Err(AuthError::PasskeyAlreadyRegistered);
+        }
+
+        self.repo.add_passkey(auth._id.clone().ok_or(AuthError::AuthNotFound)?, &credential).await?;
+        info!(target: "audit", "passkey '{}' registered for {}", credential.name, auth.username);
+
+        Ok(PasskeySummary::from(&credential))
+    }
+
+    /// The options never list credentials, so they look the same for every account and for
+    /// none. A known username only ties the challenge to its account.
+    pub async fn start_authentication(&self, identifier: Option<String>) -> Result<RequestOptions, AuthError>
+    {
+        let auth_id = match identifier
+        {
+            Some(identifier) => self.repo.fetch_by_identifier(&identifier).await.ok().and_then(|auth| auth._id),
+            None => None,
+        };
+
+        let challenge = self.create_challenge(Ceremony::Authentication, auth_id).await?;
+        Ok(request_options(&self.rp, &challenge))
+    }
+
+    pub async fn finish_authentication(&self, response: AuthenticationResponse, origin: AuditOrigin) -> Result<Token, AuthError>
+    {
+        let credential_id = response.id.trim_end_matches('=').to_string();
+        match self.verify_assertion(response).await
+        {
+            Ok(auth) => self.auth_ops.complete_login(auth, "passkey", &origin).await,
+            Err(err) => {
+                let auth = self.repo.fetch_by_credential_id(credential_id.clone()).await.ok();
+                self.auth_ops.audit_login_failed(&origin, "passkey", auth.as_ref(), &err).await;
+                Err(err)
+            },
+        }
+    }
+
+    async fn verify_assertion(&self, response: AuthenticationResponse) -> Result<Auth, AuthError>
+    {
+        let challenge = client_challenge(&response.response.client_data_json)?;
+        let pending = self.context
+                          .webauthn_challenge_repo
+                          .take(challenge.clone(), Ceremony::Authentication)
+                          .await?;
+
+        let credential_id = response.id.trim_end_matches('=').to_string();
+        let auth = self.repo.fetch_by_credential_id(credential_id.clone()).await?;
+        if pending.auth_id.is_some() && pending.auth_id != auth._id
+        {
+            return Err(AuthError::PasskeyNotFound);
+        }
+
+        let key = [LoginAttemptKey::Account(auth.username.clone())];
+        self.auth_ops.check_attempts(&key).await?;
+
+        let credential = auth.passkeys
+                             .iter()
+                             .find(|credential| credential.credential_id == credential_id)
+                             .ok_or(AuthError::PasskeyNotFound)?;
+        let sign_count = match verify_authentication(&self.rp, &challenge, credential, &response)
+        {
+            Ok(sign_count) => sign_count,
+            Err(err) => {
+                warn!(target: "audit", "passkey assertion rejected for {}: {}", auth.username, err);
+                self.auth_ops.register_failure(&key, &LockoutPolicy::from_env()).await?;
+                return Err(err);
+            },
+        };
+        auth.ensure_active(DateTime::now())?;
+
+        let auth_id = auth._id.clone().ok_or(AuthError::AuthNotFound)?;
+        if !self.repo.record_passkey_use(auth_id, &credential_id, sign_count, DateTime::now()).await?
+        {
+            // Another login got in first with this counter value.
+            return Err(AuthError::PasskeyVerification("signature counter did not increase"));
+        }
+        self.context.login_attempt_repo.reset(&key[0]).await?;
+        Ok(auth)
+    }
+
+    pub async fn list(&self, proof: AccountProof, origin: AuditOrigin) -> Result<Vec<PasskeySummary>, AuthError>
+    {
+        let auth = self.auth_ops.verify_owner(proof, PASSKEY_MANAGEMENT, &origin).await?;
+        Ok(auth.passkeys.iter().map(PasskeySummary::from).collect())
+    }
+
+    pub async fn remove(&self, proof: AccountProof, credential_id: String, origin: AuditOrigin)
+                        -> Result<(), AuthError>
+    {
+        let auth = self.auth_ops.verify_owner(proof, PASSKEY_MANAGEMENT, &origin).await?;
+
+        self.repo.remove_passkey(auth._id.clone().ok_or(AuthError::AuthNotFound)?, &credential_id).await?;
+        info!(target: "audit", "passkey {} removed for {}", credential_id, auth.username);
+        Ok(())
+    }
+
+    async fn create_challenge(&self, ceremony: Ceremony, auth_id: Option<AuthID>)
+                              -> Result<String, AuthError>
+    {
+        let challenge = random_token(32);
+        self.context
+            .webauthn_challenge_repo
+            .create(WebauthnChallenge {
+                _id: None,
+                challenge: challenge.clone(),
+                ceremony,
+                auth_id,
+                expires_at: DateTime::from_millis(DateTime::now().timestamp_millis() + CEREMONY_SECS * 1000),
+            })
+            .await?;
+        Ok(challenge)
+    }
+
+}
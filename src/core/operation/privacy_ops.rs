@@ -0,0 +1,168 @@
+use actix_web::HttpRequest;
+use mongodb::bson::DateTime;
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::auth::auth_lockout::LoginAttemptKey;
+use crate::core::domain::auth::auth_access_token::AccessTokenSummary;
+use crate::core::domain::auth::auth_session::SessionSummary;
+use crate::core::domain::auth::auth_type::AccountProof;
+use crate::core::domain::auth::AuthEntity;
+use crate::core::domain::perm::perm_cat::{DELETE_USER, READ_USER};
+use crate::core::domain::user::user_error::UserError;
+use crate::core::domain::user::user_privacy::{AccountExport, ConsentExport, DataExport, ErasedIdentity, LoginAttemptExport};
+use crate::core::domain::user::user_status::AccountStatus;
+use crate::core::domain::user::User;
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::auth_ops::AuthOps;
+use crate::core::operation::authz_ops::AuthzOps;
+use crate::utils::domains_ids::UserID;
+
+/// Method recorded on failed proofs at the self-service privacy endpoints.
+const PRIVACY_REQUEST: &str = "privacy";
+
+/// Data subject access and erasure. Admins act on any user through their permissions; users act
+/// on their own account by re-entering their credentials, and their second factor when enabled.
+pub struct PrivacyOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> PrivacyOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    pub async fn export_user(&self, req: HttpRequest, user_id: UserID) -> Result<DataExport, UserError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !AuthzOps::new(self.context).allows_on(req, READ_USER, &user_id).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+        let export = self.collect(user_id.clone()).await?;
+        self.audit(AuditEntry::new(AuditAction::UserDataExported, &origin).with_target(&user_id)).await;
+        Ok(export)
+    }
+
+    pub async fn export_own(&self, proof: AccountProof, origin: AuditOrigin) -> Result<DataExport, UserError>
+    {
+        let user_id = self.verify_self(proof, &origin).await?;
+        let export = self.collect(user_id.clone()).await?;
+        let entry = AuditEntry::new(AuditAction::UserDataExported, &origin).with_actor(&user_id).with_target(&user_id);
+        self.audit(entry).await;
+        Ok(export)
+    }
+
+    pub async fn erase_user(&self, req: HttpRequest, user_id: UserID) -> Result<User, UserError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !AuthzOps::new(self.context).allows_on(req, DELETE_USER, &user_id).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+        self.erase(user_id, origin).await
+    }
+
+    pub async fn erase_own(&self, proof: AccountProof, origin: AuditOrigin) -> Result<User, UserError>
+    {
+        let user_id = self.verify_self(proof, &origin).await?;
+        let origin = AuditOrigin { actor: Some(user_id.to_string()), ..origin };
+        self.erase(user_id, origin).await
+    }
+
+    async fn audit(&self, entry: AuditEntry)
+    {
+        AuditOps::new(self.context).record(entry).await;
+    }
+
+    async fn verify_self(&self, proof: AccountProof, origin: &AuditOrigin) -> Result<UserID, UserError>
+    {
+        let auth_ops = AuthOps::new(self.context.auth_repo.as_ref(), self.context);
+        let auth = auth_ops.verify_owner(proof, PRIVACY_REQUEST, origin).await.map_err(UserError::LoginRejected)?;
+        Ok(auth.user_id)
+    }
+
+    async fn collect(&self, user_id: UserID) -> Result<DataExport, UserError>
+    {
+        let user = self.context.user_repo.fetch_by_id(user_id.clone()).await?;
+        let auth = self.context.auth_repo.fetch_by_user_id(user_id.clone()).await.ok();
+
+        let mut login_attempts = Vec::new();
+        let mut access_tokens = Vec::new();
+        if let Some(auth) = &auth
+        {
+            let key = LoginAttemptKey::Account(auth.username.clone());
+            let attempt = self.context.login_attempt_repo.fetch(&key).await.map_err(|_| UserError::AuthError)?;
+            login_attempts.extend(attempt.map(LoginAttemptExport::from));
+
+            let auth_id = auth._id.clone().ok_or(UserError::AuthError)?;
+            let tokens = self.context.access_token_repo.fetch_by_auth_id(auth_id).await.map_err(|_| UserError::AuthError)?;
+            access_tokens.extend(tokens.iter().map(AccessTokenSummary::from));
+        }
+        let sessions = self.context.session_repo.fetch_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+        let consents = self.context.oauth_consent_repo.fetch_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+        let audit_entries = self.context
+                                .audit_repo
+                                .fetch_involving(&user_id.to_string())
+                                .await
+                                .map_err(|_| UserError::InternalServerError)?;
+
+        Ok(DataExport { generated_at: DateTime::now().timestamp_millis(),
+                        user,
+                        account: auth.as_ref().map(AccountExport::from),
+                        login_attempts,
+                        sessions: sessions.iter().map(|session| SessionSummary::new(session, None)).collect(),
+                        access_tokens,
+                        oauth_consents: consents.into_iter().map(ConsentExport::from).collect(),
+                        audit_entries })
+    }
+
+    /// Overwrites the PII on the user and auth documents with placeholders derived from the id and
+    /// disables every credential. Both documents, and their ids, stay in place.
+    async fn erase(&self, user_id: UserID, origin: AuditOrigin) -> Result<User, UserError>
+    {
+        let mut user = self.context.user_repo.fetch_by_id(user_id.clone()).await?;
+        let identity = ErasedIdentity::for_user(&user_id);
+
+        if let Ok(auth) = self.context.auth_repo.fetch_by_user_id(user_id.clone()).await
+        {
+            let auth_id = auth._id.clone().ok_or(UserError::AuthError)?;
+            self.context
+                .login_attempt_repo
+                .reset(&LoginAttemptKey::Account(auth.username.clone()))
+                .await
+                .map_err(|_| UserError::AuthError)?;
+            self.context.login_challenge_repo.delete_by_auth_id(auth_id.clone()).await.map_err(|_| UserError::AuthError)?;
+            self.context.webauthn_challenge_repo.delete_by_auth_id(auth_id.clone()).await.map_err(|_| UserError::AuthError)?;
+            self.context.session_repo.delete_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+            self.context.access_token_repo.delete_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+            self.context.oauth_refresh_token_repo.delete_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+            self.context.oauth_consent_repo.delete_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+
+            let mut auth_entity = AuthEntity::new(auth, self.context.auth_repo.as_ref()).await;
+            auth_entity.update_id(auth_id).await;
+            auth_entity.update_identity(identity.username.clone(), identity.email.clone()).await;
+            // An empty hash matches no scheme, so the password can never verify again.
+            auth_entity.update_password(String::new()).await;
+            auth_entity.update_permissions(Vec::new()).await;
+            auth_entity.update_resource_grants(Vec::new()).await;
+            auth_entity.update_two_factor(None).await;
+            auth_entity.update_passkeys(Vec::new()).await;
+            auth_entity.update_status(AccountStatus::Deactivated, None).await;
+            auth_entity.save().await.map_err(|_| UserError::AuthError)?;
+        }
+
+        user.username = identity.username;
+        user.email = identity.email;
+        user.name = identity.name;
+        user.status = AccountStatus::Deactivated;
+        let user = self.context.user_repo.save(user).await?;
+
+        self.audit(AuditEntry::new(AuditAction::UserErased, &origin).with_target(&user_id)).await;
+        Ok(user)
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use mongodb::bson::DateTime;
+use tracing::{error, info, warn};
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::auth::auth_lockout::LoginAttemptKey;
+use crate::core::domain::user::user_deletion::{DeletedUser, RetentionPolicy};
+use crate::core::domain::user::user_error::UserError;
+use crate::core::operation::audit_ops::AuditOps;
+
+/// Accounts purged per run; anything left over waits for the next tick.
+const PURGE_BATCH: i64 = 500;
+
+pub struct RetentionOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> RetentionOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    /// Hard-deletes accounts whose soft deletion is older than the retention window, together
+    /// with their auth record and pending login state. Returns how many were purged.
+    pub async fn purge_expired(&self, policy: &RetentionPolicy) -> Result<usize, UserError>
+    {
+        let cutoff = policy.cutoff(DateTime::now());
+        let expired = self.context.user_repo.fetch_deleted_before(cutoff, PURGE_BATCH).await?;
+
+        let mut purged = 0;
+        for deleted in expired
+        {
+            let username = deleted.deleted_identity.username.clone();
+            match self.purge_user(deleted).await
+            {
+                Ok(()) => purged += 1,
+                Err(err) => warn!("purge of deleted user {} failed: {}", username, err),
+            }
+        }
+        Ok(purged)
+    }
+
+    async fn purge_user(&self, deleted: DeletedUser) -> Result<(), UserError>
+    {
+        let user_id = deleted.user._id.clone().ok_or(UserError::InvalidUserId)?;
+
+        let auth = self.context
+                       .auth_repo
+                       .fetch_deleted_by_user_id(user_id.clone())
+                       .await
+                       .map_err(|_| UserError::AuthError)?;
+        if let Some(auth) = auth
+        {
+            if let Some(auth_id) = auth._id.clone()
+            {
+                self.context.login_challenge_repo.delete_by_auth_id(auth_id.clone()).await.map_err(|_| UserError::AuthError)?;
+                self.context.webauthn_challenge_repo.delete_by_auth_id(auth_id.clone()).await.map_err(|_| UserError::AuthError)?;
+                self.context.auth_repo.purge(auth_id).await.map_err(|_| UserError::AuthError)?;
+            }
+            self.context
+                .login_attempt_repo
+                .reset(&LoginAttemptKey::Account(deleted.deleted_identity.username.clone()))
+                .await
+                .map_err(|_| UserError::AuthError)?;
+        }
+
+        self.context.session_repo.delete_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+        self.context.access_token_repo.delete_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+        self.context.oauth_refresh_token_repo.delete_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+        self.context.oauth_consent_repo.delete_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+        self.context.user_repo.purge(user_id.clone()).await?;
+        let entry = AuditEntry::new(AuditAction::UserPurged, &AuditOrigin::system())
+            .with_target(&user_id)
+            .with_detail(format!("deleted at {} by {:?}", deleted.deleted_at, deleted.deleted_by));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(())
+    }
+}
+
+/// Runs the purge every `USER_PURGE_INTERVAL_SECS` for the life of the server.
+pub fn spawn_purge_task(context: Arc<Context>)
+{
+    let policy = RetentionPolicy::from_env();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(policy.purge_interval_secs.max(60)));
+        loop
+        {
+            interval.tick().await;
+            match RetentionOps::new(&context).purge_expired(&policy).await
+            {
+                Ok(0) => {},
+                Ok(purged) => info!("purged {} soft-deleted users past retention", purged),
+                Err(err) => error!("user purge failed: {}", err),
+            }
+        }
+    });
+}
@@ -0,0 +1,128 @@
+use actix_web::HttpRequest;
+use mongodb::bson::DateTime;
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::perm::perm_cat::MANAGE_SERVICE_ACCOUNTS;
+use crate::core::domain::perm::perm_repo::PermRepo;
+use crate::core::domain::service_account::service_account_error::ServiceAccountError;
+use crate::core::domain::service_account::service_account_type::{IssuedClientSecret, NewServiceAccount, ServiceAccountSummary};
+use crate::core::domain::service_account::ServiceAccount;
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::token_ops::TokenOps;
+use crate::utils::env::env_or;
+
+/// How long the previous secret keeps working after a rotation.
+const DEFAULT_SECRET_OVERLAP_SECS: i64 = 24 * 60 * 60;
+
+pub struct ServiceAccountOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> ServiceAccountOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    pub async fn create(&self, req: HttpRequest, new_account: NewServiceAccount) -> Result<IssuedClientSecret, ServiceAccountError>
+    {
+        let origin = self.authorize(req).await?;
+        let name = new_account.name.trim().to_string();
+        if name.is_empty()
+        {
+            return Err(ServiceAccountError::Invalid("name is required".to_string()));
+        }
+        // The role must have a permission set, or tokens would carry nothing.
+        self.role_permissions(&new_account.role).await?;
+
+        let (service_account, client_secret) = ServiceAccount::new(name, new_account.description, new_account.role);
+        let service_account = self.context.service_account_repo.create(service_account).await?;
+        let account = ServiceAccountSummary::from(&service_account);
+
+        let entry = AuditEntry::new(AuditAction::ServiceAccountCreated, &origin).with_target(&service_account.client_id)
+                                                                               .with_detail(format!("{} ({})", account.name, account.role));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(IssuedClientSecret { client_secret, account })
+    }
+
+    pub async fn list(&self, req: HttpRequest) -> Result<Vec<ServiceAccountSummary>, ServiceAccountError>
+    {
+        self.authorize(req).await?;
+        let service_accounts = self.context.service_account_repo.fetch_all().await?;
+        Ok(service_accounts.iter().map(ServiceAccountSummary::from).collect())
+    }
+
+    /// Issues a new secret; the previous one keeps working for `SERVICE_ACCOUNT_SECRET_OVERLAP_SECS`.
+    pub async fn rotate_secret(&self, req: HttpRequest, client_id: String) -> Result<IssuedClientSecret, ServiceAccountError>
+    {
+        let origin = self.authorize(req).await?;
+        let mut service_account = self.context.service_account_repo.fetch_by_client_id(&client_id).await?;
+        service_account.ensure_enabled()?;
+
+        let overlap_secs = env_or("SERVICE_ACCOUNT_SECRET_OVERLAP_SECS", DEFAULT_SECRET_OVERLAP_SECS);
+        let client_secret = service_account.rotate_secret(DateTime::now(), overlap_secs * 1000);
+        self.context
+            .service_account_repo
+            .update_secrets(&client_id, &service_account.secrets)
+            .await?;
+
+        let entry = AuditEntry::new(AuditAction::ServiceAccountSecretRotated, &origin).with_target(&client_id);
+        AuditOps::new(self.context).record(entry).await;
+        Ok(IssuedClientSecret { client_secret, account: ServiceAccountSummary::from(&service_account) })
+    }
+
+    /// Ends the overlap early once every caller uses the new secret.
+    pub async fn revoke_secret(&self, req: HttpRequest, client_id: String, secret_id: String) -> Result<ServiceAccountSummary, ServiceAccountError>
+    {
+        let origin = self.authorize(req).await?;
+        let mut service_account = self.context.service_account_repo.fetch_by_client_id(&client_id).await?;
+        service_account.revoke_secret(&secret_id, DateTime::now())?;
+        self.context
+            .service_account_repo
+            .update_secrets(&client_id, &service_account.secrets)
+            .await?;
+
+        let entry = AuditEntry::new(AuditAction::ServiceAccountSecretRevoked, &origin).with_target(&client_id)
+                                                                                     .with_detail(format!("secret {}", secret_id));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(ServiceAccountSummary::from(&service_account))
+    }
+
+    /// A disabled account can't get new tokens; the ones already issued run until they expire.
+    pub async fn disable(&self, req: HttpRequest, client_id: String) -> Result<(), ServiceAccountError>
+    {
+        let origin = self.authorize(req).await?;
+        self.context.service_account_repo.fetch_by_client_id(&client_id).await?;
+        if self.context.service_account_repo.disable(&client_id).await?
+        {
+            let entry = AuditEntry::new(AuditAction::ServiceAccountDisabled, &origin).with_target(&client_id);
+            AuditOps::new(self.context).record(entry).await;
+        }
+        Ok(())
+    }
+
+    /// Current permissions of a role, which is what an account's tokens carry.
+    pub async fn role_permissions(&self, role: &Role) -> Result<Vec<u32>, ServiceAccountError>
+    {
+        self.context
+            .perm_repo
+            .charge_permissions(role.to_string(), self.context)
+            .await
+            .map_err(|_| ServiceAccountError::PermError)
+    }
+
+    async fn authorize(&self, req: HttpRequest) -> Result<AuditOrigin, ServiceAccountError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !TokenOps::new(self.context).has_permission(&req, MANAGE_SERVICE_ACCOUNTS).await
+        {
+            return Err(ServiceAccountError::NotHasPermission);
+        }
+        Ok(origin)
+    }
+}
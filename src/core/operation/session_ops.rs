@@ -0,0 +1,107 @@
+use actix_web::HttpRequest;
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::auth::auth_caller::bearer_token;
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_session::{touch_cutoff, touch_interval_secs, Session, SessionSummary};
+use crate::core::domain::auth::auth_type::Token;
+use crate::core::domain::auth::Auth;
+use crate::core::domain::perm::perm_cat::READ_USER;
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::authz_ops::AuthzOps;
+use crate::utils::domains_ids::UserID;
+use crate::utils::secrets::sha256_hex;
+
+pub struct SessionOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> SessionOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    pub async fn start(&self, auth: &Auth, token: &Token, method: &str, origin: &AuditOrigin) -> Result<(), AuthError>
+    {
+        let auth_id = auth._id.clone().ok_or(AuthError::AuthNotFound)?;
+        self.context
+            .session_repo
+            .create(Session::new(auth_id, auth.user_id.clone(), &token.token, method, origin))
+            .await?;
+        Ok(())
+    }
+
+    /// Called by `session_guard` for every request with a token. Tokens without a session
+    /// (issued before sessions existed) are let through. `last_seen_at` is only rewritten once
+    /// it is `SESSION_TOUCH_INTERVAL_SECS` old.
+    pub async fn check(&self, token: &str) -> Result<(), AuthError>
+    {
+        let token_hash = sha256_hex(token);
+        let now = DateTime::now();
+        let interval_secs = touch_interval_secs();
+        match self.context.session_repo.fetch_by_token_hash(&token_hash).await?
+        {
+            Some(session) if session.revoked_at.is_some() => Err(AuthError::SessionRevoked),
+            Some(session) if session.needs_touch(now, interval_secs) => {
+                self.context.session_repo.touch(&token_hash, now, touch_cutoff(now, interval_secs)).await
+            },
+            Some(_) | None => Ok(()),
+        }
+    }
+
+    pub async fn list_own(&self, req: HttpRequest) -> Result<Vec<SessionSummary>, AuthError>
+    {
+        let current = self.current_session(&req).await?;
+        self.summaries(current.user_id, Some(&current.token_hash)).await
+    }
+
+    pub async fn revoke_own(&self, req: HttpRequest, session_id: ObjectId) -> Result<(), AuthError>
+    {
+        let current = self.current_session(&req).await?;
+        if !self.context.session_repo.revoke(session_id, current.user_id.clone()).await?
+        {
+            return Err(AuthError::SessionNotFound);
+        }
+
+        let entry = AuditEntry::new(AuditAction::SessionRevoked, &AuditOrigin::from_request(&req))
+            .with_actor(&current.user_id)
+            .with_target(&current.user_id)
+            .with_detail(format!("session {}", session_id.to_hex()));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(())
+    }
+
+    pub async fn list_for_user(&self, req: HttpRequest, user_id: UserID) -> Result<Vec<SessionSummary>, AuthError>
+    {
+        let current_token_hash = bearer_token(&req).map(|token| sha256_hex(&token));
+        if !AuthzOps::new(self.context).allows_on(req, READ_USER, &user_id).await
+        {
+            return Err(AuthError::Unauthorized);
+        }
+        self.summaries(user_id, current_token_hash.as_deref()).await
+    }
+
+    /// The session behind the request's token; a revoked one is already stopped by the guard.
+    async fn current_session(&self, req: &HttpRequest) -> Result<Session, AuthError>
+    {
+        let token = bearer_token(req).ok_or(AuthError::Unauthorized)?;
+        self.context
+            .session_repo
+            .fetch_by_token_hash(&sha256_hex(&token))
+            .await?
+            .filter(|session| session.revoked_at.is_none())
+            .ok_or(AuthError::Unauthorized)
+    }
+
+    async fn summaries(&self, user_id: UserID, current_token_hash: Option<&str>) -> Result<Vec<SessionSummary>, AuthError>
+    {
+        let sessions = self.context.session_repo.fetch_by_user_id(user_id).await?;
+        Ok(sessions.iter().map(|session| SessionSummary::new(session, current_token_hash)).collect())
+    }
+}
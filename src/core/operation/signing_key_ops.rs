@@ -0,0 +1,138 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use mongodb::bson::DateTime;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{error, info, warn};
+
+use crate::context::Context;
+use crate::core::domain::signing_key::signing_key_error::SigningKeyError;
+use crate::core::domain::signing_key::{verify_jwt, Jwks, KeyCipher, KeyPair, SigningAlgorithm, SigningKey};
+use crate::utils::env::env_or;
+
+const DEFAULT_ROTATION_DAYS: i64 = 30;
+/// Long enough for every token signed with a rotated key to expire.
+const DEFAULT_RETIRE_AFTER_SECS: i64 = 24 * 60 * 60;
+const ROTATION_CHECK_SECS: u64 = 60 * 60;
+
+/// Keys for the tokens this service signs itself. Verifiers fetch the public keys from the
+/// JWKS and pick one by the `kid` in the token header.
+pub struct SigningKeyOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> SigningKeyOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    /// Signs `claims` with the active key, creating the first key, or the replacement of one
+    /// that no longer decrypts, on demand.
+    pub async fn sign_jwt<T: Serialize>(&self, claims: &T) -> Result<String, SigningKeyError>
+    {
+        let cipher = KeyCipher::from_env()?;
+        let key_pair = match self.context.signing_key_repo.fetch_active().await?
+        {
+            Some(signing_key) => match signing_key.key_pair(&cipher)
+            {
+                Ok(key_pair) => key_pair,
+                Err(_) => self.rotate(&cipher, Some(&signing_key)).await?.1,
+            },
+            None => self.rotate(&cipher, None).await?.1,
+        };
+        key_pair.sign_jwt(claims)
+    }
+
+    /// The active key and the rotated ones still within their grace period.
+    pub async fn jwks(&self) -> Result<Jwks, SigningKeyError>
+    {
+        let signing_keys = self.context.signing_key_repo.fetch_published(DateTime::now()).await?;
+        Ok(Jwks { keys: signing_keys.into_iter().map(|signing_key| signing_key.public_jwk).collect() })
+    }
+
+    /// Claims of a token signed with one of the published keys. Expiry is the caller's to check.
+    pub async fn verify_jwt<T: DeserializeOwned>(&self, token: &str) -> Result<Option<T>, SigningKeyError>
+    {
+        Ok(verify_jwt(token, &self.jwks().await?.keys))
+    }
+
+    /// Rotates when there is no active key, when it is older than `SIGNING_KEY_ROTATION_DAYS`,
+    /// when `SIGNING_KEY_ALG` names another algorithm or when it doesn't decrypt with
+    /// `SIGNING_KEY_ENCRYPTION_KEY`.
+    pub async fn rotate_if_due(&self) -> Result<Option<SigningKey>, SigningKeyError>
+    {
+        let cipher = KeyCipher::from_env()?;
+        let rotation_ms = env_or("SIGNING_KEY_ROTATION_DAYS", DEFAULT_ROTATION_DAYS) * 24 * 60 * 60 * 1000;
+        let active = self.context.signing_key_repo.fetch_active().await?;
+        if let Some(active) = &active
+        {
+            if active.alg == configured_algorithm()? &&
+               !active.is_due(DateTime::now(), rotation_ms) &&
+               active.key_pair(&cipher).is_ok()
+            {
+                return Ok(None);
+            }
+        }
+        Ok(Some(self.rotate(&cipher, active.as_ref()).await?.0))
+    }
+
+    /// Replaces `previous` with a new active key; `previous` stays published for
+    /// `SIGNING_KEY_RETIRE_AFTER_SECS`. A key that no longer decrypts means the master key was
+    /// changed, most likely because it leaked, so it and every older key are retired at once.
+    /// Only one key can be active, so the previous one is demoted first, and an instance that
+    /// loses a concurrent rotation signs with the key of the one that won.
+    async fn rotate(&self, cipher: &KeyCipher, previous: Option<&SigningKey>) -> Result<(SigningKey, KeyPair), SigningKeyError>
+    {
+        let now = DateTime::now();
+        match previous
+        {
+            Some(previous) if previous.key_pair(cipher).is_err() => {
+                warn!("signing key {} doesn't decrypt with SIGNING_KEY_ENCRYPTION_KEY, retiring it and older keys", previous.kid);
+                self.context.signing_key_repo.retire_up_to(previous.created_at, now).await?;
+            },
+            Some(previous) => {
+                let retire_after_ms = env_or("SIGNING_KEY_RETIRE_AFTER_SECS", DEFAULT_RETIRE_AFTER_SECS) * 1000;
+                let expires_at = DateTime::from_millis(now.timestamp_millis() + retire_after_ms);
+                self.context.signing_key_repo.demote(&previous.kid, expires_at).await?;
+            },
+            None => {},
+        }
+
+        let (signing_key, key_pair) = SigningKey::generate(configured_algorithm()?, cipher)?;
+        if let Some(signing_key) = self.context.signing_key_repo.create(signing_key).await?
+        {
+            return Ok((signing_key, key_pair));
+        }
+        let winner = self.context.signing_key_repo.fetch_active().await?.ok_or(SigningKeyError::GenerationFailed)?;
+        let key_pair = winner.key_pair(cipher)?;
+        Ok((winner, key_pair))
+    }
+}
+
+/// `SIGNING_KEY_ALG`: `RS256` (default) or `EdDSA`.
+fn configured_algorithm() -> Result<SigningAlgorithm, SigningKeyError>
+{
+    env::var("SIGNING_KEY_ALG").map_or(Ok(SigningAlgorithm::RS256), |alg| alg.trim().parse())
+}
+
+/// Checks every hour whether the signing key is due for rotation, for the life of the server.
+pub fn spawn_key_rotation_task(context: Arc<Context>)
+{
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(ROTATION_CHECK_SECS));
+        loop
+        {
+            interval.tick().await;
+            match SigningKeyOps::new(&context).rotate_if_due().await
+            {
+                Ok(Some(signing_key)) => info!("signing key rotated, new kid {} ({})", signing_key.kid, signing_key.alg),
+                Ok(None) => {},
+                Err(err) => error!("signing key rotation failed: {}", err),
+            }
+        }
+    });
+}
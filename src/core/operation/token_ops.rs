@@ -0,0 +1,52 @@
+use actix_web::HttpRequest;
+use mongodb::bson::DateTime;
+use tracing::error;
+
+use crate::context::Context;
+use crate::core::domain::auth::auth_caller::bearer_token;
+use crate::core::domain::auth::auth_type::{Claims, Token};
+use crate::core::domain::signing_key::signing_key_error::SigningKeyError;
+use crate::core::operation::signing_key_ops::SigningKeyOps;
+
+/// The access tokens this service issues: JWTs signed with the active signing key, checked
+/// against the published keys like any other holder of the JWKS would.
+pub struct TokenOps<'a>
+{
+    context: &'a Context,
+}
+
+impl<'a> TokenOps<'a>
+{
+    pub fn new(context: &'a Context) -> Self
+    {
+        Self { context }
+    }
+
+    pub async fn issue(&self, claims: &Claims) -> Result<Token, SigningKeyError>
+    {
+        let token = SigningKeyOps::new(self.context).sign_jwt(claims).await?;
+        Ok(Token { token })
+    }
+
+    /// Claims of `token` if a published key signed it and it hasn't expired.
+    pub async fn verify(&self, token: &str) -> Result<Option<Claims>, SigningKeyError>
+    {
+        let claims = SigningKeyOps::new(self.context).verify_jwt::<Claims>(token).await?;
+        let now = DateTime::now().timestamp_millis() / 1000;
+        Ok(claims.filter(|claims| claims.exp > now))
+    }
+
+    /// Whether the request's token is good and carries `permission`. A lookup failure denies.
+    pub async fn has_permission(&self, req: &HttpRequest, permission: u32) -> bool
+    {
+        let Some(token) = bearer_token(req) else { return false };
+        match self.verify(&token).await
+        {
+            Ok(claims) => claims.is_some_and(|claims| claims.permissions.contains(&permission)),
+            Err(err) => {
+                error!("token check failed: {}", err);
+                false
+            },
+        }
+    }
+}
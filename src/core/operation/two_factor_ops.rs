@@ -0,0 +1,121 @@
+use std::env;
+
+use actix_web::HttpRequest;
+use mongodb::bson::DateTime;
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_lockout::LockoutPolicy;
+use crate::core::domain::auth::auth_totp::{generate_recovery_codes, generate_secret, otpauth_uri, TwoFactor};
+use crate::core::domain::auth::Auth;
+use crate::core::domain::auth::auth_type::{AuthLogin, RecoveryCodes, TotpConfirm, TotpEnrollment, TwoFactorPolicy};
+use crate::core::domain::perm::perm_cat::UPDATE_USER_ADMINISTRATION;
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::auth_ops::AuthOps;
+use crate::core::operation::authz_ops::AuthzOps;
+use crate::data::access::auth_repo::MongoAuthRepo;
+
+pub struct TwoFactorOps<'a>
+{
+    auth_ops: AuthOps<'a>,
+    context:  &'a Context,
+}
+
+impl<'a> TwoFactorOps<'a>
+{
+    pub fn new(repo: &'a MongoAuthRepo, context: &'a Context) -> Self
+    {
+        Self { auth_ops: AuthOps::new(repo, context), context }
+    }
+
+    /// Starts enrollment with a fresh secret. The credentials are checked again because the
+    /// secret is as sensitive as the password itself.
+    pub async fn enroll(&self, auth_login: AuthLogin, client_ip: Option<String>) -> Result<TotpEnrollment, AuthError>
+    {
+        let auth = self.auth_ops.verify_credentials(auth_login, client_ip).await?;
+        if auth.two_factor.as_ref().is_some_and(|two_factor| two_factor.enabled)
+        {
+            return Err(AuthError::TwoFactorAlreadyEnabled);
+        }
+        self.auth_ops.clear_attempts(&auth).await?;
+
+        let secret = generate_secret();
+        let issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| "user".to_string());
+        let otpauth_uri = otpauth_uri(&issuer, &auth.username, &secret);
+
+        self.auth_ops.save_two_factor(&auth, Some(TwoFactor::pending(secret.clone()))).await?;
+        Ok(TotpEnrollment { secret, otpauth_uri })
+    }
+
+    /// Enables 2FA once the first code matches and hands out the recovery codes, which are only
+    /// ever shown here. Wrong codes count against the lockout like wrong passwords.
+    pub async fn confirm(&self, confirm: TotpConfirm, origin: AuditOrigin) -> Result<RecoveryCodes, AuthError>
+    {
+        let auth_login = AuthLogin { username: confirm.username, password: confirm.password };
+        let auth = self.auth_ops.verify_credentials(auth_login, origin.ip.clone()).await?;
+
+        let mut two_factor = auth.two_factor.clone().ok_or(AuthError::TwoFactorNotEnrolled)?;
+        if two_factor.enabled
+        {
+            return Err(AuthError::TwoFactorAlreadyEnabled);
+        }
+        let Some(step) = two_factor.verify_code(&confirm.code, DateTime::now().timestamp_millis() / 1000)
+        else
+        {
+            let keys = AuthOps::attempt_keys(&auth, origin.ip.clone());
+            self.auth_ops.register_failure(&keys, &LockoutPolicy::from_env()).await?;
+            return Err(AuthError::InvalidTwoFactorCode);
+        };
+        self.auth_ops.clear_attempts(&auth).await?;
+
+        let (recovery_codes, hashed_codes) = generate_recovery_codes();
+        two_factor.enabled = true;
+        two_factor.last_used_step = Some(step);
+        two_factor.recovery_codes = hashed_codes;
+
+        self.auth_ops.save_two_factor(&auth, Some(two_factor)).await?;
+        self.audit(AuditAction::TwoFactorEnabled, &origin, &auth).await;
+        Ok(RecoveryCodes { recovery_codes })
+    }
+
+    /// Takes the same proof as a login: the password and a TOTP or recovery code, throttled
+    /// and audited the same way.
+    pub async fn disable(&self, confirm: TotpConfirm, origin: AuditOrigin) -> Result<(), AuthError>
+    {
+        let auth_login = AuthLogin { username: confirm.username, password: confirm.password };
+        let auth = self.auth_ops.verify_login(auth_login, Some(&confirm.code), "totp", &origin).await?;
+        if auth.two_factor.is_none()
+        {
+            return Err(AuthError::TwoFactorNotEnrolled);
+        }
+
+        self.auth_ops.save_two_factor(&auth, None).await?;
+        self.audit(AuditAction::TwoFactorDisabled, &origin, &auth).await;
+        Ok(())
+    }
+
+    pub async fn set_role_policy(&self, req: HttpRequest, policy: TwoFactorPolicy) -> Result<TwoFactorPolicy, AuthError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !AuthzOps::new(self.context).allows(req, UPDATE_USER_ADMINISTRATION, None).await
+        {
+            return Err(AuthError::Unauthorized);
+        }
+
+        let policy = self.context.two_factor_policy_repo.save(policy).await?;
+        let entry = AuditEntry::new(AuditAction::TwoFactorPolicyChanged, &origin).with_target(&policy.role)
+                                                                                 .with_detail(format!("required: {}", policy.required));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(policy)
+    }
+
+    /// The account changing its own second factor is both actor and target.
+    async fn audit(&self, action: AuditAction, origin: &AuditOrigin, auth: &Auth)
+    {
+        let entry = AuditEntry::new(action, origin).with_actor(&auth.user_id)
+                                                   .with_target(&auth.user_id);
+        AuditOps::new(self.context).record(entry).await;
+    }
+}
@@ -1,24 +1,51 @@
-use actix_web::HttpRequest;
-use bcrypt::{hash};
-use perms::has_permission;
+use std::collections::HashSet;
+
+use actix_web::{web::Bytes, HttpRequest};
+use futures_util::{stream::{self, BoxStream}, Stream, StreamExt, TryStreamExt};
+use mongodb::bson::DateTime;
+use serde_json::{json, Value};
 use crate::{
     core::domain::{
         auth::{auth_type::Role},
         perm::{perm_repo::PermRepo},
         user::{
-            user_type::{NewUser},
+            user_type::{ImportReport, LegacyUser, NewUser},
         },
     },
 };
 use crate::context::Context;
+use crate::core::domain::audit::audit_type::{AuditAction, AuditDiff, AuditOrigin};
+use crate::core::domain::audit::AuditEntry;
 use crate::core::domain::auth::{Auth, AuthEntity};
-use crate::core::domain::perm::perm_cat::READ_USER;
+use crate::core::domain::auth::auth_caller::caller_subject;
+use crate::core::domain::auth::auth_password::Passwords;
+use crate::core::domain::authz::authz_resource::ResourceGrant;
+use crate::core::domain::perm::perm_cat::{CHANGE_ROLE, CREATE_USER, DELETE_USER, READ_USER, UPDATE_USER_ADMINISTRATION};
 use crate::core::domain::user::{User, UserEntity};
+use crate::core::domain::user::user_deletion::RetentionPolicy;
 use crate::core::domain::user::user_error::UserError;
+use crate::core::domain::user::user_status::StatusChange;
+use crate::core::domain::user::user_query::{UserListQuery, UserPage, UserSearchHit, UserSearchQuery};
+use crate::core::domain::user::user_transfer::{csv_header, encode, ImportUser, RecordParser, RecordSplitter, TransferFormat};
+use crate::core::operation::audit_ops::AuditOps;
+use crate::core::operation::authz_ops::AuthzOps;
 use crate::data::access::auth_repo::MongoAuthRepo;
 use crate::data::access::perms_repo::MongoPermRepo;
 use crate::data::access::user_repo::MongoUserRepo;
 use crate::utils::domains_ids::UserID;
+use crate::utils::identifiers::{new_username, normalize_email};
+
+/// State carried across the rows of one bulk import.
+struct BulkImport
+{
+    passwords:        Passwords,
+    parser:           RecordParser,
+    can_assign_roles: bool,
+    dry_run:          bool,
+    seen:             HashSet<String>,
+    report:           ImportReport,
+    origin:           AuditOrigin,
+}
 
 pub struct UserOps<'a>
 {
@@ -40,17 +67,200 @@ impl<'a> UserOps<'a>
         }
     }
     
-    pub async fn create_user(&self, new_user: NewUser, public: bool) -> Result<User, UserError>
+    pub async fn create_user(&self, new_user: NewUser, public: bool, origin: AuditOrigin) -> Result<User, UserError>
+    {
+        let password = Passwords::from_env().hash_async(&new_user.password)
+                                            .await
+                                            .map_err(|_err| UserError::HashPasswordError)?;
+        let role = if public { Role::Client } else { Role::SuperAdmin };
+
+        self.create_account(new_user, password, role, &origin).await
+    }
+
+    /// Creates accounts moved over from other systems, keeping their password hashes as they were.
+    /// Each one is upgraded to the current scheme on its first successful login.
+    pub async fn import_legacy_users(&self, req: HttpRequest, rows: Vec<LegacyUser>) -> Result<ImportReport, UserError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !self.allows(req.clone(), CREATE_USER).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+        let can_assign_roles = self.allows(req, CHANGE_ROLE).await;
+        let passwords = Passwords::from_env();
+
+        let mut report = ImportReport::default();
+        for (index, row) in rows.into_iter().enumerate()
+        {
+            let username = row.username.clone();
+            let result = self.import_legacy_user(&passwords, row, can_assign_roles, &origin)
+                             .await
+                             .map(|user| user._id);
+            report.push(index + 1, username, result);
+        }
+
+        self.audit_import(&origin, "legacy", &report).await;
+        Ok(report)
+    }
+
+    async fn import_legacy_user(&self, passwords: &Passwords, row: LegacyUser, can_assign_roles: bool, origin: &AuditOrigin)
+                                -> Result<User, UserError>
+    {
+        let role = row.role.unwrap_or(Role::Client);
+        if role != Role::Client && !can_assign_roles
+        {
+            return Err(UserError::NotHasPermission);
+        }
+
+        let hasher = passwords.hasher(&row.algorithm)
+                              .ok_or_else(|| UserError::UnsupportedPasswordHash(row.algorithm.clone()))?;
+        let password = hasher.tag(row.password_hash.trim(), row.salt.as_deref());
+        if !hasher.recognizes(&password)
+        {
+            return Err(UserError::InvalidPasswordHash(row.algorithm));
+        }
+
+        let new_user = NewUser { username: row.username, email: row.email, password: String::new(), name: row.name };
+        self.create_account(new_user, password, role, origin).await
+    }
+
+    /// Creates users from a CSV or NDJSON body as it streams in. Every row goes through the same
+    /// checks as `create_user`; with `dry_run` nothing is written and the report says what would
+    /// have happened.
+    pub async fn import_users<S, E>(&self, req: HttpRequest, format: TransferFormat, dry_run: bool, mut body: S) -> Result<ImportReport, UserError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
     {
+        let origin = AuditOrigin::from_request(&req);
+        if !self.allows(req.clone(), CREATE_USER).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+        let mut import = BulkImport { passwords:        Passwords::from_env(),
+                                      parser:           RecordParser::new(format),
+                                      can_assign_roles: self.allows(req, CHANGE_ROLE).await,
+                                      dry_run,
+                                      seen:             HashSet::new(),
+                                      report:           ImportReport { dry_run, ..ImportReport::default() },
+                                      origin, };
+
+        let mut splitter = RecordSplitter::new(format);
+        while let Some(chunk) = body.next().await
+        {
+            let chunk = chunk.map_err(|_| UserError::ImportReadError)?;
+            for record in splitter.push(&chunk)?
+            {
+                self.import_record(&mut import, &record).await;
+            }
+        }
+        if let Some(record) = splitter.finish()
+        {
+            self.import_record(&mut import, &record).await;
+        }
+
+        if !dry_run
+        {
+            self.audit_import(&import.origin, "bulk", &import.report).await;
+        }
+        Ok(import.report)
+    }
+
+    async fn import_record(&self, import: &mut BulkImport, record: &[u8])
+    {
+        let Some(parsed) = import.parser.parse(record) else { return };
+        let row = import.report.rows.len() + 1;
+        match parsed
+        {
+            Ok(user) => {
+                let username = user.username.clone();
+                let result = self.import_user(import, user).await;
+                import.report.push(row, username, result);
+            },
+            Err(err) => import.report.push(row, String::new(), Err(UserError::InvalidImportRow(err))),
+        }
+    }
+
+    async fn import_user(&self, import: &mut BulkImport, row: ImportUser) -> Result<Option<UserID>, UserError>
+    {
+        let role = row.role.unwrap_or(Role::Client);
+        if role != Role::Client && !import.can_assign_roles
+        {
+            return Err(UserError::NotHasPermission);
+        }
+
+        let new_user = self.validate_new_user(NewUser { username: row.username,
+                                                        email:    row.email,
+                                                        password: row.password,
+                                                        name:     row.name, })
+                           .await?;
+        // A dry run writes nothing, so repeats within the file have to be caught here.
+        if !import.seen.insert(format!("username:{}", new_user.username))
+        {
+            return Err(UserError::AlreadyExists);
+        }
+        if !import.seen.insert(format!("email:{}", new_user.email))
+        {
+            return Err(UserError::EmailIsUsed);
+        }
+        if import.dry_run
+        {
+            return Ok(None);
+        }
+
+        let password = import.passwords
+                             .hash_async(&new_user.password)
+                             .await
+                             .map_err(|_err| UserError::HashPasswordError)?;
+        let user = self.insert_account(new_user, password, role, &import.origin).await?;
+        Ok(user._id)
+    }
+
+    /// Streams every user as CSV or NDJSON without holding the collection in memory.
+    pub async fn export_users(&self, req: HttpRequest, format: TransferFormat) -> Result<BoxStream<'static, Result<Bytes, UserError>>, UserError>
+    {
+        if !self.allows(req, READ_USER).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+
+        let users = self.repo
+                        .stream_export()
+                        .await?
+                        .and_then(move |user| async move { encode(format, &user).map(Bytes::from) });
+        let header = match format
+        {
+            TransferFormat::Csv => Some(Ok(Bytes::from_static(csv_header()))),
+            TransferFormat::Ndjson => None,
+        };
+        Ok(stream::iter(header).chain(users).boxed())
+    }
+
+    /// Validates the identifiers and creates the `User` + `Auth` pair around an already hashed password.
+    async fn create_account(&self, new_user: NewUser, password: String, role: Role, origin: &AuditOrigin) -> Result<User, UserError>
+    {
+        let new_user = self.validate_new_user(new_user).await?;
+        self.insert_account(new_user, password, role, origin).await
+    }
+
+    /// Normalizes the email and username and checks neither is taken.
+    async fn validate_new_user(&self, mut new_user: NewUser) -> Result<NewUser, UserError>
+    {
+        new_user.email = normalize_email(&new_user.email).ok_or(UserError::IncorrectFormatEmail)?;
+        new_user.username = new_username(&new_user.username).ok_or(UserError::InvalidUsername)?;
+
         if self.repo.fetch_by_email(new_user.email.clone()).await.is_ok()
         {
             return Err(UserError::EmailIsUsed)
         }
-        if !new_user.email.contains('@')
+        if self.auth_repo.fetch_by_username(new_user.username.clone()).await.is_ok()
         {
-            return Err(UserError::IncorrectFormatEmail)
+            return Err(UserError::AlreadyExists)
         }
-        
+        Ok(new_user)
+    }
+
+    async fn insert_account(&self, new_user: NewUser, password: String, role: Role, origin: &AuditOrigin) -> Result<User, UserError>
+    {
         let user_entity = UserEntity::new(new_user.clone(), self.repo).await;
         let user = user_entity.create().await?;
         let user_id = match user.clone()._id
@@ -59,17 +269,6 @@ impl<'a> UserOps<'a>
             None => return Err(UserError::InvalidUserId)
         };
         
-        let password = hash(new_user.password, 10).map_err(|_err| UserError::HashPasswordError)?;
-        let role;
-        if public
-        {
-            role = Role::Client;
-        }
-        else{
-            role = Role::SuperAdmin;
-        }
-        
-        
         let perms = self.perm_repo
             .charge_permissions(role.to_string(),self.context)
             .await
@@ -78,39 +277,220 @@ impl<'a> UserOps<'a>
         
         let auth = Auth{
             _id: None,
-            user_id,
+            user_id: user_id.clone(),
             username: user.username.clone(),
             email: user.email.clone(),
             password ,
-            roles: role,
+            roles: role.clone(),
             permissions: perms,
+            two_factor: None,
+            passkeys: Vec::new(),
+            status: user.status,
+            suspension: None,
+            resource_grants: Vec::new(),
         };
         
         let auth_entity = AuthEntity::new(auth, self.auth_repo).await;
         auth_entity.create()
             .await
             .map_err(|_| UserError::AuthError)?;
-        
+
+        let created = json!({ "role": role, "status": user.status });
+        let entry = AuditEntry::new(AuditAction::UserCreated, origin).with_target(&user_id)
+                                                                     .with_diff(AuditDiff::created(&created));
+        AuditOps::new(self.context).record(entry).await;
 
         Ok(user)
     }
+
+    async fn audit_import(&self, origin: &AuditOrigin, kind: &str, report: &ImportReport)
+    {
+        let detail = format!("{} import: {} imported, {} failed", kind, report.imported, report.failed);
+        AuditOps::new(self.context).record(AuditEntry::new(AuditAction::UsersImported, origin).with_detail(detail)).await;
+    }
     
 
-    pub async fn load_users(&self, req: HttpRequest) -> Result<Vec<User>, UserError>
+    pub async fn load_users(&self, req: HttpRequest, query: UserListQuery) -> Result<UserPage, UserError>
     {
-        if !has_permission(req, READ_USER).await
+        if !self.allows(req, READ_USER).await
         {
             return Err(UserError::NotHasPermission);
         }
 
-        let users = self.repo.fetch_all().await?;
+        let users = self.repo.fetch_page(&query).await?;
         Ok(users)
     }
     
+    pub async fn search_users(&self, req: HttpRequest, query: UserSearchQuery) -> Result<Vec<UserSearchHit>, UserError>
+    {
+        if !self.allows(req, READ_USER).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+
+        self.repo.search(query.terms()?, query.limit()).await
+    }
+
+    /// Moves an account through its lifecycle. The `auth` document is updated first since it is
+    /// the one login checks.
+    pub async fn change_status(&self, req: HttpRequest, user_id: UserID, change: StatusChange) -> Result<User, UserError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !self.allows_on(req, UPDATE_USER_ADMINISTRATION, &user_id).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+
+        let mut user = self.repo.fetch_by_id(user_id.clone()).await?;
+        let status = user.status.apply(&change)?;
+
+        let auth = self.auth_repo.fetch_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+        let auth_id = auth._id.clone().ok_or(UserError::AuthError)?;
+        let mut auth_entity = AuthEntity::new(auth, self.auth_repo).await;
+        auth_entity.update_id(auth_id).await;
+        auth_entity.update_status(status, change.suspension()).await;
+        auth_entity.save().await.map_err(|_| UserError::AuthError)?;
+
+        self.repo.update_status(user_id.clone(), status).await?;
+        let mut entry = AuditEntry::new(AuditAction::UserStatusChanged, &origin)
+            .with_target(&user_id)
+            .with_diff(AuditDiff::between(&json!({ "status": user.status }), &json!({ "status": status })));
+        if let Some(suspension) = change.suspension()
+        {
+            entry = entry.with_detail(suspension.reason);
+        }
+        AuditOps::new(self.context).record(entry).await;
+
+        user.status = status;
+        Ok(user)
+    }
+
+    /// Soft-deletes the user and its auth record; both stay restorable until the purge job runs.
+    pub async fn delete_user(&self, req: HttpRequest, user_id: UserID) -> Result<(), UserError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        let deleted_by = caller_subject(&req);
+        if !self.allows_on(req, DELETE_USER, &user_id).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+
+        let user = self.repo.fetch_by_id(user_id.clone()).await?;
+        if let Some(auth_id) = self.auth_repo.fetch_by_user_id(user_id.clone()).await.ok().and_then(|auth| auth._id)
+        {
+            self.auth_repo.delete(auth_id, deleted_by.clone()).await.map_err(|_| UserError::AuthError)?;
+        }
+        self.repo.delete(user_id.clone(), deleted_by).await?;
+
+        let entry = AuditEntry::new(AuditAction::UserDeleted, &origin).with_target(&user_id)
+                                                                      .with_diff(AuditDiff::removed(&audit_view(&user)));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(())
+    }
+
+    pub async fn restore_user(&self, req: HttpRequest, user_id: UserID) -> Result<User, UserError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !self.allows_on(req, DELETE_USER, &user_id).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+
+        let deleted = self.repo.fetch_deleted(user_id.clone()).await?;
+        let auth = self.auth_repo.fetch_deleted_by_user_id(user_id.clone()).await.map_err(|_| UserError::AuthError)?;
+        let identity = deleted.deleted_identity.clone();
+        let email_taken = self.repo.fetch_by_email(identity.email.clone()).await.is_ok()
+                          || self.auth_repo.fetch_by_email(identity.email).await.is_ok();
+        let username_taken = self.auth_repo.fetch_by_username(identity.username).await.is_ok();
+        deleted.check_restorable(&RetentionPolicy::from_env(), DateTime::now(), email_taken, username_taken)?;
+
+        if let Some(auth_id) = auth.and_then(|auth| auth._id)
+        {
+            self.auth_repo.restore(auth_id).await.map_err(|_| UserError::AuthError)?;
+        }
+        self.repo.restore(user_id.clone()).await?;
+
+        let user = deleted.into_user();
+        let entry = AuditEntry::new(AuditAction::UserRestored, &origin).with_target(&user_id)
+                                                                       .with_diff(AuditDiff::created(&audit_view(&user)));
+        AuditOps::new(self.context).record(entry).await;
+        Ok(user)
+    }
+
     pub async fn load_user_by_id(&self, id: UserID) -> Result<User, UserError>
     {
         let user = self.repo.fetch_by_id(id).await?;
         Ok(user)
     }
+
+    /// Adds a permission on some resources to the user, on top of what their role grants.
+    pub async fn add_resource_grant(&self, req: HttpRequest, user_id: UserID, grant: ResourceGrant) -> Result<Vec<ResourceGrant>, UserError>
+    {
+        grant.validate().map_err(UserError::InvalidResourceGrant)?;
+        let add = |grants: &mut Vec<ResourceGrant>| {
+            if !grants.contains(&grant)
+            {
+                grants.push(grant);
+            }
+        };
+        self.change_resource_grants(req, user_id, add).await
+    }
+
+    pub async fn remove_resource_grant(&self, req: HttpRequest, user_id: UserID, grant: ResourceGrant) -> Result<Vec<ResourceGrant>, UserError>
+    {
+        self.change_resource_grants(req, user_id, |grants| grants.retain(|held| *held != grant)).await
+    }
+
+    /// Grants are handed out by those who may change roles, and only with that permission held
+    /// outright.
+    async fn change_resource_grants(&self, req: HttpRequest, user_id: UserID, change: impl FnOnce(&mut Vec<ResourceGrant>))
+                                    -> Result<Vec<ResourceGrant>, UserError>
+    {
+        let origin = AuditOrigin::from_request(&req);
+        if !self.allows(req, CHANGE_ROLE).await
+        {
+            return Err(UserError::NotHasPermission);
+        }
+
+        let auth = self.auth_repo.fetch_by_user_id(user_id.clone()).await.map_err(|_| UserError::UserNotFound)?;
+        let auth_id = auth._id.clone().ok_or(UserError::AuthError)?;
+        let before = auth.resource_grants.clone();
+        let mut grants = before.clone();
+        change(&mut grants);
+        if grants == before
+        {
+            return Ok(grants);
+        }
+
+        let mut auth_entity = AuthEntity::new(auth, self.auth_repo).await;
+        auth_entity.update_id(auth_id).await;
+        auth_entity.update_resource_grants(grants.clone()).await;
+        auth_entity.save().await.map_err(|_| UserError::AuthError)?;
+
+        let diff = AuditDiff::between(&json!({ "resource_grants": before }), &json!({ "resource_grants": grants }));
+        let entry = AuditEntry::new(AuditAction::PermissionsChanged, &origin).with_target(&user_id)
+                                                                             .with_diff(diff)
+                                                                             .with_detail("resource grants");
+        AuditOps::new(self.context).record(entry).await;
+        Ok(grants)
+    }
+
+    async fn allows(&self, req: HttpRequest, permission: u32) -> bool
+    {
+        AuthzOps::new(self.context).allows(req, permission, None).await
+    }
+
+    /// Also lets through callers allowed on this user only, by a resource grant or a policy.
+    async fn allows_on(&self, req: HttpRequest, permission: u32, user_id: &UserID) -> bool
+    {
+        AuthzOps::new(self.context).allows_on(req, permission, user_id).await
+    }
           
 }
+
+/// What the audit log keeps of a user. The entry's target is the user id; names and contact
+/// details stay out of the chain, which can't be edited when the user is erased.
+fn audit_view(user: &User) -> Value
+{
+    json!({ "status": user.status })
+}
@@ -1,4 +1,27 @@
+pub mod access_token_repo;
+pub mod audit_checkpoint_repo;
+pub mod audit_repo;
 pub mod auth_repo;
+pub mod login_attempt_repo;
+pub mod login_challenge_repo;
+pub mod oauth_client_repo;
+pub mod oauth_code_repo;
+pub mod oauth_consent_repo;
+pub mod oauth_refresh_token_repo;
 pub mod perms_repo;
+pub mod service_account_repo;
+pub mod service_account_token_repo;
+pub mod session_repo;
+pub mod signing_key_repo;
+pub mod two_factor_policy_repo;
 pub mod user_repo;
+pub mod webauthn_challenge_repo;
 pub mod migration;
+
+use mongodb::error::{Error as MongoError, ErrorKind, WriteFailure};
+
+/// Whether a write failed on a unique index.
+pub(crate) fn is_duplicate_key(err: &MongoError) -> bool
+{
+    matches!(err.kind.as_ref(), ErrorKind::Write(WriteFailure::WriteError(write_err)) if write_err.code == 11000)
+}
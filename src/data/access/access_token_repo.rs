@@ -0,0 +1,82 @@
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::auth::{auth_access_token::AccessToken, auth_error::AuthError};
+use crate::utils::domains_ids::{AuthID, UserID};
+
+#[derive(Clone)]
+pub struct MongoAccessTokenRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoAccessTokenRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, access_token: AccessToken) -> Result<AccessToken, AuthError>
+    {
+        let mut token_doc = to_document(&access_token).map_err(|_| AuthError::AuthDocumentNotCreated)?;
+        token_doc.remove("_id");
+        let inserted = self.collection
+                           .insert_one(token_doc)
+                           .await?;
+        Ok(AccessToken { _id: inserted.inserted_id.as_object_id(), ..access_token })
+    }
+
+    pub async fn fetch_by_token_hash(&self, token_hash: &str) -> Result<AccessToken, AuthError>
+    {
+        let token_doc = self.collection
+                            .find_one(doc! { "token_hash": token_hash })
+                            .await?
+                            .ok_or(AuthError::AccessTokenNotFound)?;
+        from_document(token_doc).map_err(|_| AuthError::AccessTokenNotFound)
+    }
+
+    pub async fn fetch_by_auth_id(&self, auth_id: AuthID) -> Result<Vec<AccessToken>, AuthError>
+    {
+        let token_docs: Vec<Document> = self.collection
+                                            .find(doc! { "auth_id": ObjectId::from(auth_id) })
+                                            .sort(doc! { "created_at": -1 })
+                                            .await?
+                                            .try_collect()
+                                            .await?;
+        token_docs.into_iter()
+                  .map(|token_doc| from_document(token_doc).map_err(|_| AuthError::AccessTokenNotFound))
+                  .collect()
+    }
+
+    /// Only rewrites a `last_used_at` older than `stale`, so racing requests write it once.
+    pub async fn touch(&self, id: ObjectId, now: DateTime, stale: DateTime) -> Result<(), AuthError>
+    {
+        self.collection
+            .update_one(doc! { "_id": id, "$or": [{ "last_used_at": null }, { "last_used_at": { "$lt": stale } }] },
+                        doc! { "$set": { "last_used_at": now } })
+            .await?;
+        Ok(())
+    }
+
+    /// Only revokes a token of `auth_id`.
+    pub async fn revoke(&self, id: ObjectId, auth_id: AuthID) -> Result<bool, AuthError>
+    {
+        let result = self.collection
+                         .update_one(doc! { "_id": id, "auth_id": ObjectId::from(auth_id), "revoked_at": null },
+                                     doc! { "$set": { "revoked_at": DateTime::now() } })
+                         .await?;
+        Ok(result.modified_count > 0)
+    }
+
+    pub async fn delete_by_user_id(&self, user_id: UserID) -> Result<u64, AuthError>
+    {
+        let result = self.collection
+                         .delete_many(doc! { "user_id": ObjectId::from(user_id) })
+                         .await?;
+        Ok(result.deleted_count)
+    }
+}
@@ -0,0 +1,54 @@
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, to_document, Document},
+    Collection,
+};
+
+use crate::core::domain::audit::{audit_chain::Checkpoint, audit_error::AuditError};
+
+#[derive(Clone)]
+pub struct MongoAuditCheckpointRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoAuditCheckpointRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, checkpoint: &Checkpoint) -> Result<(), AuditError>
+    {
+        let mut checkpoint_doc = to_document(checkpoint).map_err(|_| AuditError::EncodingError)?;
+        checkpoint_doc.remove("_id");
+        self.collection
+            .insert_one(checkpoint_doc)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_latest(&self) -> Result<Option<Checkpoint>, AuditError>
+    {
+        let checkpoint_doc = self.collection
+                                 .find_one(doc! {})
+                                 .sort(doc! { "seq": -1 })
+                                 .await?;
+        checkpoint_doc.map(|checkpoint_doc| from_document(checkpoint_doc).map_err(|_| AuditError::DecodingError))
+                      .transpose()
+    }
+
+    pub async fn fetch_all(&self) -> Result<Vec<Checkpoint>, AuditError>
+    {
+        let checkpoint_docs: Vec<Document> = self.collection
+                                                 .find(doc! {})
+                                                 .sort(doc! { "seq": 1 })
+                                                 .await?
+                                                 .try_collect()
+                                                 .await?;
+        checkpoint_docs.into_iter()
+                       .map(|checkpoint_doc| from_document(checkpoint_doc).map_err(|_| AuditError::DecodingError))
+                       .collect()
+    }
+}
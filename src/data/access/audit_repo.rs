@@ -0,0 +1,124 @@
+use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
+use mongodb::{
+    bson::{doc, from_document, to_document, Document},
+    Collection,
+};
+
+use crate::core::domain::audit::{
+    audit_chain::GENESIS_HASH,
+    audit_error::AuditError,
+    audit_query::{involving, AuditPage, AuditQuery, AuditRecord},
+    AuditEntry,
+};
+use crate::data::access::is_duplicate_key;
+
+/// Retries when concurrent writers race for the same chain position.
+const APPEND_ATTEMPTS: usize = 5;
+
+/// Insert-only access to `audit_log`; there is deliberately no update or delete.
+#[derive(Clone)]
+pub struct MongoAuditRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoAuditRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    /// Links the entry after the current head and inserts it. `seq` is unique, so when another
+    /// writer takes the same position first the insert fails and is retried on the new head.
+    pub async fn append(&self, entry: &AuditEntry) -> Result<AuditEntry, AuditError>
+    {
+        let mut entry = entry.clone();
+        for _ in 0..APPEND_ATTEMPTS
+        {
+            let (seq, prev_hash) = match self.fetch_head().await?
+            {
+                Some(head) => (head.seq + 1, head.hash),
+                None => (1, GENESIS_HASH.to_string()),
+            };
+            entry.link(seq, prev_hash);
+
+            let mut entry_doc = to_document(&entry).map_err(|_| AuditError::EncodingError)?;
+            entry_doc.remove("_id");
+            match self.collection.insert_one(entry_doc).await
+            {
+                Ok(inserted) => {
+                    entry._id = inserted.inserted_id.as_object_id();
+                    return Ok(entry);
+                },
+                Err(err) if is_duplicate_key(&err) => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(AuditError::ChainContention)
+    }
+
+    pub async fn fetch_head(&self) -> Result<Option<AuditEntry>, AuditError>
+    {
+        let head_doc = self.collection
+                           .find_one(doc! { "seq": { "$gt": 0 } })
+                           .sort(doc! { "seq": -1 })
+                           .await?;
+        head_doc.map(|head_doc| from_document(head_doc).map_err(|_| AuditError::DecodingError))
+                .transpose()
+    }
+
+    /// Every entry in chain order, read lazily so verification doesn't hold the log in memory.
+    pub async fn stream_chain(&self) -> Result<BoxStream<'static, Result<AuditEntry, AuditError>>, AuditError>
+    {
+        let cursor = self.collection
+                         .find(doc! {})
+                         .sort(doc! { "seq": 1, "_id": 1 })
+                         .await?;
+        Ok(cursor.map_err(AuditError::from)
+                 .and_then(|entry_doc| async move { from_document(entry_doc).map_err(|_| AuditError::DecodingError) })
+                 .boxed())
+    }
+
+    /// Newest first; ids grow with insertion time so `_id` doubles as the page cursor.
+    pub async fn fetch_page(&self, query: &AuditQuery) -> Result<AuditPage, AuditError>
+    {
+        let limit = query.page_size();
+        let mut docs: Vec<Document> = self.collection
+                                          .find(query.filter()?)
+                                          .sort(doc! { "_id": -1 })
+                                          .limit(i64::from(limit) + 1)
+                                          .await?
+                                          .try_collect()
+                                          .await?;
+
+        let mut next_cursor = None;
+        if docs.len() > limit as usize
+        {
+            docs.truncate(limit as usize);
+            next_cursor = docs.last().and_then(|last| last.get_object_id("_id").ok()).map(|id| id.to_hex());
+        }
+        let items = docs.into_iter()
+                        .map(|entry_doc| {
+                            from_document::<AuditEntry>(entry_doc).map(AuditRecord::from)
+                                                                  .map_err(|_| AuditError::DecodingError)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(AuditPage { items, next_cursor })
+    }
+
+    /// Every entry `subject` acted in or was the target of, oldest first.
+    pub async fn fetch_involving(&self, subject: &str) -> Result<Vec<AuditRecord>, AuditError>
+    {
+        let docs: Vec<Document> = self.collection
+                                      .find(involving(subject))
+                                      .sort(doc! { "_id": 1 })
+                                      .await?
+                                      .try_collect()
+                                      .await?;
+        docs.into_iter()
+            .map(|entry_doc| from_document::<AuditEntry>(entry_doc).map(AuditRecord::from).map_err(|_| AuditError::DecodingError))
+            .collect()
+    }
+}
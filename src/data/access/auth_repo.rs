@@ -2,7 +2,7 @@
 use async_trait::async_trait;
 use futures_util::TryStreamExt;
 use mongodb::{ bson::{doc, oid::ObjectId, Document}, Collection};
-use mongodb::bson::{from_document, to_document};
+use mongodb::bson::{from_document, to_document, Bson, DateTime};
 use crate::core::domain::{
     auth::{
         auth_repo::AuthRepo,
@@ -10,7 +10,10 @@ use crate::core::domain::{
 };
 use crate::core::domain::auth::Auth;
 use crate::core::domain::auth::auth_error::AuthError;
-use crate::utils::domains_ids::AuthID;
+use crate::core::domain::auth::auth_webauthn::PasskeyCredential;
+use crate::core::domain::user::user_deletion::{restore_update, soft_delete_update, tombstone};
+use crate::utils::domains_ids::{AuthID, UserID};
+use crate::utils::identifiers::{is_email, normalize_identifier};
 
 #[derive(Clone)]
 pub struct MongoAuthRepo {
@@ -41,6 +44,11 @@ impl MongoAuthRepo {
                 password: new_auth.password,
                 roles: new_auth.roles,
                 permissions: new_auth.permissions,
+                two_factor: new_auth.two_factor,
+                passkeys: new_auth.passkeys,
+                status: new_auth.status,
+                suspension: new_auth.suspension,
+                resource_grants: new_auth.resource_grants,
             })
         }
         else
@@ -50,7 +58,7 @@ impl MongoAuthRepo {
     }
     pub async fn fetch_all(&self) -> Result<Vec<Auth>, AuthError>
     {
-        let filter = doc! {};
+        let filter = live(doc! {});
         let mut cursor = self.collection
             .find(filter)
             .await
@@ -69,7 +77,7 @@ impl MongoAuthRepo {
 
     pub async fn fetch_all_actives(&self) -> Result<Vec<Auth>, AuthError>
     {
-        let filter = doc! {"status": "Active"};
+        let filter = live(doc! {"status": "Active"});
         let mut cursor = self.collection
             .find(filter)
             .await
@@ -89,7 +97,7 @@ impl MongoAuthRepo {
     pub async fn fetch_by_id(&self, id: AuthID) -> Result<Auth, AuthError>
     {
         let collection = &self.collection;
-        let filter = doc! { "_id": ObjectId::from(id)};
+        let filter = live(doc! { "_id": ObjectId::from(id)});
         let auth_doc = collection.find_one(filter)
             .await
             .map_err(|_| AuthError::AuthNotFound)?
@@ -106,7 +114,7 @@ impl MongoAuthRepo {
         {
             let auth_doc = to_document(&auth).map_err(|_| AuthError::AuthDocumentNotCreated)?;
 
-            let filter = doc! { "_id": ObjectId::from(auth_id.clone()) };
+            let filter = live(doc! { "_id": ObjectId::from(auth_id.clone()) });
             let update_result = collection.update_one(filter, doc! { "$set": auth_doc })
                 .await;
 
@@ -132,7 +140,41 @@ impl MongoAuthRepo {
         }
     }
 
-    pub async fn delete (&self, id: AuthID) -> Result<(), AuthError>
+    /// Marks the auth record as deleted so the account can no longer log in; see `MongoUserRepo::delete`.
+    pub async fn delete (&self, id: AuthID, deleted_by: Option<String>) -> Result<(), AuthError>
+    {
+        let id = ObjectId::from(id);
+        let filter = live(doc! { "_id": id });
+        let update = soft_delete_update(&tombstone(&id.to_hex()), DateTime::now(), deleted_by);
+        let result = self.collection.update_one(filter, update).await?;
+        if result.matched_count == 0
+        {
+            return Err(AuthError::AuthNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn fetch_deleted_by_user_id(&self, user_id: UserID) -> Result<Option<Auth>, AuthError>
+    {
+        let filter = doc! { "user_id": ObjectId::from(user_id), "deleted_at": { "$type": "date" } };
+        let auth_doc = self.collection.find_one(filter).await?;
+        auth_doc.map(|auth_doc| from_document(auth_doc).map_err(|_| AuthError::AuthNotFound))
+                .transpose()
+    }
+
+    pub async fn restore(&self, id: AuthID) -> Result<(), AuthError>
+    {
+        let filter = doc! { "_id": ObjectId::from(id), "deleted_at": { "$type": "date" } };
+        let result = self.collection.update_one(filter, restore_update()).await?;
+        if result.matched_count == 0
+        {
+            return Err(AuthError::AuthNotFound);
+        }
+        Ok(())
+    }
+
+    /// Removes the document for good; only the purge job calls this.
+    pub async fn purge (&self, id: AuthID) -> Result<(), AuthError>
     {
         let collection = &self.collection;
         let filter = doc! { "_id": ObjectId::from(id) };
@@ -155,10 +197,22 @@ impl MongoAuthRepo {
         }
     }
 
+    pub async fn fetch_by_user_id(&self, user_id: UserID) -> Result<Auth, AuthError>
+    {
+        let filter = live(doc! { "user_id": ObjectId::from(user_id) });
+        let auth_doc = self.collection.find_one(filter)
+            .await
+            .map_err(|_| AuthError::AuthNotFound)?
+            .ok_or(AuthError::AuthNotFound)?;
+
+        let auth: Auth = from_document(auth_doc).map_err(|_| AuthError::AuthNotFound)?;
+        Ok(auth)
+    }
+
     pub async fn fetch_by_username(&self, username: String) -> Result<Auth, AuthError>
     {
         let collection = &self.collection;
-        let filter = doc! { "username": username};
+        let filter = live(doc! { "username": username});
         let auth_doc = collection.find_one(filter)
             .await
             .map_err(|_| AuthError::AuthNotFound)?
@@ -168,10 +222,86 @@ impl MongoAuthRepo {
         Ok(auth)
     }
 
+    /// Looks an account up by username or email, comparing canonical forms.
+    pub async fn fetch_by_identifier(&self, identifier: &str) -> Result<Auth, AuthError>
+    {
+        let normalized = normalize_identifier(identifier);
+        if is_email(&normalized)
+        {
+            self.fetch_by_email(normalized).await
+        }
+        else
+        {
+            self.fetch_by_username(normalized).await
+        }
+    }
+
+    pub async fn fetch_by_credential_id(&self, credential_id: String) -> Result<Auth, AuthError>
+    {
+        let collection = &self.collection;
+        let filter = live(doc! { "passkeys.credential_id": credential_id});
+        let auth_doc = collection.find_one(filter)
+            .await
+            .map_err(|_| AuthError::AuthNotFound)?
+            .ok_or(AuthError::PasskeyNotFound)?;
+
+        let auth: Auth = from_document(auth_doc).map_err(|_| AuthError::AuthNotFound)?;
+        Ok(auth)
+    }
+
+    /// Adds a passkey without rewriting the others, so a login updating a counter at the same
+    /// time isn't lost.
+    pub async fn add_passkey(&self, id: AuthID, credential: &PasskeyCredential) -> Result<(), AuthError>
+    {
+        let credential_doc = to_document(credential).map_err(|_| AuthError::InternalServerError)?;
+        let filter = live(doc! { "_id": ObjectId::from(id) });
+        let update = doc! { "$push": { "passkeys": credential_doc } };
+        let result = self.collection.update_one(filter, update).await?;
+        if result.matched_count == 0
+        {
+            return Err(AuthError::AuthNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn remove_passkey(&self, id: AuthID, credential_id: &str) -> Result<(), AuthError>
+    {
+        let filter = live(doc! { "_id": ObjectId::from(id), "passkeys.credential_id": credential_id });
+        let update = doc! { "$pull": { "passkeys": { "credential_id": credential_id } } };
+        let result = self.collection.update_one(filter, update).await?;
+        if result.matched_count == 0
+        {
+            return Err(AuthError::PasskeyNotFound);
+        }
+        Ok(())
+    }
+
+    /// Stores the counter of a verified assertion, but only while it is still ahead of the stored
+    /// one: of two logins racing with the same counter, one gets `false`. Authenticators without a
+    /// counter always report 0 and are only matched while the stored count is 0 as well.
+    pub async fn record_passkey_use(&self, id: AuthID, credential_id: &str, sign_count: u32, used_at: DateTime)
+                                    -> Result<bool, AuthError>
+    {
+        let counter = if sign_count == 0 { doc! { "$eq": 0 } } else { doc! { "$lt": sign_count } };
+        let filter = live(doc! { "_id": ObjectId::from(id) });
+        let update = doc! {
+            "$set": {
+                "passkeys.$[credential].sign_count": sign_count,
+                "passkeys.$[credential].last_used_at": used_at,
+            }
+        };
+        let result = self.collection
+                         .update_one(filter, update)
+                         .array_filters(vec![doc! { "credential.credential_id": credential_id,
+                                                    "credential.sign_count": counter }])
+                         .await?;
+        Ok(result.modified_count > 0)
+    }
+
     pub async fn fetch_by_email(&self, email: String) -> Result<Auth, AuthError>
     {
         let collection = &self.collection;
-        let filter = doc! { "email": email};
+        let filter = live(doc! { "email": email});
         let auth_doc = collection.find_one(filter)
             .await
             .map_err(|_| AuthError::AuthNotFound)?
@@ -182,6 +312,12 @@ impl MongoAuthRepo {
     }
 }
 
+/// Restricts a filter to documents that aren't soft-deleted.
+fn live(mut filter: Document) -> Document
+{
+    filter.insert("deleted_at", Bson::Null);
+    filter
+}
 
 #[async_trait]
 impl AuthRepo for MongoAuthRepo {
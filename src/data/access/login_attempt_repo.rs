@@ -0,0 +1,95 @@
+use mongodb::{
+    bson::{doc, from_document, DateTime, Document},
+    options::ReturnDocument,
+    Collection,
+};
+
+use crate::core::domain::auth::{
+    auth_error::AuthError,
+    auth_lockout::{LockoutPolicy, LoginAttempt, LoginAttemptKey},
+};
+
+#[derive(Clone)]
+pub struct MongoLoginAttemptRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoLoginAttemptRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn fetch(&self, key: &LoginAttemptKey) -> Result<Option<LoginAttempt>, AuthError>
+    {
+        let filter = doc! { "key": key.as_key() };
+        let attempt_doc = self.collection
+                              .find_one(filter)
+                              .await?;
+
+        match attempt_doc
+        {
+            Some(attempt_doc) => Ok(Some(from_document(attempt_doc).map_err(|_| AuthError::InternalServerError)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Records a failed attempt and returns the updated counter with its backoff/lockout applied.
+    /// The whole update is one pipeline so concurrent replicas share the same count and the
+    /// backoff and lock always follow the count they were computed from; a count whose window
+    /// has expired starts over instead of growing forever.
+    pub async fn register_failure(&self, key: &LoginAttemptKey, policy: &LockoutPolicy)
+                                  -> Result<LoginAttempt, AuthError>
+    {
+        let now = DateTime::now();
+        let filter = doc! { "key": key.as_key() };
+        // A missing `window_started_at` sorts before any date, so new and legacy records restart too.
+        let expired = doc! { "$lt": ["$window_started_at", policy.window_start(now)] };
+        // base * 2^(failures - 1), capped; the same as `LockoutPolicy::backoff_secs`.
+        let backoff_secs = doc! {
+            "$min": [
+                { "$multiply": [policy.backoff_base_secs, { "$pow": [2, { "$min": [{ "$subtract": ["$failures", 1] }, 30] }] }] },
+                policy.backoff_max_secs,
+            ]
+        };
+        let update = vec![
+            doc! {
+                "$set": {
+                    "failures": { "$cond": [expired.clone(), 1, { "$add": ["$failures", 1] }] },
+                    "window_started_at": { "$cond": [expired, now, "$window_started_at"] },
+                    "last_failure_at": now,
+                }
+            },
+            doc! {
+                "$set": {
+                    "next_attempt_at": { "$add": [now, { "$multiply": [backoff_secs, 1000] }] },
+                    "locked_until": {
+                        "$cond": [
+                            { "$gte": ["$failures", policy.threshold(key)] },
+                            { "$add": [now, policy.lockout_secs * 1000] },
+                            "$locked_until",
+                        ]
+                    },
+                }
+            },
+        ];
+        let attempt_doc = self.collection
+                              .find_one_and_update(filter, update)
+                              .upsert(true)
+                              .return_document(ReturnDocument::After)
+                              .await?
+                              .ok_or(AuthError::InternalServerError)?;
+        from_document(attempt_doc).map_err(|_| AuthError::InternalServerError)
+    }
+
+    pub async fn reset(&self, key: &LoginAttemptKey) -> Result<bool, AuthError>
+    {
+        let filter = doc! { "key": key.as_key() };
+        let delete_result = self.collection
+                                .delete_one(filter)
+                                .await?;
+        Ok(delete_result.deleted_count > 0)
+    }
+}
@@ -0,0 +1,51 @@
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::auth::{auth_error::AuthError, auth_type::PendingLogin};
+use crate::utils::domains_ids::AuthID;
+
+#[derive(Clone)]
+pub struct MongoLoginChallengeRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoLoginChallengeRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, pending: PendingLogin) -> Result<PendingLogin, AuthError>
+    {
+        let pending_doc = to_document(&pending).map_err(|_| AuthError::AuthDocumentNotCreated)?;
+        self.collection
+            .insert_one(pending_doc)
+            .await?;
+        Ok(pending)
+    }
+
+    /// Removes and returns the pending login so a challenge token can only be exchanged once.
+    pub async fn take(&self, token_hash: String) -> Result<PendingLogin, AuthError>
+    {
+        let filter = doc! { "token_hash": token_hash, "expires_at": { "$gt": DateTime::now() } };
+        let pending_doc = self.collection
+                              .find_one_and_delete(filter)
+                              .await?
+                              .ok_or(AuthError::ChallengeNotFound)?;
+
+        let pending: PendingLogin = from_document(pending_doc).map_err(|_| AuthError::ChallengeNotFound)?;
+        Ok(pending)
+    }
+
+    pub async fn delete_by_auth_id(&self, auth_id: AuthID) -> Result<u64, AuthError>
+    {
+        let result = self.collection
+                         .delete_many(doc! { "auth_id": ObjectId::from(auth_id) })
+                         .await?;
+        Ok(result.deleted_count)
+    }
+}
@@ -3,11 +3,68 @@ use crate::data::access::migration::migrator::{Migrator};
 
 use mongodb::{ error::Error as MongoError};
 use crate::data::access::migration::mongo::v01::Migration001;
+use crate::data::access::migration::mongo::v02::Migration002;
+use crate::data::access::migration::mongo::v03::Migration003;
+use crate::data::access::migration::mongo::v04::Migration004;
+use crate::data::access::migration::mongo::v05::Migration005;
+use crate::data::access::migration::mongo::v06::Migration006;
+use crate::data::access::migration::mongo::v07::Migration007;
+use crate::data::access::migration::mongo::v08::Migration008;
+use crate::data::access::migration::mongo::v09::Migration009;
+use crate::data::access::migration::mongo::v10::Migration010;
+use crate::data::access::migration::mongo::v11::Migration011;
+use crate::data::access::migration::mongo::v12::Migration012;
+use crate::data::access::migration::mongo::v13::Migration013;
+use crate::data::access::migration::mongo::v14::Migration014;
+use crate::data::access::migration::mongo::v15::Migration015;
+use crate::data::access::migration::mongo::v16::Migration016;
+use crate::data::access::migration::mongo::v17::Migration017;
+use crate::data::access::migration::mongo::v18::Migration018;
+use crate::data::access::migration::mongo::v19::Migration019;
+use crate::data::access::migration::mongo::v20::Migration020;
 
 pub mod v01;
+pub mod v02;
+pub mod v03;
+pub mod v04;
+pub mod v05;
+pub mod v06;
+pub mod v07;
+pub mod v08;
+pub mod v09;
+pub mod v10;
+pub mod v11;
+pub mod v12;
+pub mod v13;
+pub mod v14;
+pub mod v15;
+pub mod v16;
+pub mod v17;
+pub mod v18;
+pub mod v19;
+pub mod v20;
 pub async fn migrate_mongo(context: MigrationContext) -> Result<usize, MongoError> {
     let mut migrator = Migrator::new(&context)
-        .register_migration(Box::new(Migration001));
+        .register_migration(Box::new(Migration001))
+        .register_migration(Box::new(Migration002))
+        .register_migration(Box::new(Migration003))
+        .register_migration(Box::new(Migration004))
+        .register_migration(Box::new(Migration005))
+        .register_migration(Box::new(Migration006))
+        .register_migration(Box::new(Migration007))
+        .register_migration(Box::new(Migration008))
+        .register_migration(Box::new(Migration009))
+        .register_migration(Box::new(Migration010))
+        .register_migration(Box::new(Migration011))
+        .register_migration(Box::new(Migration012))
+        .register_migration(Box::new(Migration013))
+        .register_migration(Box::new(Migration014))
+        .register_migration(Box::new(Migration015))
+        .register_migration(Box::new(Migration016))
+        .register_migration(Box::new(Migration017))
+        .register_migration(Box::new(Migration018))
+        .register_migration(Box::new(Migration019))
+        .register_migration(Box::new(Migration020));
 
     let applied = migrator.migrate().await?;
     Ok(applied)
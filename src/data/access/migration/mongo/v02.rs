@@ -0,0 +1,33 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration002;
+
+#[async_trait]
+impl Migration for Migration002 {
+    fn name(&self) -> &'static str {
+        "create_login_attempts_index"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+        let coll = db.collection::<Document>("login_attempts");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "key": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        coll.create_index(index).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use std::{env, time::Duration};
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration003;
+
+#[async_trait]
+impl Migration for Migration003 {
+    fn name(&self) -> &'static str {
+        "create_two_factor_indexes"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // Los logins pendientes se borran solos al pasar expires_at
+        let challenges = db.collection::<Document>("login_challenges");
+        challenges.create_index(IndexModel::builder()
+                                    .keys(doc! { "expires_at": 1 })
+                                    .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+                                    .build())
+                  .await?;
+        challenges.create_index(IndexModel::builder()
+                                    .keys(doc! { "token_hash": 1 })
+                                    .options(IndexOptions::builder().unique(true).build())
+                                    .build())
+                  .await?;
+
+        let policies = db.collection::<Document>("two_factor_policy");
+        policies.create_index(IndexModel::builder()
+                                  .keys(doc! { "role": 1 })
+                                  .options(IndexOptions::builder().unique(true).build())
+                                  .build())
+                .await?;
+
+        Ok(())
+    }
+}
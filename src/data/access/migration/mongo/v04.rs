@@ -0,0 +1,49 @@
+use std::{env, time::Duration};
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration004;
+
+#[async_trait]
+impl Migration for Migration004 {
+    fn name(&self) -> &'static str {
+        "create_passkey_indexes"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        let challenges = db.collection::<Document>("webauthn_challenges");
+        challenges.create_index(IndexModel::builder()
+                                    .keys(doc! { "expires_at": 1 })
+                                    .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+                                    .build())
+                  .await?;
+        challenges.create_index(IndexModel::builder()
+                                    .keys(doc! { "challenge": 1 })
+                                    .options(IndexOptions::builder().unique(true).build())
+                                    .build())
+                  .await?;
+
+        // Un credential id solo puede pertenecer a una cuenta
+        let auth = db.collection::<Document>("auth");
+        auth.create_index(IndexModel::builder()
+                              .keys(doc! { "passkeys.credential_id": 1 })
+                              .options(IndexOptions::builder()
+                                           .unique(true)
+                                           .partial_filter_expression(doc! { "passkeys.credential_id": { "$exists": true } })
+                                           .build())
+                              .build())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use futures_util::TryStreamExt;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, Collection, Database, IndexModel};
+use mongodb::bson::{oid::ObjectId, DateTime, Document};
+use tracing::{error, info};
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+use crate::utils::identifiers::{canonical_username, normalize_identifier};
+
+pub struct Migration005;
+
+#[async_trait]
+impl Migration for Migration005 {
+    fn name(&self) -> &'static str {
+        "normalize_usernames_and_emails"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // El informe refleja las colisiones de este intento, no las de intentos anteriores
+        db.collection::<Document>("identifier_collisions").delete_many(doc! {}).await?;
+
+        let mut collisions = 0;
+        for collection in ["users", "auth"] {
+            collisions += Migration005::normalize_collection(&db, collection).await?;
+        }
+
+        // Los índices únicos solo tienen sentido con todos los identificadores canónicos y sin
+        // ambigüedad: con colisiones la migración falla y se vuelve a ejecutar una vez resueltas,
+        // en vez de quedar registrada sin sus índices
+        if collisions > 0 {
+            let message = format!("Se encontraron {} colisiones de identificadores, revisar 'identifier_collisions'", collisions);
+            error!("{}", message);
+            return Err(MongoError::custom(message));
+        }
+        Migration005::create_unique_index(&db, "users", "email").await?;
+        Migration005::create_unique_index(&db, "auth", "username").await?;
+        Migration005::create_unique_index(&db, "auth", "email").await?;
+
+        Ok(())
+    }
+}
+
+impl Migration005 {
+    async fn normalize_collection(db: &Database, collection_name: &str) -> Result<usize, MongoError> {
+        let coll = db.collection::<Document>(collection_name);
+        let docs: Vec<Document> = coll.find(doc! {}).await?.try_collect().await?;
+
+        let mut updates: HashMap<ObjectId, Document> = HashMap::new();
+        let mut colliding: Vec<ObjectId> = Vec::new();
+        let mut collisions = 0;
+
+        for field in ["username", "email"] {
+            let mut groups: HashMap<String, Vec<(ObjectId, String)>> = HashMap::new();
+            for doc in &docs {
+                if let (Ok(id), Ok(value)) = (doc.get_object_id("_id"), doc.get_str(field)) {
+                    let normalized = match field {
+                        "email" => normalize_identifier(value),
+                        _ => canonical_username(value),
+                    };
+                    groups.entry(normalized).or_default().push((id, value.to_string()));
+                }
+            }
+
+            for (normalized, entries) in groups {
+                if entries.len() > 1 {
+                    collisions += 1;
+                    let ids: Vec<ObjectId> = entries.iter().map(|(id, _)| *id).collect();
+                    error!("Colisión en {}.{}: '{}' usado por {:?}", collection_name, field, normalized, ids);
+                    Migration005::report_collision(db, collection_name, field, &normalized, &entries).await?;
+                    colliding.extend(ids);
+                    continue;
+                }
+                let (id, original) = &entries[0];
+                if *original != normalized {
+                    updates.entry(*id).or_default().insert(field, normalized);
+                }
+            }
+        }
+
+        let mut updated = 0;
+        for (id, set) in updates {
+            if colliding.contains(&id) {
+                continue;
+            }
+            coll.update_one(doc! { "_id": id }, doc! { "$set": set }).await?;
+            updated += 1;
+        }
+        info!("Identificadores normalizados en '{}': {}", collection_name, updated);
+
+        Ok(collisions)
+    }
+
+    async fn report_collision(db: &Database, collection_name: &str, field: &str, normalized: &str,
+                              entries: &[(ObjectId, String)]) -> Result<(), MongoError> {
+        let reports: Collection<Document> = db.collection("identifier_collisions");
+        let originals: Vec<Document> = entries
+            .iter()
+            .map(|(id, value)| doc! { "_id": id, "value": value })
+            .collect();
+        reports
+            .insert_one(doc! {
+                "collection": collection_name,
+                "field": field,
+                "normalized": normalized,
+                "documents": originals,
+                "detected_at": DateTime::now(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn create_unique_index(db: &Database, collection_name: &str, field: &str) -> Result<(), MongoError> {
+        let coll = db.collection::<Document>(collection_name);
+        coll.create_index(IndexModel::builder()
+                              .keys(doc! { field: 1 })
+                              .options(IndexOptions::builder().unique(true).build())
+                              .build())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration006;
+
+#[async_trait]
+impl Migration for Migration006 {
+    fn name(&self) -> &'static str {
+        "create_user_listing_indexes"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // Orden por nombre en el listado paginado (keyset sobre name + _id)
+        let users = db.collection::<Document>("users");
+        users.create_index(IndexModel::builder().keys(doc! { "name": 1, "_id": 1 }).build()).await?;
+        users.create_index(IndexModel::builder().keys(doc! { "status": 1, "_id": 1 }).build()).await?;
+
+        // Join users -> auth para filtrar y proyectar el rol
+        let auth = db.collection::<Document>("auth");
+        auth.create_index(IndexModel::builder().keys(doc! { "user_id": 1 }).build()).await?;
+
+        Ok(())
+    }
+}
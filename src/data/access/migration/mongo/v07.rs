@@ -0,0 +1,38 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration007;
+
+#[async_trait]
+impl Migration for Migration007 {
+    fn name(&self) -> &'static str {
+        "create_user_search_text_index"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+        let coll = db.collection::<Document>("users");
+
+        // Índice de texto v3: insensible a mayúsculas y acentos; stemming en español
+        let index = IndexModel::builder()
+            .keys(doc! { "username": "text", "email": "text", "name": "text" })
+            .options(IndexOptions::builder()
+                .name("user_search".to_string())
+                .default_language("spanish".to_string())
+                .weights(doc! { "username": 10, "email": 5, "name": 3 })
+                .build())
+            .build();
+        coll.create_index(index).await?;
+
+        Ok(())
+    }
+}
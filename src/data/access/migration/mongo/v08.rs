@@ -0,0 +1,37 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError};
+use mongodb::bson::Document;
+use tracing::info;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration008;
+
+#[async_trait]
+impl Migration for Migration008 {
+    fn name(&self) -> &'static str {
+        "backfill_account_status"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // Las cuentas existentes pasan a "Active"; las que ya tienen estado no se tocan
+        for collection in ["users", "auth"]
+        {
+            let coll = db.collection::<Document>(collection);
+            let result = coll
+                .update_many(doc! { "status": { "$exists": false } }, doc! { "$set": { "status": "Active" } })
+                .await?;
+            info!("Estado 'Active' asignado a {} documentos de '{}'", result.modified_count, collection);
+        }
+
+        Ok(())
+    }
+}
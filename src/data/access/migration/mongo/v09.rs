@@ -0,0 +1,38 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration009;
+
+#[async_trait]
+impl Migration for Migration009 {
+    fn name(&self) -> &'static str {
+        "create_soft_delete_indexes"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // Solo los documentos borrados entran en el índice que usa la purga
+        for collection in ["users", "auth"]
+        {
+            let index = IndexModel::builder()
+                .keys(doc! { "deleted_at": 1 })
+                .options(IndexOptions::builder()
+                    .partial_filter_expression(doc! { "deleted_at": { "$type": "date" } })
+                    .build())
+                .build();
+            db.collection::<Document>(collection).create_index(index).await?;
+        }
+
+        Ok(())
+    }
+}
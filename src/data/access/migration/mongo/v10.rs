@@ -0,0 +1,48 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, IndexModel};
+use mongodb::bson::Document;
+use tracing::info;
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::perm::perm_cat::READ_AUDIT;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration010;
+
+#[async_trait]
+impl Migration for Migration010 {
+    fn name(&self) -> &'static str {
+        "create_audit_log"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // Consultas de auditoría: más recientes primero, filtradas por actor, objetivo o acción
+        let audit = db.collection::<Document>("audit_log");
+        for field in ["actor", "target", "action"]
+        {
+            audit.create_index(IndexModel::builder().keys(doc! { field: 1, "_id": -1 }).build()).await?;
+        }
+        audit.create_index(IndexModel::builder().keys(doc! { "at": -1 }).build()).await?;
+
+        // El permiso de auditoría nuevo se concede al SuperAdmin, en el catálogo y en sus cuentas
+        let role = Role::SuperAdmin.to_string();
+        let perm = i64::from(READ_AUDIT);
+        db.collection::<Document>("relationship")
+            .update_many(doc! { "role": &role }, doc! { "$addToSet": { "perms": perm } })
+            .await?;
+        let granted = db.collection::<Document>("auth")
+            .update_many(doc! { "roles": &role }, doc! { "$addToSet": { "permissions": perm } })
+            .await?;
+        info!("Permiso de auditoría concedido a {} cuentas SuperAdmin", granted.modified_count);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use futures_util::TryStreamExt;
+use mongodb::{bson::{doc, from_document}, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use tracing::info;
+use crate::core::domain::audit::audit_chain::GENESIS_HASH;
+use crate::core::domain::audit::AuditEntry;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration011;
+
+#[async_trait]
+impl Migration for Migration011 {
+    fn name(&self) -> &'static str {
+        "chain_audit_log"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+        let audit = db.collection::<Document>("audit_log");
+
+        // Las entradas anteriores a la cadena se encadenan en orden de inserción
+        let mut entries = audit.find(doc! { "seq": { "$exists": false } }).sort(doc! { "_id": 1 }).await?;
+        let mut seq = 0;
+        let mut prev_hash = GENESIS_HASH.to_string();
+        while let Some(entry_doc) = entries.try_next().await?
+        {
+            // Una entrada ilegible detiene la migración en vez del arranque; se reintenta al corregirla
+            let mut entry: AuditEntry = from_document(entry_doc)
+                .map_err(|err| MongoError::custom(format!("Entrada de auditoría con formato no válido: {}", err)))?;
+            seq += 1;
+            entry.link(seq, prev_hash);
+            audit.update_one(doc! { "_id": entry._id },
+                             doc! { "$set": { "seq": entry.seq, "prev_hash": &entry.prev_hash, "hash": &entry.hash } })
+                .await?;
+            prev_hash = entry.hash;
+        }
+        info!("Entradas de auditoría encadenadas: {}", seq);
+
+        // La posición en la cadena es única: dos escritores no pueden ocupar el mismo hueco
+        let index = IndexModel::builder()
+            .keys(doc! { "seq": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        audit.create_index(index).await?;
+
+        Ok(())
+    }
+}
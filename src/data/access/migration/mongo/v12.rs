@@ -0,0 +1,45 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use std::time::Duration;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration012;
+
+#[async_trait]
+impl Migration for Migration012 {
+    fn name(&self) -> &'static str {
+        "create_session_indexes"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+        let sessions = db.collection::<Document>("sessions");
+
+        // Cada petición busca su sesión por el hash del token
+        let token_index = IndexModel::builder()
+            .keys(doc! { "token_hash": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        sessions.create_index(token_index).await?;
+
+        // Listado de sesiones de un usuario, más recientes primero
+        sessions.create_index(IndexModel::builder().keys(doc! { "user_id": 1, "created_at": -1 }).build()).await?;
+
+        // Mongo elimina las sesiones al llegar a expires_at
+        let ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+            .build();
+        sessions.create_index(ttl_index).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration013;
+
+#[async_trait]
+impl Migration for Migration013 {
+    fn name(&self) -> &'static str {
+        "create_access_token_indexes"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+        let access_tokens = db.collection::<Document>("access_tokens");
+
+        // El guard busca el token por su hash en cada petición
+        let token_index = IndexModel::builder()
+            .keys(doc! { "token_hash": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        access_tokens.create_index(token_index).await?;
+
+        // Listado de tokens de una cuenta, más recientes primero
+        access_tokens.create_index(IndexModel::builder().keys(doc! { "auth_id": 1, "created_at": -1 }).build()).await?;
+
+        // Borrado de los tokens al purgar o borrar los datos de un usuario
+        access_tokens.create_index(IndexModel::builder().keys(doc! { "user_id": 1 }).build()).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use tracing::info;
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::perm::perm_cat::MANAGE_SERVICE_ACCOUNTS;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration014;
+
+#[async_trait]
+impl Migration for Migration014 {
+    fn name(&self) -> &'static str {
+        "create_service_accounts"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // El endpoint de token busca la cuenta de servicio por su client_id
+        let client_index = IndexModel::builder()
+            .keys(doc! { "client_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        db.collection::<Document>("service_accounts").create_index(client_index).await?;
+
+        // El permiso para gestionar cuentas de servicio se concede al SuperAdmin
+        let role = Role::SuperAdmin.to_string();
+        let perm = i64::from(MANAGE_SERVICE_ACCOUNTS);
+        db.collection::<Document>("relationship")
+            .update_many(doc! { "role": &role }, doc! { "$addToSet": { "perms": perm } })
+            .await?;
+        let granted = db.collection::<Document>("auth")
+            .update_many(doc! { "roles": &role }, doc! { "$addToSet": { "permissions": perm } })
+            .await?;
+        info!("Permiso de cuentas de servicio concedido a {} cuentas SuperAdmin", granted.modified_count);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use std::env;
+use std::time::Duration;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use tracing::info;
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::perm::perm_cat::MANAGE_OAUTH_CLIENTS;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration015;
+
+#[async_trait]
+impl Migration for Migration015 {
+    fn name(&self) -> &'static str {
+        "create_oauth_collections"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // Los clientes OAuth se buscan por su client_id
+        let client_index = IndexModel::builder()
+            .keys(doc! { "client_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        db.collection::<Document>("oauth_clients").create_index(client_index).await?;
+
+        // Códigos de autorización: un solo uso, Mongo los borra al caducar
+        let code_index = IndexModel::builder()
+            .keys(doc! { "code_hash": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        let code_ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+            .build();
+        db.collection::<Document>("oauth_codes").create_indexes(vec![code_index, code_ttl_index]).await?;
+
+        // Un consentimiento por usuario y cliente
+        let consent_index = IndexModel::builder()
+            .keys(doc! { "user_id": 1, "client_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        db.collection::<Document>("oauth_consents").create_index(consent_index).await?;
+
+        // Refresh tokens: búsqueda por hash, borrado por usuario y al caducar
+        let refresh_index = IndexModel::builder()
+            .keys(doc! { "token_hash": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        let refresh_user_index = IndexModel::builder()
+            .keys(doc! { "user_id": 1 })
+            .build();
+        let refresh_ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+            .build();
+        db.collection::<Document>("oauth_refresh_tokens")
+            .create_indexes(vec![refresh_index, refresh_user_index, refresh_ttl_index])
+            .await?;
+
+        // El permiso para registrar clientes OAuth se concede al SuperAdmin
+        let role = Role::SuperAdmin.to_string();
+        let perm = i64::from(MANAGE_OAUTH_CLIENTS);
+        db.collection::<Document>("relationship")
+            .update_many(doc! { "role": &role }, doc! { "$addToSet": { "perms": perm } })
+            .await?;
+        let granted = db.collection::<Document>("auth")
+            .update_many(doc! { "roles": &role }, doc! { "$addToSet": { "permissions": perm } })
+            .await?;
+        info!("Permiso de clientes OAuth concedido a {} cuentas SuperAdmin", granted.modified_count);
+
+        Ok(())
+    }
+}
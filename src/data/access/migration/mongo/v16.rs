@@ -0,0 +1,32 @@
+use std::env;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration016;
+
+#[async_trait]
+impl Migration for Migration016 {
+    fn name(&self) -> &'static str {
+        "index_refresh_tokens_by_session"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // userinfo encuentra los scopes de identidad a partir del access token
+        let session_index = IndexModel::builder()
+            .keys(doc! { "session_token_hash": 1 })
+            .build();
+        db.collection::<Document>("oauth_refresh_tokens").create_index(session_index).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use std::env;
+use std::time::Duration;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration017;
+
+#[async_trait]
+impl Migration for Migration017 {
+    fn name(&self) -> &'static str {
+        "create_signing_keys"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // Cada clave se identifica por su kid
+        let kid_index = IndexModel::builder()
+            .keys(doc! { "kid": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        // Una sola clave activa: de varias instancias rotando a la vez solo una la inserta
+        let active_index = IndexModel::builder()
+            .keys(doc! { "status": 1 })
+            .options(IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "status": "Active" })
+                .build())
+            .build();
+        // Las claves rotadas se borran cuando termina su periodo de publicación
+        let ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+            .build();
+        db.collection::<Document>("signing_keys")
+            .create_indexes(vec![kid_index, active_index, ttl_index])
+            .await?;
+
+        Ok(())
+    }
+}
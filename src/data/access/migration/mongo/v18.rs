@@ -0,0 +1,41 @@
+use std::env;
+use std::time::Duration;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::doc, error::Error as MongoError, options::IndexOptions, IndexModel};
+use mongodb::bson::Document;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration018;
+
+#[async_trait]
+impl Migration for Migration018 {
+    fn name(&self) -> &'static str {
+        "create_service_account_tokens"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // Búsqueda de los tokens emitidos por su hash
+        let token_index = IndexModel::builder()
+            .keys(doc! { "token_hash": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        // Los registros se borran cuando el token expira
+        let ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+            .build();
+        db.collection::<Document>("service_account_tokens")
+            .create_indexes(vec![token_index, ttl_index])
+            .await?;
+
+        Ok(())
+    }
+}
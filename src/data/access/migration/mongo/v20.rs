@@ -0,0 +1,58 @@
+use std::{env, fs};
+use async_trait::async_trait;
+use dotenv::dotenv;
+use mongodb::{bson::{doc, to_document}, error::Error as MongoError};
+use mongodb::bson::Document;
+use tracing::info;
+use crate::core::domain::perm::perm_hierarchy::RoleHierarchy;
+use crate::core::domain::perm::perm_type::PermsRelationship;
+use crate::data::access::migration::MigrationContext;
+use crate::data::access::migration::Migration;
+
+pub struct Migration020;
+
+#[async_trait]
+impl Migration for Migration020 {
+    fn name(&self) -> &'static str {
+        "apply_role_hierarchy"
+    }
+
+    async fn up(&self, context: &MigrationContext) -> Result<(), MongoError> {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = context.client.database(database_name.as_str());
+
+        // El catálogo declara ahora los padres de cada rol; se recarga para guardarlos
+        let catalogs_path = env::var("CATALOGS_PATH")
+            .map_err(|_| MongoError::custom("Path of catalog not defined".to_string()))?;
+        let file_content = fs::read_to_string(format!("{}/perms_relationship.json", catalogs_path))
+            .map_err(|err| MongoError::custom(format!("Error al leer el archivo perms_relationship.json: {}", err)))?;
+        let relationships: Vec<PermsRelationship> = serde_json::from_str(&file_content)
+            .map_err(|err| MongoError::custom(format!("perms_relationship.json no válido: {}", err)))?;
+        // Un ciclo o un padre desconocido detiene la migración sin tocar nada
+        let hierarchy = RoleHierarchy::new(&relationships)
+            .map_err(|err| MongoError::custom(format!("Jerarquía de roles inválida: {}", err)))?;
+
+        let relationship_coll = db.collection::<Document>("relationship");
+        relationship_coll.delete_many(doc! {}).await?;
+        for relationship in &relationships {
+            let relationship_doc = to_document(relationship)
+                .map_err(|err| MongoError::custom(format!("Error al convertir PermsRelationship: {}", err)))?;
+            relationship_coll.insert_one(relationship_doc).await?;
+        }
+
+        // Cada cuenta recibe los permisos efectivos de su rol, heredados incluidos
+        let auth_coll = db.collection::<Document>("auth");
+        let mut updated = 0;
+        for (role, perms) in hierarchy.into_permissions() {
+            let perms: Vec<i64> = perms.into_iter().map(i64::from).collect();
+            let result = auth_coll.update_many(doc! { "roles": role.to_string() }, doc! { "$set": { "permissions": perms } }).await?;
+            updated += result.modified_count;
+        }
+        info!("Cuentas con permisos heredados actualizados: {}", updated);
+
+        Ok(())
+    }
+}
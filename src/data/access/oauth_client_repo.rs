@@ -0,0 +1,63 @@
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::oauth::{oauth_client::OAuthClient, oauth_error::OAuthError};
+
+#[derive(Clone)]
+pub struct MongoOAuthClientRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoOAuthClientRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, client: OAuthClient) -> Result<OAuthClient, OAuthError>
+    {
+        let mut client_doc = to_document(&client).map_err(|_| OAuthError::ServerError)?;
+        client_doc.remove("_id");
+        let inserted = self.collection
+                           .insert_one(client_doc)
+                           .await?;
+        Ok(OAuthClient { _id: inserted.inserted_id.as_object_id(), ..client })
+    }
+
+    pub async fn fetch_by_client_id(&self, client_id: &str) -> Result<Option<OAuthClient>, OAuthError>
+    {
+        let client_doc = self.collection
+                             .find_one(doc! { "client_id": client_id })
+                             .await?;
+        client_doc.map(|client_doc| from_document(client_doc).map_err(|_| OAuthError::ServerError))
+                  .transpose()
+    }
+
+    pub async fn fetch_all(&self) -> Result<Vec<OAuthClient>, OAuthError>
+    {
+        let client_docs: Vec<Document> = self.collection
+                                             .find(doc! {})
+                                             .sort(doc! { "created_at": -1 })
+                                             .await?
+                                             .try_collect()
+                                             .await?;
+        client_docs.into_iter()
+                   .map(|client_doc| from_document(client_doc).map_err(|_| OAuthError::ServerError))
+                   .collect()
+    }
+
+    /// `false` when the client doesn't exist or was already disabled.
+    pub async fn disable(&self, client_id: &str) -> Result<bool, OAuthError>
+    {
+        let result = self.collection
+                         .update_one(doc! { "client_id": client_id, "disabled_at": null },
+                                     doc! { "$set": { "disabled_at": DateTime::now() } })
+                         .await?;
+        Ok(result.modified_count > 0)
+    }
+}
@@ -0,0 +1,41 @@
+use mongodb::{
+    bson::{doc, from_document, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::oauth::{oauth_authorize::AuthorizationCode, oauth_error::OAuthError};
+
+#[derive(Clone)]
+pub struct MongoOAuthCodeRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoOAuthCodeRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, authorization_code: AuthorizationCode) -> Result<(), OAuthError>
+    {
+        let mut code_doc = to_document(&authorization_code).map_err(|_| OAuthError::ServerError)?;
+        code_doc.remove("_id");
+        self.collection
+            .insert_one(code_doc)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes and returns the code so it can only be exchanged once.
+    pub async fn take(&self, code_hash: &str) -> Result<AuthorizationCode, OAuthError>
+    {
+        let filter = doc! { "code_hash": code_hash, "expires_at": { "$gt": DateTime::now() } };
+        let code_doc = self.collection
+                           .find_one_and_delete(filter)
+                           .await?
+                           .ok_or_else(|| OAuthError::InvalidGrant("invalid or expired code".to_string()))?;
+        from_document(code_doc).map_err(|_| OAuthError::ServerError)
+    }
+}
@@ -0,0 +1,64 @@
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, DateTime, Document},
+    options::UpdateOptions,
+    Collection,
+};
+
+use crate::core::domain::oauth::{oauth_authorize::OAuthConsent, oauth_error::OAuthError};
+use crate::utils::domains_ids::UserID;
+
+#[derive(Clone)]
+pub struct MongoOAuthConsentRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoOAuthConsentRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn fetch(&self, user_id: UserID, client_id: &str) -> Result<Option<OAuthConsent>, OAuthError>
+    {
+        let consent_doc = self.collection
+                              .find_one(doc! { "user_id": ObjectId::from(user_id), "client_id": client_id })
+                              .await?;
+        consent_doc.map(|consent_doc| from_document(consent_doc).map_err(|_| OAuthError::ServerError))
+                   .transpose()
+    }
+
+    pub async fn fetch_by_user_id(&self, user_id: UserID) -> Result<Vec<OAuthConsent>, OAuthError>
+    {
+        let consent_docs: Vec<Document> = self.collection
+                                              .find(doc! { "user_id": ObjectId::from(user_id) })
+                                              .await?
+                                              .try_collect()
+                                              .await?;
+        consent_docs.into_iter()
+                    .map(|consent_doc| from_document(consent_doc).map_err(|_| OAuthError::ServerError))
+                    .collect()
+    }
+
+    /// Adds `scopes` to what the user already granted the client.
+    pub async fn grant(&self, user_id: UserID, client_id: &str, scopes: &[u32]) -> Result<(), OAuthError>
+    {
+        let scopes: Vec<i64> = scopes.iter().map(|scope| i64::from(*scope)).collect();
+        self.collection
+            .update_one(doc! { "user_id": ObjectId::from(user_id), "client_id": client_id },
+                        doc! { "$addToSet": { "scopes": { "$each": scopes } }, "$set": { "granted_at": DateTime::now() } })
+            .with_options(UpdateOptions::builder().upsert(true).build())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_user_id(&self, user_id: UserID) -> Result<u64, OAuthError>
+    {
+        let result = self.collection
+                         .delete_many(doc! { "user_id": ObjectId::from(user_id) })
+                         .await?;
+        Ok(result.deleted_count)
+    }
+}
@@ -0,0 +1,68 @@
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::oauth::{oauth_error::OAuthError, oauth_refresh::RefreshToken};
+use crate::utils::domains_ids::UserID;
+
+#[derive(Clone)]
+pub struct MongoOAuthRefreshTokenRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoOAuthRefreshTokenRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, refresh_token: RefreshToken) -> Result<RefreshToken, OAuthError>
+    {
+        let mut token_doc = to_document(&refresh_token).map_err(|_| OAuthError::ServerError)?;
+        token_doc.remove("_id");
+        let inserted = self.collection
+                           .insert_one(token_doc)
+                           .await?;
+        Ok(RefreshToken { _id: inserted.inserted_id.as_object_id(), ..refresh_token })
+    }
+
+    pub async fn fetch_by_token_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>, OAuthError>
+    {
+        let token_doc = self.collection
+                            .find_one(doc! { "token_hash": token_hash })
+                            .await?;
+        token_doc.map(|token_doc| from_document(token_doc).map_err(|_| OAuthError::ServerError))
+                 .transpose()
+    }
+
+    /// The refresh token issued together with an access token.
+    pub async fn fetch_by_session_token_hash(&self, session_token_hash: &str) -> Result<Option<RefreshToken>, OAuthError>
+    {
+        let token_doc = self.collection
+                            .find_one(doc! { "session_token_hash": session_token_hash })
+                            .await?;
+        token_doc.map(|token_doc| from_document(token_doc).map_err(|_| OAuthError::ServerError))
+                 .transpose()
+    }
+
+    /// `false` when it was already revoked, so two concurrent refreshes can't both succeed.
+    pub async fn revoke(&self, id: ObjectId) -> Result<bool, OAuthError>
+    {
+        let result = self.collection
+                         .update_one(doc! { "_id": id, "revoked_at": null },
+                                     doc! { "$set": { "revoked_at": DateTime::now() } })
+                         .await?;
+        Ok(result.modified_count > 0)
+    }
+
+    pub async fn delete_by_user_id(&self, user_id: UserID) -> Result<u64, OAuthError>
+    {
+        let result = self.collection
+                         .delete_many(doc! { "user_id": ObjectId::from(user_id) })
+                         .await?;
+        Ok(result.deleted_count)
+    }
+}
@@ -1,11 +1,13 @@
 
 use async_trait::async_trait;
 use futures_util::TryStreamExt;
-use mongodb::{bson::{doc, from_bson, Document}, Collection};
+use mongodb::{bson::{doc, Document}, Collection};
 use mongodb::bson::{from_document, to_document};
 use mongodb::bson::oid::ObjectId;
 use tracing::log;
 use crate::context::Context;
+use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::perm::perm_hierarchy::RoleHierarchy;
 use crate::core::domain::perm::{perm_repo::PermRepo, perm_type::{PermsRelationship}, Perm};
 use crate::core::domain::perm::perm_error::PermError;
 use crate::utils::domains_ids::PermID;
@@ -152,17 +154,44 @@ impl MongoPermRepo
     }
 }
 
+/// Every role of the catalog as stored, parents included.
+async fn fetch_relationships(collection: &Collection<Document>) -> Result<Vec<PermsRelationship>, PermError>
+{
+    let mut cursor = collection.find(doc! {})
+        .await
+        .map_err(|_e| PermError::PermNotFound)?;
+
+    let mut relationships = Vec::new();
+    while let Some(document) = cursor.try_next()
+        .await
+        .map_err(|_e| PermError::PermNotFound)?
+    {
+        let relationship: PermsRelationship = from_document(document).map_err(|_e| PermError::PermDocParseError)?;
+        relationships.push(relationship);
+    }
+    Ok(relationships)
+}
+
 #[async_trait]
 impl PermRepo for MongoPermRepo
 {
+    /// New roles may inherit from the ones already stored, but not close a cycle.
     async fn create_perms_relationship(&self, perms_relationships: Vec<PermsRelationship>, context: &Context) -> Result<(), PermError>
     {
         let collection = context.get_collection("relationship");
+        let mut catalog = fetch_relationships(&collection).await?;
+        catalog.extend(perms_relationships.iter().cloned());
+        RoleHierarchy::new(&catalog).map_err(PermError::InvalidRoleHierarchy)?;
+
         let docs: Vec<Document> = perms_relationships.into_iter()
             .map(|perms_relationship| {
                 doc! {
                                                                  "role": perms_relationship.role.to_string(),
                                                                  "perms": perms_relationship.perms,
+                                                                 "parents": perms_relationship.parents
+                                                                                              .iter()
+                                                                                              .map(|parent| parent.to_string())
+                                                                                              .collect::<Vec<String>>(),
                                                              }
             })
             .collect();
@@ -173,37 +202,28 @@ impl PermRepo for MongoPermRepo
         Ok(())
     }
 
+    /// Effective permissions of the role, the inherited ones included.
     async fn charge_permissions(&self, command: String, context: &Context) -> Result<Vec<u32>, PermError>
     {
         let collection_relationship: Collection<Document> = context.get_collection("relationship");
+        let relationships = fetch_relationships(&collection_relationship).await?;
+        let hierarchy = RoleHierarchy::new(&relationships).map_err(PermError::InvalidRoleHierarchy)?;
 
-        let filter = doc! { "role": command };
-
-        let result = collection_relationship.find_one(filter.clone())
-            .await
-            .map_err(|_e| { PermError::PermNotFound }
-            )?;
-
-        if let Some(document) = result
+        let role: Role = match command.parse()
         {
-
-            if let Some(perms_bson) = document.get("perms")
-            {
-
-                let perms: Vec<u32> =
-                    from_bson(perms_bson.clone()).map_err(|_e| {
-                        PermError::PermNotFound
-                    })?;
-
-                return Ok(perms);
-            } else {
-                log::warn!("'perm' field not found in the relationship document: {:?}", document);
-            }
-        } else {
-            log::warn!("No document found with filter: {:?}", filter);
+            Ok(role) => role,
+            Err(_) => {
+                log::warn!("Unknown role: {}", command);
+                return Err(PermError::PermNotFound);
+            },
+        };
+        match hierarchy.permissions(&role)
+        {
+            Some(perms) => Ok(perms.to_vec()),
+            None => {
+                log::warn!("No relationship found for role: {}", command);
+                Err(PermError::PermNotFound)
+            },
         }
-
-
-        Err(PermError::PermNotFound)
     }
 }
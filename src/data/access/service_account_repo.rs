@@ -0,0 +1,80 @@
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, to_bson, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::service_account::{
+    service_account_error::ServiceAccountError,
+    ClientSecret,
+    ServiceAccount,
+};
+
+#[derive(Clone)]
+pub struct MongoServiceAccountRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoServiceAccountRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, service_account: ServiceAccount) -> Result<ServiceAccount, ServiceAccountError>
+    {
+        let mut account_doc = to_document(&service_account).map_err(|_| ServiceAccountError::DocumentError)?;
+        account_doc.remove("_id");
+        let inserted = self.collection
+                           .insert_one(account_doc)
+                           .await?;
+        Ok(ServiceAccount { _id: inserted.inserted_id.as_object_id(), ..service_account })
+    }
+
+    pub async fn fetch_by_client_id(&self, client_id: &str) -> Result<ServiceAccount, ServiceAccountError>
+    {
+        let account_doc = self.collection
+                              .find_one(doc! { "client_id": client_id })
+                              .await?
+                              .ok_or(ServiceAccountError::NotFound)?;
+        from_document(account_doc).map_err(|_| ServiceAccountError::DocumentError)
+    }
+
+    pub async fn fetch_all(&self) -> Result<Vec<ServiceAccount>, ServiceAccountError>
+    {
+        let account_docs: Vec<Document> = self.collection
+                                              .find(doc! {})
+                                              .sort(doc! { "created_at": -1 })
+                                              .await?
+                                              .try_collect()
+                                              .await?;
+        account_docs.into_iter()
+                    .map(|account_doc| from_document(account_doc).map_err(|_| ServiceAccountError::DocumentError))
+                    .collect()
+    }
+
+    pub async fn update_secrets(&self, client_id: &str, secrets: &[ClientSecret]) -> Result<(), ServiceAccountError>
+    {
+        let secrets = to_bson(secrets).map_err(|_| ServiceAccountError::DocumentError)?;
+        let result = self.collection
+                         .update_one(doc! { "client_id": client_id }, doc! { "$set": { "secrets": secrets } })
+                         .await?;
+        if result.matched_count == 0
+        {
+            return Err(ServiceAccountError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// `false` when the account was already disabled.
+    pub async fn disable(&self, client_id: &str) -> Result<bool, ServiceAccountError>
+    {
+        let result = self.collection
+                         .update_one(doc! { "client_id": client_id, "disabled_at": null },
+                                     doc! { "$set": { "disabled_at": DateTime::now() } })
+                         .await?;
+        Ok(result.modified_count > 0)
+    }
+}
@@ -0,0 +1,42 @@
+use mongodb::{
+    bson::{doc, from_document, to_document, Document},
+    Collection,
+};
+
+use crate::core::domain::service_account::{
+    service_account_error::ServiceAccountError,
+    service_account_token::ServiceAccountToken,
+};
+
+#[derive(Clone)]
+pub struct MongoServiceAccountTokenRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoServiceAccountTokenRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, token: ServiceAccountToken) -> Result<ServiceAccountToken, ServiceAccountError>
+    {
+        let mut token_doc = to_document(&token).map_err(|_| ServiceAccountError::DocumentError)?;
+        token_doc.remove("_id");
+        let inserted = self.collection
+                           .insert_one(token_doc)
+                           .await?;
+        Ok(ServiceAccountToken { _id: inserted.inserted_id.as_object_id(), ..token })
+    }
+
+    pub async fn fetch_by_token_hash(&self, token_hash: &str) -> Result<Option<ServiceAccountToken>, ServiceAccountError>
+    {
+        let token_doc = self.collection
+                            .find_one(doc! { "token_hash": token_hash })
+                            .await?;
+        token_doc.map(|token_doc| from_document(token_doc).map_err(|_| ServiceAccountError::DocumentError))
+                 .transpose()
+    }
+}
@@ -0,0 +1,94 @@
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::auth::{auth_error::AuthError, auth_session::Session};
+use crate::utils::domains_ids::UserID;
+
+#[derive(Clone)]
+pub struct MongoSessionRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoSessionRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, session: Session) -> Result<Session, AuthError>
+    {
+        let mut session_doc = to_document(&session).map_err(|_| AuthError::AuthDocumentNotCreated)?;
+        session_doc.remove("_id");
+        let inserted = self.collection
+                           .insert_one(session_doc)
+                           .await?;
+        Ok(Session { _id: inserted.inserted_id.as_object_id(), ..session })
+    }
+
+    pub async fn fetch_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, AuthError>
+    {
+        let session_doc = self.collection
+                              .find_one(doc! { "token_hash": token_hash })
+                              .await?;
+        session_doc.map(|session_doc| from_document(session_doc).map_err(|_| AuthError::SessionNotFound))
+                   .transpose()
+    }
+
+    /// Newest first, revoked ones included so the list doubles as the login history.
+    pub async fn fetch_by_user_id(&self, user_id: UserID) -> Result<Vec<Session>, AuthError>
+    {
+        let session_docs: Vec<Document> = self.collection
+                                              .find(doc! { "user_id": ObjectId::from(user_id) })
+                                              .sort(doc! { "created_at": -1 })
+                                              .await?
+                                              .try_collect()
+                                              .await?;
+        session_docs.into_iter()
+                    .map(|session_doc| from_document(session_doc).map_err(|_| AuthError::SessionNotFound))
+                    .collect()
+    }
+
+    /// Only writes when `last_seen_at` is still before `stale`, so concurrent requests that all
+    /// saw a stale session write it once.
+    pub async fn touch(&self, token_hash: &str, now: DateTime, stale: DateTime) -> Result<(), AuthError>
+    {
+        self.collection
+            .update_one(doc! { "token_hash": token_hash, "last_seen_at": { "$lt": stale } },
+                        doc! { "$set": { "last_seen_at": now } })
+            .await?;
+        Ok(())
+    }
+
+    /// Only revokes a session of `user_id`, so one user can't revoke another's by guessing ids.
+    pub async fn revoke(&self, id: ObjectId, user_id: UserID) -> Result<bool, AuthError>
+    {
+        let result = self.collection
+                         .update_one(doc! { "_id": id, "user_id": ObjectId::from(user_id), "revoked_at": null },
+                                     doc! { "$set": { "revoked_at": DateTime::now() } })
+                         .await?;
+        Ok(result.modified_count > 0)
+    }
+
+    /// Ends the session behind a token, whoever owns it; used when the token itself is revoked.
+    pub async fn revoke_by_token_hash(&self, token_hash: &str) -> Result<bool, AuthError>
+    {
+        let result = self.collection
+                         .update_one(doc! { "token_hash": token_hash, "revoked_at": null },
+                                     doc! { "$set": { "revoked_at": DateTime::now() } })
+                         .await?;
+        Ok(result.modified_count > 0)
+    }
+
+    pub async fn delete_by_user_id(&self, user_id: UserID) -> Result<u64, AuthError>
+    {
+        let result = self.collection
+                         .delete_many(doc! { "user_id": ObjectId::from(user_id) })
+                         .await?;
+        Ok(result.deleted_count)
+    }
+}
@@ -0,0 +1,86 @@
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, to_bson, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::signing_key::{signing_key_error::SigningKeyError, SigningKey, SigningKeyStatus};
+use crate::data::access::is_duplicate_key;
+
+#[derive(Clone)]
+pub struct MongoSigningKeyRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoSigningKeyRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    /// Stores a new active key. `None` when another key is active already: a unique index
+    /// keeps a single active key, so only one of several instances rotating at once gets in.
+    pub async fn create(&self, signing_key: SigningKey) -> Result<Option<SigningKey>, SigningKeyError>
+    {
+        let mut key_doc = to_document(&signing_key).map_err(|_| SigningKeyError::DocumentError)?;
+        key_doc.remove("_id");
+        match self.collection.insert_one(key_doc).await
+        {
+            Ok(inserted) => Ok(Some(SigningKey { _id: inserted.inserted_id.as_object_id(), ..signing_key })),
+            Err(err) if is_duplicate_key(&err) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub async fn fetch_active(&self) -> Result<Option<SigningKey>, SigningKeyError>
+    {
+        let status = to_bson(&SigningKeyStatus::Active).map_err(|_| SigningKeyError::DocumentError)?;
+        let key_doc = self.collection
+                          .find_one(doc! { "status": status })
+                          .await?;
+        key_doc.map(|key_doc| from_document(key_doc).map_err(|_| SigningKeyError::DocumentError))
+               .transpose()
+    }
+
+    /// Keys whose tokens may still be in circulation, newest first. The TTL monitor only runs
+    /// every minute, so expired keys are filtered here too.
+    pub async fn fetch_published(&self, now: DateTime) -> Result<Vec<SigningKey>, SigningKeyError>
+    {
+        let key_docs: Vec<Document> = self.collection
+                                          .find(doc! { "$or": [{ "expires_at": null }, { "expires_at": { "$gt": now } }] })
+                                          .sort(doc! { "created_at": -1 })
+                                          .await?
+                                          .try_collect()
+                                          .await?;
+        key_docs.into_iter()
+                .map(|key_doc| from_document(key_doc).map_err(|_| SigningKeyError::DocumentError))
+                .collect()
+    }
+
+    /// Turns the active key `kid` into a previous one, published until `expires_at`. False when
+    /// it was no longer active.
+    pub async fn demote(&self, kid: &str, expires_at: DateTime) -> Result<bool, SigningKeyError>
+    {
+        let active = to_bson(&SigningKeyStatus::Active).map_err(|_| SigningKeyError::DocumentError)?;
+        let previous = to_bson(&SigningKeyStatus::Previous).map_err(|_| SigningKeyError::DocumentError)?;
+        let result = self.collection
+                         .update_one(doc! { "kid": kid, "status": active },
+                                     doc! { "$set": { "status": previous, "expires_at": expires_at } })
+                         .await?;
+        Ok(result.modified_count > 0)
+    }
+
+    /// Retires every key created up to `created_at`, for good: they are neither active nor
+    /// published from `now` on.
+    pub async fn retire_up_to(&self, created_at: DateTime, now: DateTime) -> Result<u64, SigningKeyError>
+    {
+        let previous = to_bson(&SigningKeyStatus::Previous).map_err(|_| SigningKeyError::DocumentError)?;
+        let result = self.collection
+                         .update_many(doc! { "created_at": { "$lte": created_at } },
+                                      doc! { "$set": { "status": previous, "expires_at": now } })
+                         .await?;
+        Ok(result.modified_count)
+    }
+}
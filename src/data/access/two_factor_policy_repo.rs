@@ -0,0 +1,42 @@
+use mongodb::{
+    bson::{doc, Document},
+    Collection,
+};
+
+use crate::core::domain::auth::{auth_error::AuthError, auth_type::{Role, TwoFactorPolicy}};
+
+#[derive(Clone)]
+pub struct MongoTwoFactorPolicyRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoTwoFactorPolicyRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn is_required(&self, role: &Role) -> Result<bool, AuthError>
+    {
+        let filter = doc! { "role": role.to_string() };
+        let policy_doc = self.collection
+                             .find_one(filter)
+                             .await?;
+
+        Ok(policy_doc.and_then(|policy_doc| policy_doc.get_bool("required").ok())
+                     .unwrap_or(false))
+    }
+
+    pub async fn save(&self, policy: TwoFactorPolicy) -> Result<TwoFactorPolicy, AuthError>
+    {
+        let filter = doc! { "role": policy.role.to_string() };
+        self.collection
+            .update_one(filter, doc! { "$set": { "required": policy.required } })
+            .upsert(true)
+            .await
+            .map_err(|_| AuthError::AuthDocNotUpdated)?;
+        Ok(policy)
+    }
+}
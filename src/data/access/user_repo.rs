@@ -1,9 +1,14 @@
 use async_trait::async_trait;
-use futures_util::TryStreamExt;
+use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
 use mongodb::{bson::{doc, oid::ObjectId, Document}, Collection};
-use mongodb::bson::{from_document, to_document};
+use mongodb::bson::{from_document, to_document, Bson, DateTime};
 use crate::core::domain::user::{user_repo::UserRepo, User};
+use crate::core::domain::user::user_deletion::{restore_update, soft_delete_update, tombstone, DeletedUser};
 use crate::core::domain::user::user_error::UserError;
+use crate::core::domain::user::user_status::AccountStatus;
+use crate::core::domain::user::user_query::{PageCursor, UserListItem, UserListQuery, UserPage, UserSearchHit};
+use crate::core::domain::user::user_query::search_prefix_filter;
+use crate::core::domain::user::user_transfer::ExportedUser;
 use crate::utils::domains_ids::UserID;
 
 #[derive(Clone, Debug)]
@@ -35,6 +40,7 @@ impl MongoUserRepo
                 email: new_user.email.clone(),
                 
                 name: new_user.name.clone(),
+                status: new_user.status,
             })
         }
         else
@@ -44,7 +50,7 @@ impl MongoUserRepo
     }
     pub async fn fetch_all(&self) -> Result<Vec<User>, UserError>
     {
-        let filter = doc! {};
+        let filter = live(doc! {});
         let mut cursor = self.collection
             .find(filter)
             .await
@@ -61,9 +67,133 @@ impl MongoUserRepo
         Ok(users)
     }
 
+    /// One page of users for `query`, resuming after its cursor. The `auth` collection is only
+    /// joined when the role is filtered on or projected, and only after paging unless filtered.
+    pub async fn fetch_page(&self, query: &UserListQuery) -> Result<UserPage, UserError>
+    {
+        let fields = query.projected_fields()?;
+        let limit = query.page_size();
+        let role_stages = vec![
+            doc! { "$lookup": { "from": "auth", "localField": "_id", "foreignField": "user_id", "as": "auth" } },
+            doc! { "$set": { "role": { "$first": "$auth.roles" } } },
+        ];
+        let mut filtered = vec![doc! { "$match": live(query.user_filter()) }];
+        if let Some(role) = &query.role
+        {
+            filtered.extend(role_stages.clone());
+            filtered.push(doc! { "$match": { "role": role.to_string() } });
+        }
+
+        let total = if query.include_total { Some(self.count(filtered.clone()).await?) } else { None };
+
+        let mut pipeline = filtered;
+        if let Some(cursor) = &query.cursor
+        {
+            pipeline.push(doc! { "$match": query.cursor_filter(&PageCursor::decode(cursor)?) });
+        }
+        pipeline.push(doc! { "$sort": query.sort() });
+        pipeline.push(doc! { "$limit": i64::from(limit) + 1 });
+        if query.role.is_none() && query.needs_role(&fields)
+        {
+            pipeline.extend(role_stages);
+        }
+        let mut projection = doc! { "_id": 1, "_sort": format!("${}", query.sort.field()) };
+        for field in &fields
+        {
+            projection.insert(*field, 1);
+        }
+        pipeline.push(doc! { "$project": projection });
+
+        let mut docs: Vec<Document> = self.collection.aggregate(pipeline).await?.try_collect().await?;
+        let mut next_cursor = None;
+        if docs.len() > limit as usize
+        {
+            docs.truncate(limit as usize);
+            next_cursor = docs.last().and_then(|last| {
+                let id = last.get_object_id("_id").ok()?;
+                Some(PageCursor { id, value: last.get_str("_sort").ok().map(str::to_string) }.encode())
+            });
+        }
+        let items = docs.into_iter()
+                        .map(|user_doc| from_document::<UserListItem>(user_doc).map_err(|_| UserError::UserNotFound))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(UserPage { items, next_cursor, total })
+    }
+
+    /// Matches against the `user_search` text index, which ignores case and accents, best score
+    /// first. Text search only matches whole words, so a short page is topped up with prefix
+    /// matches (score 0) to catch partially typed names.
+    pub async fn search(&self, terms: &str, limit: u32) -> Result<Vec<UserSearchHit>, UserError>
+    {
+        let score = doc! { "$meta": "textScore" };
+        let mut hits = self.collection
+                           .find(live(doc! { "$text": { "$search": terms } }))
+                           .projection(doc! { "username": 1, "email": 1, "name": 1, "score": score.clone() })
+                           .sort(doc! { "score": score })
+                           .limit(i64::from(limit))
+                           .await?
+                           .try_collect::<Vec<Document>>()
+                           .await?;
+
+        if hits.len() < limit as usize
+        {
+            let found: Vec<Bson> = hits.iter().filter_map(|hit| hit.get("_id").cloned()).collect();
+            let partial = self.collection
+                              .find(live(search_prefix_filter(terms, found)))
+                              .projection(doc! { "username": 1, "email": 1, "name": 1, "score": { "$literal": 0.0 } })
+                              .sort(doc! { "username": 1 })
+                              .limit(i64::from(limit) - hits.len() as i64)
+                              .await?
+                              .try_collect::<Vec<Document>>()
+                              .await?;
+            hits.extend(partial);
+        }
+
+        hits.into_iter()
+            .map(|hit| from_document::<UserSearchHit>(hit).map_err(|_| UserError::UserNotFound))
+            .collect()
+    }
+
+    async fn count(&self, mut pipeline: Vec<Document>) -> Result<u64, UserError>
+    {
+        pipeline.push(doc! { "$count": "total" });
+        let counted = self.collection.aggregate(pipeline).await?.try_next().await?;
+        let total = counted.and_then(|counted| {
+            counted.get_i32("total").map(i64::from).or_else(|_| counted.get_i64("total")).ok()
+        });
+        Ok(total.unwrap_or(0) as u64)
+    }
+
+    /// Users with their role, in `_id` order, yielded as the cursor advances rather than
+    /// collected like `fetch_all`.
+    pub async fn stream_export(&self) -> Result<BoxStream<'static, Result<ExportedUser, UserError>>, UserError>
+    {
+        let pipeline = vec![
+            doc! { "$match": live(doc! {}) },
+            doc! { "$sort": { "_id": 1 } },
+            doc! { "$lookup": { "from": "auth", "localField": "_id", "foreignField": "user_id", "as": "auth" } },
+            doc! { "$project": {
+                "_id": 0,
+                "id": { "$toString": "$_id" },
+                "username": 1,
+                "email": 1,
+                "name": 1,
+                "role": { "$first": "$auth.roles" },
+            } },
+        ];
+        let cursor = self.collection.aggregate(pipeline).await?;
+
+        Ok(cursor.map_err(UserError::from)
+                 .and_then(|user_doc| async move {
+                     from_document::<ExportedUser>(user_doc).map_err(|_| UserError::UserNotFound)
+                 })
+                 .boxed())
+    }
+
     pub async fn fetch_all_actives(&self) -> Result<Vec<User>, UserError>
     {
-        let filter = doc! {"status": "Active"};
+        let filter = live(doc! {"status": "Active"});
         let mut cursor = self.collection
             .find(filter)
             .await
@@ -83,7 +213,7 @@ impl MongoUserRepo
     pub async fn fetch_by_id(&self, id: UserID) -> Result<User, UserError>
     {
         let collection = &self.collection;
-        let filter = doc! { "_id": ObjectId::from(id)};
+        let filter = live(doc! { "_id": ObjectId::from(id)});
         let user_doc = collection.find_one(filter)
             .await
             .map_err(|_| UserError::UserNotFound)?
@@ -93,10 +223,24 @@ impl MongoUserRepo
         Ok(user)
     }
 
+    pub async fn update_status(&self, id: UserID, status: AccountStatus) -> Result<(), UserError>
+    {
+        let filter = live(doc! { "_id": ObjectId::from(id) });
+        let result = self.collection
+                         .update_one(filter, doc! { "$set": { "status": status.to_string() } })
+                         .await
+                         .map_err(|_| UserError::UserDocNotUpdated)?;
+        if result.matched_count == 0
+        {
+            return Err(UserError::UserNotFound);
+        }
+        Ok(())
+    }
+
     pub async fn fetch_by_email(&self, email: String) -> Result<User, UserError>
     {
         let collection = &self.collection;
-        let filter = doc! {"email": email};
+        let filter = live(doc! {"email": email});
         let user_doc = collection.find_one(filter)
             .await
             .map_err(|_| UserError::UserNotFound)?
@@ -113,7 +257,7 @@ impl MongoUserRepo
         {
             let user_doc = to_document(&user).map_err(|_| UserError::UserDocumentNotCreated)?;
 
-            let filter = doc! { "_id": ObjectId::from(user_id.clone()) };
+            let filter = live(doc! { "_id": ObjectId::from(user_id.clone()) });
             let update_result = collection.update_one(filter, doc! { "$set": user_doc })
                 .await;
 
@@ -139,7 +283,60 @@ impl MongoUserRepo
         }
     }
 
-    pub async fn delete (&self, id: UserID) -> Result<(), UserError>
+    /// Marks the user as deleted; it disappears from every fetch but stays restorable until purged.
+    /// Its username and email are set aside, so they can be registered again meanwhile.
+    pub async fn delete (&self, id: UserID, deleted_by: Option<String>) -> Result<(), UserError>
+    {
+        let id = ObjectId::from(id);
+        let filter = live(doc! { "_id": id });
+        let update = soft_delete_update(&tombstone(&id.to_hex()), DateTime::now(), deleted_by);
+        let result = self.collection.update_one(filter, update).await.map_err(|_| UserError::UserDocNotUpdated)?;
+        if result.matched_count == 0
+        {
+            return Err(UserError::UserNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn fetch_deleted(&self, id: UserID) -> Result<DeletedUser, UserError>
+    {
+        let filter = doc! { "_id": ObjectId::from(id), "deleted_at": { "$type": "date" } };
+        let user_doc = self.collection.find_one(filter)
+            .await?
+            .ok_or(UserError::UserNotFound)?;
+
+        from_document(user_doc).map_err(|_| UserError::UserNotFound)
+    }
+
+    /// Soft-deleted users whose deletion is older than `cutoff`, oldest first.
+    pub async fn fetch_deleted_before(&self, cutoff: DateTime, limit: i64) -> Result<Vec<DeletedUser>, UserError>
+    {
+        let user_docs: Vec<Document> = self.collection
+                                           .find(doc! { "deleted_at": { "$lt": cutoff } })
+                                           .sort(doc! { "deleted_at": 1 })
+                                           .limit(limit)
+                                           .await?
+                                           .try_collect()
+                                           .await?;
+
+        user_docs.into_iter()
+                 .map(|user_doc| from_document(user_doc).map_err(|_| UserError::UserNotFound))
+                 .collect()
+    }
+
+    pub async fn restore(&self, id: UserID) -> Result<(), UserError>
+    {
+        let filter = doc! { "_id": ObjectId::from(id), "deleted_at": { "$type": "date" } };
+        let result = self.collection.update_one(filter, restore_update()).await.map_err(|_| UserError::UserDocNotUpdated)?;
+        if result.matched_count == 0
+        {
+            return Err(UserError::UserNotFound);
+        }
+        Ok(())
+    }
+
+    /// Removes the document for good; only the purge job calls this.
+    pub async fn purge (&self, id: UserID) -> Result<(), UserError>
     {
         let collection = &self.collection;
         let filter = doc! { "_id": ObjectId::from(id) };
@@ -187,6 +384,13 @@ impl MongoUserRepo
     
 }
 
+/// Restricts a filter to documents that aren't soft-deleted.
+fn live(mut filter: Document) -> Document
+{
+    filter.insert("deleted_at", Bson::Null);
+    filter
+}
+
 #[async_trait]
 impl UserRepo for MongoUserRepo
 {
@@ -0,0 +1,59 @@
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, to_document, DateTime, Document},
+    Collection,
+};
+
+use crate::core::domain::auth::{
+    auth_error::AuthError,
+    auth_webauthn::{Ceremony, WebauthnChallenge},
+};
+use crate::utils::domains_ids::AuthID;
+
+#[derive(Clone)]
+pub struct MongoWebauthnChallengeRepo
+{
+    collection: Collection<Document>,
+}
+
+impl MongoWebauthnChallengeRepo
+{
+    pub fn new(collection: Collection<Document>) -> Self
+    {
+        Self { collection }
+    }
+
+    pub async fn create(&self, challenge: WebauthnChallenge) -> Result<WebauthnChallenge, AuthError>
+    {
+        let challenge_doc = to_document(&challenge).map_err(|_| AuthError::AuthDocumentNotCreated)?;
+        self.collection
+            .insert_one(challenge_doc)
+            .await?;
+        Ok(challenge)
+    }
+
+    /// Removes and returns the ceremony state, so every challenge can be answered only once.
+    pub async fn take(&self, challenge: String, ceremony: Ceremony) -> Result<WebauthnChallenge, AuthError>
+    {
+        let ceremony = mongodb::bson::to_bson(&ceremony).map_err(|_| AuthError::ChallengeNotFound)?;
+        let filter = doc! {
+            "challenge": challenge,
+            "ceremony": ceremony,
+            "expires_at": { "$gt": DateTime::now() },
+        };
+        let challenge_doc = self.collection
+                                .find_one_and_delete(filter)
+                                .await?
+                                .ok_or(AuthError::ChallengeNotFound)?;
+
+        let challenge: WebauthnChallenge = from_document(challenge_doc).map_err(|_| AuthError::ChallengeNotFound)?;
+        Ok(challenge)
+    }
+
+    pub async fn delete_by_auth_id(&self, auth_id: AuthID) -> Result<u64, AuthError>
+    {
+        let result = self.collection
+                         .delete_many(doc! { "auth_id": ObjectId::from(auth_id) })
+                         .await?;
+        Ok(result.deleted_count)
+    }
+}
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
-use std::{env, fs};
+use std::{env, fs, io};
 use dotenv::dotenv;
 use futures_util::TryStreamExt;
-use mongodb::bson::{doc, to_bson, Document};
+use mongodb::bson::{doc, from_document, to_bson, to_document, Document};
 use mongodb::{Client};
 
 use crate::core::domain::auth::auth_type::Role;
+use crate::core::domain::authz::authz_policy::{Policy, PolicySet};
+use crate::core::domain::perm::perm_hierarchy::RoleHierarchy;
 use crate::core::domain::perm::perm_type::PermsRelationship;
 use crate::error::{ServiceError, ServiceResult};
 
@@ -22,22 +24,16 @@ impl MongoCatalogRepo{
         }
     }
     
-    pub async fn import_perm_relationships(&self) -> ServiceResult<()>
+    /// Reads and checks the relationship catalog without touching the stored one.
+    pub fn load_perm_relationships(&self) -> ServiceResult<Vec<PermsRelationship>>
     {
-        dotenv().ok();
-        let database_name = env::var("MONGO_DATABASE")
-            .expect("Variable isn't found: MONGO_DATABASE");
-
-        let db = self.client.database(database_name.as_str()) ;
-        let coll = db.collection::<Document>("relationship");
-        coll.delete_many(Document::new()).await
-            .expect("Error al vaciar la colección 'relationship'");
-
         let file_path = "C:/Users/alorenzo/Proyectos-2/user/tests/fixtures/perms_relationship.json";
         let file_content = fs::read_to_string(file_path)
             .expect("Error al leer el archivo perms_relationship.json");
 
         let mut relationships: Vec<PermsRelationship> = serde_json::from_str(&file_content).unwrap();
+        // A hierarchy with cycles or unknown parents leaves the current catalog in place
+        RoleHierarchy::new(&relationships).map_err(ServiceError::InvalidRoleHierarchy)?;
 
         // Convert `Vec<u64>` to `Vec<u32>` during iteration
         for relationship in relationships.iter_mut() {
@@ -49,6 +45,20 @@ impl MongoCatalogRepo{
                 })) // Vec<u32>
                 .collect();
         }
+        Ok(relationships)
+    }
+
+    /// Replaces the stored relationships with ones from `load_perm_relationships`.
+    pub async fn import_perm_relationships(&self, relationships: Vec<PermsRelationship>) -> ServiceResult<()>
+    {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let db = self.client.database(database_name.as_str()) ;
+        let coll = db.collection::<Document>("relationship");
+        coll.delete_many(Document::new()).await
+            .expect("Error al vaciar la colección 'relationship'");
 
         for relationship in relationships {
             let bson_doc = to_bson(&relationship)
@@ -68,6 +78,7 @@ impl MongoCatalogRepo{
     
    
 
+    /// Effective permissions of every role, with the inherited ones included.
     pub async fn fetch_perm_relationships(&self) -> Result<HashMap<Role, Vec<u32>>, ServiceError> {
         dotenv().ok();
         let database_name = env::var("MONGO_DATABASE")
@@ -76,44 +87,84 @@ impl MongoCatalogRepo{
         let db = self.client.database(database_name.as_str());
         let relationship_coll = db.collection::<Document>("relationship");
 
-        let mut relationship_map = HashMap::new();
         let filter = doc! {};
         let mut cursor = relationship_coll
             .find(filter)
             .await
             .map_err(|_| ServiceError::RelationalNotFound)?;
 
+        let mut relationships = Vec::new();
         while let Some(relational_doc) = cursor.try_next()
             .await
             .map_err(|_| ServiceError::RelationalDocumentNotFound)?
         {
-            
-            let role = relational_doc.get_str("role")
-                .map(|r| r.to_string()) 
+            let relationship: PermsRelationship = from_document(relational_doc)
                 .map_err(|_| ServiceError::RelationalDeserializeError)?;
+            relationships.push(relationship);
+        }
+
+        let hierarchy = RoleHierarchy::new(&relationships).map_err(ServiceError::InvalidRoleHierarchy)?;
+        Ok(hierarchy.into_permissions())
+    }
 
-            let permissions = relational_doc.get_array("perms")
-                .map_err(|_| ServiceError::RelationalDeserializeError)?
-                .iter()
-                .map(|p| {
-                    match p.as_i64() {
-                        Some(value) => value.try_into().unwrap_or_else(|_| {
-                            panic!("Error: No se pudo convertir {} a u32", value)
-                        }),
-                        None => panic!("Error: Permiso no era un Int64"),
-                    }
-                })
-                .collect::<Vec<u32>>();
-            
-            let role_enum: Role = role.parse().unwrap();
-            relationship_map
-                .entry(role_enum)
-                .or_insert_with(Vec::new)
-                .extend(permissions);
+    /// Reads and checks `{CATALOGS_PATH}/policies.json` without touching the stored policies.
+    /// Catalogs without the file define no policies.
+    pub fn load_policies(&self) -> ServiceResult<PolicySet>
+    {
+        dotenv().ok();
+        let catalogs_path = env::var("CATALOGS_PATH").expect("Path of catalog not defined");
+
+        match fs::read_to_string(format!("{}/policies.json", catalogs_path))
+        {
+            Ok(file_content) => PolicySet::from_json(&file_content).map_err(ServiceError::InvalidPolicy),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(PolicySet::default()),
+            Err(_) => Err(ServiceError::PolicyCatalogNotFound),
         }
+    }
+
+    /// Replaces the stored policies with ones from `load_policies`.
+    pub async fn import_policies(&self, policies: &PolicySet) -> ServiceResult<()>
+    {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let docs = policies.policies()
+                           .iter()
+                           .map(to_document)
+                           .collect::<Result<Vec<Document>, _>>()
+                           .map_err(|err| ServiceError::InvalidPolicy(err.to_string()))?;
 
-        Ok(relationship_map)
+        let coll = self.client.database(database_name.as_str()).collection::<Document>("policies");
+        coll.delete_many(Document::new()).await
+            .map_err(|_| ServiceError::InternalServerError)?;
+        if !docs.is_empty()
+        {
+            coll.insert_many(docs).await
+                .map_err(|_| ServiceError::InternalServerError)?;
+        }
+        Ok(())
     }
 
+    pub async fn fetch_policies(&self) -> ServiceResult<PolicySet>
+    {
+        dotenv().ok();
+        let database_name = env::var("MONGO_DATABASE")
+            .expect("Variable isn't found: MONGO_DATABASE");
+
+        let coll = self.client.database(database_name.as_str()).collection::<Document>("policies");
+        let mut cursor = coll.find(doc! {})
+            .await
+            .map_err(|_| ServiceError::PolicyFetchError)?;
 
-}
\ No newline at end of file
+        let mut policies = Vec::new();
+        while let Some(policy_doc) = cursor.try_next()
+            .await
+            .map_err(|_| ServiceError::PolicyFetchError)?
+        {
+            let policy: Policy = from_document(policy_doc).map_err(|_| ServiceError::PolicyFetchError)?;
+            policies.push(policy);
+        }
+        PolicySet::new(policies).map_err(ServiceError::InvalidPolicy)
+    }
+}
@@ -20,5 +20,17 @@ pub enum ServiceError {
     UpdateUserError,
 
     #[error("Relational Deserialize Error")]   
-    RelationalDeserializeError
+    RelationalDeserializeError,
+
+    #[error("Policy catalog not found")]
+    PolicyCatalogNotFound,
+
+    #[error("Invalid policy catalog: {0}")]
+    InvalidPolicy(String),
+
+    #[error("Policies could not be loaded")]
+    PolicyFetchError,
+
+    #[error("Invalid role hierarchy: {0}")]
+    InvalidRoleHierarchy(String),
 }
\ No newline at end of file
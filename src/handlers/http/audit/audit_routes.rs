@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use actix_web::{web, web::Query, HttpRequest, HttpResponse, Responder};
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_error::AuditError;
+use crate::core::domain::audit::audit_query::AuditQuery;
+use crate::core::operation::audit_ops::AuditOps;
+
+pub fn config(cfg: &mut web::ServiceConfig)
+{
+    cfg.service(web::scope("/api/audit")
+        .route("", web::get().to(load_entries))
+        .route("/verify", web::get().to(verify_chain)));
+}
+
+async fn load_entries(req: HttpRequest, context: web::Data<Arc<Context>>, query: Query<AuditQuery>) -> impl Responder
+{
+    let audit_ops = AuditOps::new(&context);
+    match audit_ops.load_entries(req, query.into_inner()).await
+    {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(err @ AuditError::InvalidQuery(_)) => HttpResponse::BadRequest().json(err.to_string()),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn verify_chain(req: HttpRequest, context: web::Data<Arc<Context>>) -> impl Responder
+{
+    let audit_ops = AuditOps::new(&context);
+    match audit_ops.verify_chain(req).await
+    {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
@@ -1,26 +1,309 @@
 use std::sync::Arc;
 
-use actix_web::{web, web::Json, HttpResponse, Responder};
+use actix_web::{http::header, web, web::{Form, Json, Path}, HttpRequest, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
 
 use crate::{
     context::Context,
-    core::{domain::auth::auth_type::AuthLogin},
+    core::{domain::auth::auth_type::{AccountProof,
+                                     AuthLogin,
+                                     PasskeyLoginStart,
+                                     PasskeyRemove,
+                                     TotpConfirm,
+                                     TwoFactorLogin,
+                                     TwoFactorPolicy,
+                                     UnlockRequest}},
 };
+use crate::core::domain::audit::audit_type::AuditOrigin;
+use crate::core::domain::auth::auth_access_token::{CreateAccessToken, RevokeAccessToken};
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::auth::auth_introspection::IntrospectionRequest;
+use crate::core::domain::auth::auth_webauthn::{AuthenticationResponse, RegistrationResponse};
+use crate::core::operation::access_token_ops::AccessTokenOps;
 use crate::core::operation::auth_ops::AuthOps;
+use crate::core::operation::introspection_ops::IntrospectionOps;
+use crate::core::operation::passkey_ops::PasskeyOps;
+use crate::core::operation::session_ops::SessionOps;
+use crate::core::operation::two_factor_ops::TwoFactorOps;
+use crate::handlers::http::oauth::oauth_routes::{self, client_credentials};
+use crate::utils::client_ip::client_ip;
 
 pub fn config(cfg: &mut web::ServiceConfig)
 {
-    cfg.service(web::scope("/api/auth").route("/login", web::post().to(login)));
+    cfg.service(web::scope("/api/auth")
+        .route("/login", web::post().to(login))
+        .route("/login/2fa", web::post().to(login_two_factor))
+        .route("/unlock", web::post().to(unlock))
+        .route("/2fa/enroll", web::post().to(enroll_two_factor))
+        .route("/2fa/confirm", web::post().to(confirm_two_factor))
+        .route("/2fa/disable", web::post().to(disable_two_factor))
+        .route("/2fa/policy", web::post().to(two_factor_policy))
+        .route("/passkeys/register/start", web::post().to(start_passkey_registration))
+        .route("/passkeys/register/finish", web::post().to(finish_passkey_registration))
+        .route("/passkeys/login/start", web::post().to(start_passkey_login))
+        .route("/passkeys/login/finish", web::post().to(finish_passkey_login))
+        .route("/passkeys/list", web::post().to(list_passkeys))
+        .route("/passkeys/remove", web::post().to(remove_passkey))
+        .route("/sessions", web::get().to(list_sessions))
+        .route("/sessions/{id}", web::delete().to(revoke_session))
+        .route("/tokens", web::post().to(create_access_token))
+        .route("/tokens/list", web::post().to(list_access_tokens))
+        .route("/tokens/revoke", web::post().to(revoke_access_token))
+        .route("/introspect", web::post().to(introspect)));
 }
 
-async fn login(context: web::Data<Arc<Context>>, payload: Json<AuthLogin>) -> impl Responder
+fn error_response(err: AuthError) -> HttpResponse
+{
+    match err
+    {
+        AuthError::AccountLocked | AuthError::TooManyAttempts => HttpResponse::TooManyRequests().json(err.to_string()),
+        AuthError::AccountSuspended | AuthError::AccountDeactivated => HttpResponse::Forbidden().json(err.to_string()),
+        AuthError::Unauthorized | AuthError::SessionRevoked => HttpResponse::Unauthorized().json(err.to_string()),
+        AuthError::TwoFactorRequired | AuthError::InvalidTwoFactorCode => HttpResponse::Unauthorized().json(err.to_string()),
+        AuthError::SessionNotFound | AuthError::AccessTokenNotFound => HttpResponse::NotFound().json(err.to_string()),
+        AuthError::InvalidAccessToken(_) => HttpResponse::BadRequest().json(err.to_string()),
+        _ => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn login(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AuthLogin>) -> impl Responder
 {
     let auth_repo = context.get_ref().get_auth_repo();
-    let auth_ops = AuthOps::new(&auth_repo);
-    match auth_ops.do_login(payload.into_inner())
+    let auth_ops = AuthOps::new(&auth_repo, &context);
+    match auth_ops.do_login(payload.into_inner(), AuditOrigin::from_request(&req))
                       .await
     {
         Ok(user) => HttpResponse::Ok().json(user),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn login_two_factor(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<TwoFactorLogin>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let auth_ops = AuthOps::new(&auth_repo, &context);
+    match auth_ops.do_login_two_factor(payload.into_inner(), AuditOrigin::from_request(&req))
+                      .await
+    {
+        Ok(token) => HttpResponse::Ok().json(token),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn unlock(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<UnlockRequest>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let auth_ops = AuthOps::new(&auth_repo, &context);
+    match auth_ops.unlock(req, payload.into_inner())
+                      .await
+    {
+        Ok(unlocked) => HttpResponse::Ok().json(unlocked),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn enroll_two_factor(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AuthLogin>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let two_factor_ops = TwoFactorOps::new(&auth_repo, &context);
+    match two_factor_ops.enroll(payload.into_inner(), client_ip(&req))
+                        .await
+    {
+        Ok(enrollment) => HttpResponse::Ok().json(enrollment),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn confirm_two_factor(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<TotpConfirm>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let two_factor_ops = TwoFactorOps::new(&auth_repo, &context);
+    match two_factor_ops.confirm(payload.into_inner(), AuditOrigin::from_request(&req))
+                        .await
+    {
+        Ok(recovery_codes) => HttpResponse::Ok().json(recovery_codes),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn disable_two_factor(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<TotpConfirm>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let two_factor_ops = TwoFactorOps::new(&auth_repo, &context);
+    match two_factor_ops.disable(payload.into_inner(), AuditOrigin::from_request(&req))
+                        .await
+    {
+        Ok(disabled) => HttpResponse::Ok().json(disabled),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn two_factor_policy(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<TwoFactorPolicy>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let two_factor_ops = TwoFactorOps::new(&auth_repo, &context);
+    match two_factor_ops.set_role_policy(req, payload.into_inner())
+                        .await
+    {
+        Ok(policy) => HttpResponse::Ok().json(policy),
         Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
     }
 }
+
+async fn start_passkey_registration(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AccountProof>)
+                                    -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let passkey_ops = PasskeyOps::new(&auth_repo, &context);
+    match passkey_ops.start_registration(payload.into_inner(), AuditOrigin::from_request(&req))
+                     .await
+    {
+        Ok(options) => HttpResponse::Ok().json(options),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn finish_passkey_registration(context: web::Data<Arc<Context>>, payload: Json<RegistrationResponse>)
+                                     -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let passkey_ops = PasskeyOps::new(&auth_repo, &context);
+    match passkey_ops.finish_registration(payload.into_inner())
+                     .await
+    {
+        Ok(passkey) => HttpResponse::Ok().json(passkey),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn start_passkey_login(context: web::Data<Arc<Context>>, payload: Json<PasskeyLoginStart>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let passkey_ops = PasskeyOps::new(&auth_repo, &context);
+    match passkey_ops.start_authentication(payload.into_inner().username)
+                     .await
+    {
+        Ok(options) => HttpResponse::Ok().json(options),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn finish_passkey_login(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AuthenticationResponse>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let passkey_ops = PasskeyOps::new(&auth_repo, &context);
+    match passkey_ops.finish_authentication(payload.into_inner(), AuditOrigin::from_request(&req))
+                     .await
+    {
+        Ok(token) => HttpResponse::Ok().json(token),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn list_passkeys(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AccountProof>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let passkey_ops = PasskeyOps::new(&auth_repo, &context);
+    match passkey_ops.list(payload.into_inner(), AuditOrigin::from_request(&req))
+                     .await
+    {
+        Ok(passkeys) => HttpResponse::Ok().json(passkeys),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn remove_passkey(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<PasskeyRemove>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let passkey_ops = PasskeyOps::new(&auth_repo, &context);
+    let payload = payload.into_inner();
+    let proof = AccountProof { username: payload.username, password: payload.password, code: payload.code };
+    match passkey_ops.remove(proof, payload.credential_id, AuditOrigin::from_request(&req))
+                     .await
+    {
+        Ok(removed) => HttpResponse::Ok().json(removed),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn list_sessions(req: HttpRequest, context: web::Data<Arc<Context>>) -> impl Responder
+{
+    let session_ops = SessionOps::new(&context);
+    match session_ops.list_own(req).await
+    {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn revoke_session(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let session_ops = SessionOps::new(&context);
+    let Ok(session_id) = ObjectId::parse_str(path.into_inner()) else
+    {
+        return error_response(AuthError::SessionNotFound);
+    };
+    match session_ops.revoke_own(req, session_id).await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn create_access_token(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<CreateAccessToken>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let access_token_ops = AccessTokenOps::new(&auth_repo, &context);
+    match access_token_ops.create(payload.into_inner(), AuditOrigin::from_request(&req))
+                          .await
+    {
+        Ok(created) => HttpResponse::Ok().json(created),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn list_access_tokens(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AccountProof>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let access_token_ops = AccessTokenOps::new(&auth_repo, &context);
+    match access_token_ops.list(payload.into_inner(), AuditOrigin::from_request(&req))
+                          .await
+    {
+        Ok(access_tokens) => HttpResponse::Ok().json(access_tokens),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn revoke_access_token(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<RevokeAccessToken>) -> impl Responder
+{
+    let auth_repo = context.get_ref().get_auth_repo();
+    let access_token_ops = AccessTokenOps::new(&auth_repo, &context);
+    match access_token_ops.revoke(payload.into_inner(), AuditOrigin::from_request(&req))
+                          .await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => error_response(err),
+    }
+}
+
+/// RFC 7662 introspection for resource servers. The answer may be cached for the `max-age`
+/// it carries.
+async fn introspect(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Form<IntrospectionRequest>) -> impl Responder
+{
+    let request = payload.into_inner();
+    let credentials = match client_credentials(&req, &request.client_id, &request.client_secret)
+    {
+        Ok(credentials) => credentials,
+        Err(err) => return oauth_routes::error_response(err),
+    };
+    let introspection_ops = IntrospectionOps::new(&context);
+    match introspection_ops.introspect(request, credentials).await
+    {
+        Ok(introspection) => {
+            let cache_control = format!("private, max-age={}", introspection_ops.max_age(&introspection));
+            HttpResponse::Ok().insert_header((header::CACHE_CONTROL, cache_control))
+                              .json(introspection)
+        },
+        Err(err) => oauth_routes::error_response(err),
+    }
+}
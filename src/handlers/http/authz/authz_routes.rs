@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use actix_web::{web, web::Json, HttpRequest, HttpResponse, Responder};
+
+use crate::context::Context;
+use crate::core::domain::authz::authz_error::AuthzError;
+use crate::core::domain::authz::authz_type::{AuthzBatch, AuthzCheck};
+use crate::core::operation::authz_ops::AuthzOps;
+use crate::handlers::http::oauth::oauth_routes::{self, client_credentials};
+
+/// Decision API for the other services. Callers authenticate with their client credentials in
+/// the `Authorization: Basic` header.
+pub fn config(cfg: &mut web::ServiceConfig)
+{
+    cfg.service(web::scope("/api/authz")
+        .route("/check", web::post().to(check))
+        .route("/check/batch", web::post().to(check_batch)));
+}
+
+fn error_response(err: AuthzError) -> HttpResponse
+{
+    match err
+    {
+        AuthzError::Client(err) => oauth_routes::error_response(err),
+        AuthzError::EmptyBatch | AuthzError::BatchTooLarge(_) => HttpResponse::BadRequest().json(err.to_string()),
+        _ => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn check(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AuthzCheck>) -> impl Responder
+{
+    let credentials = match client_credentials(&req, &None, &None)
+    {
+        Ok(credentials) => credentials,
+        Err(err) => return oauth_routes::error_response(err),
+    };
+    match AuthzOps::new(&context).check(credentials, payload.into_inner()).await
+    {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn check_batch(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AuthzBatch>) -> impl Responder
+{
+    let credentials = match client_credentials(&req, &None, &None)
+    {
+        Ok(credentials) => credentials,
+        Err(err) => return oauth_routes::error_response(err),
+    };
+    match AuthzOps::new(&context).check_batch(credentials, payload.into_inner()).await
+    {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(err) => error_response(err),
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use crate::context::Context;
+use crate::core::domain::audit::audit_type::AuditOrigin;
 use crate::core::operation::catalogs_ops::CatalogsOps;
 use crate::data::catalog_importer::MongoCatalogRepo;
 
@@ -12,14 +13,14 @@ pub fn config(cfg: &mut web::ServiceConfig)
             ));
 }
 
-async fn import_catalogs(context: web::Data<Arc<Context>>) -> impl Responder
+async fn import_catalogs(req: HttpRequest, context: web::Data<Arc<Context>>) -> impl Responder
 {
     let client = context.client.as_ref().clone();
     let repo = MongoCatalogRepo::new(client.clone());
     let auth_repo = context.get_ref().auth_repo.clone();
     
-    let catalogs_ops = CatalogsOps::new(&repo, &auth_repo);
-    match catalogs_ops.sync_catalogs()
+    let catalogs_ops = CatalogsOps::new(&repo, &auth_repo, &context);
+    match catalogs_ops.sync_catalogs(AuditOrigin::from_request(&req))
         .await
     {
         Ok(user) => HttpResponse::Ok().json(user),
@@ -1,4 +1,9 @@
+pub mod audit;
 pub mod auth;
+pub mod authz;
 pub mod users;
 pub mod catalogs;
-
+pub mod oauth;
+pub mod oidc;
+pub mod service_accounts;
+pub mod session_guard;
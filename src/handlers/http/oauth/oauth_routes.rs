@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use actix_web::{
+    http::header,
+    web,
+    web::{Form, Json, Path, Query},
+    HttpRequest,
+    HttpResponse,
+    HttpResponseBuilder,
+    Responder,
+};
+use data_encoding::BASE64;
+
+use crate::context::Context;
+use crate::core::domain::audit::audit_type::AuditOrigin;
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::domain::oauth::oauth_authorize::{AuthorizeDecision, AuthorizeRequest};
+use crate::core::domain::oauth::oauth_client::NewOAuthClient;
+use crate::core::domain::oauth::oauth_error::OAuthError;
+use crate::core::domain::oauth::oauth_type::{ClientCredentials, OAuthErrorBody, RevokeRequest, TokenRequest};
+use crate::core::operation::oauth_client_ops::OAuthClientOps;
+use crate::core::operation::oauth_ops::OAuthOps;
+use crate::core::operation::oidc_ops::OidcOps;
+
+pub fn config(cfg: &mut web::ServiceConfig)
+{
+    cfg.service(web::scope("/api/oauth")
+        .route("/authorize", web::get().to(authorize_redirect))
+        .route("/authorize", web::post().to(authorize))
+        .route("/token", web::post().to(token))
+        .route("/revoke", web::post().to(revoke))
+        .route("/userinfo", web::get().to(userinfo))
+        .route("/userinfo", web::post().to(userinfo))
+        .route("/clients", web::post().to(register_client))
+        .route("/clients", web::get().to(list_clients))
+        .route("/clients/{client_id}/disable", web::post().to(disable_client)));
+}
+
+/// Token responses must not be cached (RFC 6749 §5.1).
+fn no_store(mut builder: HttpResponseBuilder) -> HttpResponseBuilder
+{
+    builder.insert_header((header::CACHE_CONTROL, "no-store"))
+           .insert_header((header::PRAGMA, "no-cache"));
+    builder
+}
+
+pub fn error_response(err: OAuthError) -> HttpResponse
+{
+    let mut builder = match &err
+    {
+        OAuthError::InvalidClient => {
+            let mut builder = HttpResponse::Unauthorized();
+            builder.insert_header((header::WWW_AUTHENTICATE, "Basic realm=\"oauth\""));
+            builder
+        },
+        OAuthError::InvalidToken => {
+            let mut builder = HttpResponse::Unauthorized();
+            builder.insert_header((header::WWW_AUTHENTICATE, "Bearer error=\"invalid_token\""));
+            builder
+        },
+        OAuthError::Login(AuthError::AccountLocked | AuthError::TooManyAttempts) => HttpResponse::TooManyRequests(),
+        OAuthError::Login(AuthError::MongoError(_)) => HttpResponse::InternalServerError(),
+        OAuthError::Login(_) => HttpResponse::Unauthorized(),
+        OAuthError::NotHasPermission => HttpResponse::Forbidden(),
+        OAuthError::ServerError | OAuthError::MongoError(_) => HttpResponse::InternalServerError(),
+        _ => HttpResponse::BadRequest(),
+    };
+    let error_description = match &err
+    {
+        OAuthError::MongoError(_) => OAuthError::ServerError.to_string(),
+        err => err.to_string(),
+    };
+    builder = no_store(builder);
+    builder.json(OAuthErrorBody { error: err.code(), error_description })
+}
+
+/// Client credentials from the Basic header or the form, but not both. Client ids and secrets
+/// issued here are URL-safe, so the Basic values need no form decoding.
+pub fn client_credentials(req: &HttpRequest, client_id: &Option<String>, client_secret: &Option<String>)
+                      -> Result<Option<ClientCredentials>, OAuthError>
+{
+    let basic = req.headers()
+                   .get(header::AUTHORIZATION)
+                   .and_then(|value| value.to_str().ok())
+                   .and_then(|value| value.trim().strip_prefix("Basic "));
+    match (basic, client_id)
+    {
+        (Some(_), Some(_)) => Err(OAuthError::InvalidRequest("client authenticated with more than one method".to_string())),
+        (Some(_), None) if client_secret.is_some() => {
+            Err(OAuthError::InvalidRequest("client authenticated with more than one method".to_string()))
+        },
+        (Some(encoded), None) => {
+            let decoded = BASE64.decode(encoded.trim().as_bytes())
+                                .ok()
+                                .and_then(|bytes| String::from_utf8(bytes).ok())
+                                .ok_or(OAuthError::InvalidClient)?;
+            let (client_id, client_secret) = decoded.split_once(':').ok_or(OAuthError::InvalidClient)?;
+            Ok(Some(ClientCredentials { client_id: client_id.to_string(), client_secret: Some(client_secret.to_string()) }))
+        },
+        (None, Some(client_id)) => Ok(Some(ClientCredentials { client_id: client_id.clone(), client_secret: client_secret.clone() })),
+        (None, None) => Ok(None),
+    }
+}
+
+async fn authorize_redirect(context: web::Data<Arc<Context>>, query: Query<AuthorizeRequest>) -> impl Responder
+{
+    let oauth_ops = OAuthOps::new(&context);
+    match oauth_ops.authorize_redirect(query.into_inner()).await
+    {
+        Ok(location) => HttpResponse::Found().insert_header((header::LOCATION, location)).finish(),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn authorize(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AuthorizeDecision>) -> impl Responder
+{
+    let oauth_ops = OAuthOps::new(&context);
+    match oauth_ops.authorize(payload.into_inner(), AuditOrigin::from_request(&req))
+                   .await
+    {
+        Ok(outcome) => no_store(HttpResponse::Ok()).json(outcome),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn token(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Form<TokenRequest>) -> impl Responder
+{
+    let request = payload.into_inner();
+    let credentials = match client_credentials(&req, &request.client_id, &request.client_secret)
+    {
+        Ok(credentials) => credentials,
+        Err(err) => return error_response(err),
+    };
+    let oauth_ops = OAuthOps::new(&context);
+    match oauth_ops.token(request, credentials, AuditOrigin::from_request(&req))
+                   .await
+    {
+        Ok(token) => no_store(HttpResponse::Ok()).json(token),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn revoke(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Form<RevokeRequest>) -> impl Responder
+{
+    let request = payload.into_inner();
+    let credentials = match client_credentials(&req, &request.client_id, &request.client_secret)
+    {
+        Ok(credentials) => credentials,
+        Err(err) => return error_response(err),
+    };
+    let oauth_ops = OAuthOps::new(&context);
+    match oauth_ops.revoke(request, credentials, AuditOrigin::from_request(&req))
+                   .await
+    {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn userinfo(req: HttpRequest, context: web::Data<Arc<Context>>) -> impl Responder
+{
+    let oidc_ops = OidcOps::new(&context);
+    match oidc_ops.userinfo(&req).await
+    {
+        Ok(userinfo) => no_store(HttpResponse::Ok()).json(userinfo),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn register_client(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<NewOAuthClient>) -> impl Responder
+{
+    let oauth_client_ops = OAuthClientOps::new(&context);
+    match oauth_client_ops.register(req, payload.into_inner()).await
+    {
+        Ok(client) => HttpResponse::Ok().json(client),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn list_clients(req: HttpRequest, context: web::Data<Arc<Context>>) -> impl Responder
+{
+    let oauth_client_ops = OAuthClientOps::new(&context);
+    match oauth_client_ops.list(req).await
+    {
+        Ok(clients) => HttpResponse::Ok().json(clients),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn disable_client(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let oauth_client_ops = OAuthClientOps::new(&context);
+    match oauth_client_ops.disable(req, path.into_inner()).await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => error_response(err),
+    }
+}
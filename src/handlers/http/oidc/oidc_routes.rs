@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use actix_web::{http::header, web, HttpResponse, Responder};
+
+use crate::context::Context;
+use crate::core::operation::oidc_ops::OidcOps;
+use crate::handlers::http::oauth::oauth_routes::error_response;
+
+/// Public metadata relying parties fetch before talking to the OAuth endpoints.
+pub fn config(cfg: &mut web::ServiceConfig)
+{
+    cfg.service(web::scope("/.well-known")
+        .route("/openid-configuration", web::get().to(discovery))
+        .route("/jwks.json", web::get().to(jwks)));
+}
+
+async fn discovery(context: web::Data<Arc<Context>>) -> impl Responder
+{
+    match OidcOps::new(&context).discovery()
+    {
+        Ok(document) => HttpResponse::Ok().insert_header((header::CACHE_CONTROL, "public, max-age=3600"))
+                                          .json(document),
+        Err(err) => error_response(err),
+    }
+}
+
+/// Short lived in caches, so a new key is picked up soon after it is published.
+async fn jwks(context: web::Data<Arc<Context>>) -> impl Responder
+{
+    match OidcOps::new(&context).jwks().await
+    {
+        Ok(jwks) => HttpResponse::Ok().insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+                                      .json(jwks),
+        Err(err) => error_response(err),
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use actix_web::{web, web::{Json, Path}, HttpRequest, HttpResponse, Responder};
+
+use crate::context::Context;
+use crate::core::domain::service_account::service_account_error::ServiceAccountError;
+use crate::core::domain::service_account::service_account_type::NewServiceAccount;
+use crate::core::operation::service_account_ops::ServiceAccountOps;
+
+pub fn config(cfg: &mut web::ServiceConfig)
+{
+    cfg.service(web::scope("/api/service-accounts")
+        .route("", web::post().to(create_service_account))
+        .route("", web::get().to(list_service_accounts))
+        .route("/{client_id}/secrets", web::post().to(rotate_secret))
+        .route("/{client_id}/secrets/{secret_id}", web::delete().to(revoke_secret))
+        .route("/{client_id}/disable", web::post().to(disable_service_account)));
+}
+
+fn error_response(err: ServiceAccountError) -> HttpResponse
+{
+    match err
+    {
+        ServiceAccountError::NotHasPermission => HttpResponse::Forbidden().json(err.to_string()),
+        ServiceAccountError::NotFound | ServiceAccountError::SecretNotFound => HttpResponse::NotFound().json(err.to_string()),
+        ServiceAccountError::Invalid(_) | ServiceAccountError::LastSecret | ServiceAccountError::Disabled => {
+            HttpResponse::BadRequest().json(err.to_string())
+        },
+        _ => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn create_service_account(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<NewServiceAccount>) -> impl Responder
+{
+    let service_account_ops = ServiceAccountOps::new(&context);
+    match service_account_ops.create(req, payload.into_inner()).await
+    {
+        Ok(issued) => HttpResponse::Ok().json(issued),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn list_service_accounts(req: HttpRequest, context: web::Data<Arc<Context>>) -> impl Responder
+{
+    let service_account_ops = ServiceAccountOps::new(&context);
+    match service_account_ops.list(req).await
+    {
+        Ok(service_accounts) => HttpResponse::Ok().json(service_accounts),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn rotate_secret(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let service_account_ops = ServiceAccountOps::new(&context);
+    match service_account_ops.rotate_secret(req, path.into_inner()).await
+    {
+        Ok(issued) => HttpResponse::Ok().json(issued),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn revoke_secret(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<(String, String)>) -> impl Responder
+{
+    let service_account_ops = ServiceAccountOps::new(&context);
+    let (client_id, secret_id) = path.into_inner();
+    match service_account_ops.revoke_secret(req, client_id, secret_id).await
+    {
+        Ok(service_account) => HttpResponse::Ok().json(service_account),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn disable_service_account(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let service_account_ops = ServiceAccountOps::new(&context);
+    match service_account_ops.disable(req, path.into_inner()).await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => error_response(err),
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderValue, AUTHORIZATION},
+    middleware::Next,
+    web,
+    Error,
+    HttpResponse,
+};
+
+use crate::context::Context;
+use crate::core::domain::auth::auth_access_token::ACCESS_TOKEN_PREFIX;
+use crate::core::domain::auth::auth_caller::bearer_token;
+use crate::core::domain::auth::auth_error::AuthError;
+use crate::core::operation::access_token_ops::AccessTokenOps;
+use crate::core::operation::session_ops::SessionOps;
+
+/// Rejects requests whose token belongs to a revoked session and keeps `last_seen_at` current.
+/// Personal access tokens are swapped for a login token limited to their scopes before the
+/// request goes on. Signature and permission checks stay with `TokenOps::has_permission` in
+/// each operation.
+pub async fn session_guard(mut req: ServiceRequest, next: Next<impl MessageBody>)
+                           -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error>
+{
+    let context = req.app_data::<web::Data<Arc<Context>>>().cloned();
+    if let (Some(context), Some(token)) = (context, bearer_token(req.request()))
+    {
+        let checked = if token.starts_with(ACCESS_TOKEN_PREFIX)
+        {
+            exchange_access_token(&context, &mut req, &token).await
+        }
+        else
+        {
+            SessionOps::new(&context).check(&token).await
+        };
+
+        if let Err(err) = checked
+        {
+            let response = match err
+            {
+                AuthError::SessionRevoked |
+                AuthError::AccessTokenNotFound |
+                AuthError::AccessTokenRevoked |
+                AuthError::AccessTokenExpired |
+                AuthError::AuthNotFound |
+                AuthError::AccountSuspended |
+                AuthError::AccountDeactivated => HttpResponse::Unauthorized().json(err.to_string()),
+                _ => HttpResponse::InternalServerError().json(err.to_string()),
+            };
+            return Ok(req.into_response(response).map_into_right_body());
+        }
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+/// Replaces the `Authorization` header with the login token the access token stands for.
+async fn exchange_access_token(context: &Context, req: &mut ServiceRequest, token: &str) -> Result<(), AuthError>
+{
+    let auth_repo = context.get_auth_repo();
+    let jwt = AccessTokenOps::new(&auth_repo, context).exchange(token).await?;
+    let value = HeaderValue::from_str(&format!("Bearer {}", jwt)).map_err(|_| AuthError::FailToCreateToken)?;
+    req.headers_mut().insert(AUTHORIZATION, value);
+    Ok(())
+}
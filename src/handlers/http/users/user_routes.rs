@@ -1,8 +1,9 @@
 use std::sync::Arc;
 
 use actix_web::{
+    http::header,
     web,
-    web::{Json},
+    web::{Json, Path, Payload, Query},
     HttpRequest,
     HttpResponse,
     Responder,
@@ -11,8 +12,16 @@ use actix_web::{
 
 use crate::{
     context::Context,
-    core::{domain::user::user_type::NewUser, operation::user_ops::UserOps },
+    core::{
+        domain::user::{user_query::{UserListQuery, UserSearchQuery}, user_status::{StatusChange, SuspendRequest}, user_transfer::{ExportQuery, ImportQuery, TransferFormat}, user_type::{LegacyUser, NewUser}},
+        operation::{privacy_ops::PrivacyOps, session_ops::SessionOps, user_ops::UserOps},
+    },
 };
+use crate::core::domain::audit::audit_type::AuditOrigin;
+use crate::core::domain::auth::auth_type::AccountProof;
+use crate::core::domain::authz::authz_resource::ResourceGrant;
+use crate::core::domain::user::user_error::UserError;
+use crate::utils::domains_ids::UserID;
 
 
 pub fn config(cfg: &mut web::ServiceConfig)
@@ -20,12 +29,29 @@ pub fn config(cfg: &mut web::ServiceConfig)
     cfg.service(web::scope("/api/users")
         .route("/newuser", web::post().to(new_user)).service(web::scope("")
         .route("/all", web::get().to(load_users))
+        .route("/search", web::get().to(search_users))
+        .route("/import", web::post().to(import_users))
+        .route("/import/legacy", web::post().to(import_legacy_users))
+        .route("/export", web::get().to(export_users))
+        .route("/{id}/activate", web::post().to(activate_user))
+        .route("/{id}/suspend", web::post().to(suspend_user))
+        .route("/{id}/deactivate", web::post().to(deactivate_user))
+        .route("/{id}/reactivate", web::post().to(reactivate_user))
+        .route("/{id}/restore", web::post().to(restore_user))
+        .route("/me/data-export", web::post().to(export_own_data))
+        .route("/me/erase", web::post().to(erase_own_data))
+        .route("/{id}/data-export", web::get().to(export_user_data))
+        .route("/{id}/erase", web::post().to(erase_user_data))
+        .route("/{id}/sessions", web::get().to(user_sessions))
+        .route("/{id}/grants", web::post().to(add_resource_grant))
+        .route("/{id}/grants", web::delete().to(remove_resource_grant))
+        .route("/{id}", web::delete().to(delete_user))
         // .route("/username/{una}", web::get().to(load_users_username))
         // .route("/userid/{id}", web::get().to(load_users_id))
     ));
 }
 
-async fn new_user(context: web::Data<Arc<Context>>, payload: Json<NewUser>) -> impl Responder
+async fn new_user(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<NewUser>) -> impl Responder
 {
     let user_repo =  context.get_ref().get_user_repo();
     let auth_repo=   context.get_ref().get_auth_repo();
@@ -33,14 +59,14 @@ async fn new_user(context: web::Data<Arc<Context>>, payload: Json<NewUser>) -> i
     
     let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
 
-    match user_ops.create_user(payload.into_inner(), true).await
+    match user_ops.create_user(payload.into_inner(), true, AuditOrigin::from_request(&req)).await
     {
         Ok(user) => HttpResponse::Ok().json(user),
         Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
     }
 }
 
-async fn load_users(req: HttpRequest, context: web::Data<Arc<Context>>) -> impl Responder
+async fn load_users(req: HttpRequest, context: web::Data<Arc<Context>>, query: Query<UserListQuery>) -> impl Responder
 {
     let user_repo =  context.get_ref().get_user_repo();
     let auth_repo=   context.get_ref().get_auth_repo();
@@ -48,12 +74,256 @@ async fn load_users(req: HttpRequest, context: web::Data<Arc<Context>>) -> impl
 
     let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
 
-    match user_ops.load_users(req).await
+    match user_ops.load_users(req, query.into_inner()).await
     {
         Ok(user) => HttpResponse::Ok().json(user),
         Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
     }
 }
+
+async fn search_users(req: HttpRequest, context: web::Data<Arc<Context>>, query: Query<UserSearchQuery>) -> impl Responder
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+
+    match user_ops.search_users(req, query.into_inner()).await
+    {
+        Ok(users) => HttpResponse::Ok().json(users),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn activate_user(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    change_status(req, context, path.into_inner(), StatusChange::Activate).await
+}
+
+async fn suspend_user(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>, payload: Json<SuspendRequest>) -> impl Responder
+{
+    change_status(req, context, path.into_inner(), payload.into_inner().into()).await
+}
+
+async fn deactivate_user(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    change_status(req, context, path.into_inner(), StatusChange::Deactivate).await
+}
+
+async fn reactivate_user(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    change_status(req, context, path.into_inner(), StatusChange::Reactivate).await
+}
+
+async fn delete_user(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+
+    let Ok(user_id) = UserID::parse_str(&path.into_inner()) else
+    {
+        return HttpResponse::BadRequest().json(UserError::InvalidUserId.to_string());
+    };
+    match user_ops.delete_user(req, user_id).await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn restore_user(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+
+    let Ok(user_id) = UserID::parse_str(&path.into_inner()) else
+    {
+        return HttpResponse::BadRequest().json(UserError::InvalidUserId.to_string());
+    };
+    match user_ops.restore_user(req, user_id).await
+    {
+        Ok(user) => HttpResponse::Ok().json(user),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn export_user_data(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let privacy_ops = PrivacyOps::new(&context);
+    let Ok(user_id) = UserID::parse_str(&path.into_inner()) else
+    {
+        return HttpResponse::BadRequest().json(UserError::InvalidUserId.to_string());
+    };
+    match privacy_ops.export_user(req, user_id).await
+    {
+        Ok(export) => HttpResponse::Ok().json(export),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn export_own_data(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AccountProof>) -> impl Responder
+{
+    let privacy_ops = PrivacyOps::new(&context);
+    match privacy_ops.export_own(payload.into_inner(), AuditOrigin::from_request(&req)).await
+    {
+        Ok(export) => HttpResponse::Ok().json(export),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn erase_user_data(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let privacy_ops = PrivacyOps::new(&context);
+    let Ok(user_id) = UserID::parse_str(&path.into_inner()) else
+    {
+        return HttpResponse::BadRequest().json(UserError::InvalidUserId.to_string());
+    };
+    match privacy_ops.erase_user(req, user_id).await
+    {
+        Ok(user) => HttpResponse::Ok().json(user),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn erase_own_data(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<AccountProof>) -> impl Responder
+{
+    let privacy_ops = PrivacyOps::new(&context);
+    match privacy_ops.erase_own(payload.into_inner(), AuditOrigin::from_request(&req)).await
+    {
+        Ok(user) => HttpResponse::Ok().json(user),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn user_sessions(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>) -> impl Responder
+{
+    let session_ops = SessionOps::new(&context);
+    let Ok(user_id) = UserID::parse_str(&path.into_inner()) else
+    {
+        return HttpResponse::BadRequest().json(UserError::InvalidUserId.to_string());
+    };
+    match session_ops.list_for_user(req, user_id).await
+    {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn add_resource_grant(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>, payload: Json<ResourceGrant>) -> impl Responder
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+
+    let Ok(user_id) = UserID::parse_str(&path.into_inner()) else
+    {
+        return HttpResponse::BadRequest().json(UserError::InvalidUserId.to_string());
+    };
+    match user_ops.add_resource_grant(req, user_id, payload.into_inner()).await
+    {
+        Ok(grants) => HttpResponse::Ok().json(grants),
+        Err(err @ UserError::InvalidResourceGrant(_)) => HttpResponse::BadRequest().json(err.to_string()),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn remove_resource_grant(req: HttpRequest, context: web::Data<Arc<Context>>, path: Path<String>, payload: Json<ResourceGrant>) -> impl Responder
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+
+    let Ok(user_id) = UserID::parse_str(&path.into_inner()) else
+    {
+        return HttpResponse::BadRequest().json(UserError::InvalidUserId.to_string());
+    };
+    match user_ops.remove_resource_grant(req, user_id, payload.into_inner()).await
+    {
+        Ok(grants) => HttpResponse::Ok().json(grants),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn change_status(req: HttpRequest, context: web::Data<Arc<Context>>, id: String, change: StatusChange) -> HttpResponse
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+
+    let Ok(user_id) = UserID::parse_str(&id) else
+    {
+        return HttpResponse::BadRequest().json(UserError::InvalidUserId.to_string());
+    };
+    match user_ops.change_status(req, user_id, change).await
+    {
+        Ok(user) => HttpResponse::Ok().json(user),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn import_legacy_users(req: HttpRequest, context: web::Data<Arc<Context>>, payload: Json<Vec<LegacyUser>>) -> impl Responder
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+
+    match user_ops.import_legacy_users(req, payload.into_inner()).await
+    {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn import_users(req: HttpRequest, context: web::Data<Arc<Context>>, query: Query<ImportQuery>, payload: Payload) -> impl Responder
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+
+    let format = query.format.unwrap_or_else(|| {
+        let content_type = req.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok());
+        TransferFormat::from_content_type(content_type.unwrap_or_default())
+    });
+
+    match user_ops.import_users(req, format, query.dry_run, payload).await
+    {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
+
+async fn export_users(req: HttpRequest, context: web::Data<Arc<Context>>, query: Query<ExportQuery>) -> impl Responder
+{
+    let user_repo =  context.get_ref().get_user_repo();
+    let auth_repo=   context.get_ref().get_auth_repo();
+    let perm_repo=  context.get_ref().get_perm_repo();
+
+    let user_ops = UserOps::new(&user_repo, perm_repo.as_ref(), auth_repo.as_ref(), &context).await;
+    let format = query.format.unwrap_or(TransferFormat::Ndjson);
+
+    match user_ops.export_users(req, format).await
+    {
+        Ok(users) => HttpResponse::Ok().content_type(format.content_type()).streaming(users),
+        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    }
+}
 // 
 // async fn load_users_username(path: Path<String>, context: web::Data<Arc<Context>>) -> impl Responder
 // {
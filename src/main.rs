@@ -3,6 +3,7 @@ use actix_cors::Cors;
 use actix_web::{
     dev::RequestHead,
     http::header::{self, HeaderValue},
+    middleware::from_fn,
     web,
     App,
     HttpServer,
@@ -13,6 +14,10 @@ use env_logger::Env;
 
 use user::data::access::migration::{ MigrationContext};
 use user::data::access::migration::mongo::migrate_mongo;
+use user::core::operation::audit_ops::spawn_checkpoint_task;
+use user::core::operation::retention_ops::spawn_purge_task;
+use user::core::operation::signing_key_ops::spawn_key_rotation_task;
+use user::core::domain::signing_key::KeyCipher;
 
 #[actix_web::main]
 async fn main() -> io::Result<()>
@@ -30,11 +35,20 @@ async fn main() -> io::Result<()>
             std::process::exit(1); // Salida del programa si hay un error crítico
         }
     }
+    // La clave maestra solo llega por el entorno; sin ella no se pueden firmar tokens
+    if let Err(err) = KeyCipher::from_env() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
 
 
+    spawn_purge_task(context.clone());
+    spawn_checkpoint_task(context.clone());
+    spawn_key_rotation_task(context.clone());
 
     HttpServer::new(move || {
-        App::new().wrap(Cors::default().allowed_origin_fn(|origin: &HeaderValue, _req_head: &RequestHead| {
+        App::new().wrap(from_fn(http::session_guard::session_guard)).wrap(Cors::default().allowed_origin_fn(|origin: &HeaderValue, _req_head: &RequestHead| {
             if let Ok(origin_str) = origin.to_str()
             {
                 origin_str == env::var("URL_FRONT_DEV").unwrap()
@@ -45,13 +59,18 @@ async fn main() -> io::Result<()>
                 false
             }
         })
-            .allowed_methods(vec!["GET", "POST", "OPTIONS"])
+            .allowed_methods(vec!["GET", "POST", "DELETE", "OPTIONS"])
             .allowed_headers(vec![header::CONTENT_TYPE, header::AUTHORIZATION])
             .max_age(3600))
             .app_data(web::Data::new(context.clone()))
             .configure(http::users::user_routes::config)
             .configure(http::auth::auth_routes::config)
+            .configure(http::authz::authz_routes::config)
             .configure(http::catalogs::catalog_routes::config)
+            .configure(http::audit::audit_routes::config)
+            .configure(http::oauth::oauth_routes::config)
+            .configure(http::oidc::oidc_routes::config)
+            .configure(http::service_accounts::service_account_routes::config)
     }).bind(env::var("HTTP_BIND").unwrap().to_string())?
         .run()
         .await
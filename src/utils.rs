@@ -1 +1,5 @@
-pub mod domains_ids;
\ No newline at end of file
+pub mod client_ip;
+pub mod domains_ids;
+pub mod env;
+pub mod identifiers;
+pub mod secrets;
\ No newline at end of file
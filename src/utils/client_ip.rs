@@ -0,0 +1,50 @@
+use std::env;
+use std::net::IpAddr;
+
+use actix_web::HttpRequest;
+
+/// Address of the client behind a request. `X-Forwarded-For` is only believed when the
+/// connection comes from one of the `TRUSTED_PROXIES` (comma separated IPs); otherwise any
+/// client could pick the address its attempts are throttled and audited under.
+pub fn client_ip(req: &HttpRequest) -> Option<String>
+{
+    let forwarded_for = req.headers()
+                           .get("x-forwarded-for")
+                           .and_then(|value| value.to_str().ok());
+    resolve_client_ip(req.peer_addr().map(|addr| addr.ip()), forwarded_for, &trusted_proxies()).map(|ip| ip.to_string())
+}
+
+/// Walks `X-Forwarded-For` from the nearest hop back while the hops are trusted proxies; the
+/// first other address is the client. Entries further left were written by the client itself.
+pub fn resolve_client_ip(peer: Option<IpAddr>, forwarded_for: Option<&str>, trusted: &[IpAddr]) -> Option<IpAddr>
+{
+    let peer = peer?;
+    if !trusted.contains(&peer)
+    {
+        return Some(peer);
+    }
+    let mut client = peer;
+    for hop in forwarded_for.unwrap_or_default().rsplit(',')
+    {
+        match hop.trim().parse::<IpAddr>()
+        {
+            Ok(ip) => {
+                client = ip;
+                if !trusted.contains(&ip)
+                {
+                    break;
+                }
+            },
+            Err(_) => break,
+        }
+    }
+    Some(client)
+}
+
+fn trusted_proxies() -> Vec<IpAddr>
+{
+    env::var("TRUSTED_PROXIES").unwrap_or_default()
+                               .split(',')
+                               .filter_map(|ip| ip.trim().parse().ok())
+                               .collect()
+}
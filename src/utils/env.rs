@@ -0,0 +1,9 @@
+use std::{env, str::FromStr};
+
+/// Reads an optional setting, falling back to `default` when unset or unparsable.
+pub fn env_or<T: FromStr>(name: &str, default: T) -> T
+{
+    env::var(name).ok()
+                  .and_then(|value| value.parse().ok())
+                  .unwrap_or(default)
+}
@@ -0,0 +1,68 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// Login identifiers are emails when they contain an `@`, usernames otherwise.
+pub fn is_email(identifier: &str) -> bool
+{
+    identifier.contains('@')
+}
+
+/// Trims and lowercases the address; the domain is converted to its ASCII (punycode) form so
+/// `ÑANDÚ.es` and `xn--and-6ma2c.es` are the same mailbox domain.
+pub fn normalize_email(email: &str) -> Option<String>
+{
+    let email = email.trim();
+    let (local, domain) = email.rsplit_once('@')?;
+    if local.is_empty() || domain.is_empty() || local.chars().any(char::is_whitespace)
+    {
+        return None;
+    }
+
+    let domain = idna::domain_to_ascii(domain).ok()?;
+    if !domain.contains('.') || domain.starts_with('.') || domain.ends_with('.')
+    {
+        return None;
+    }
+    Some(format!("{}@{}", local.nfkc().collect::<String>().to_lowercase(), domain))
+}
+
+/// Usernames compare case-insensitively and without compatibility variants (NFKC).
+pub fn canonical_username(username: &str) -> String
+{
+    username.trim().nfkc().collect::<String>().to_lowercase()
+}
+
+/// Canonical form of a username being registered. Names that are empty or that would read as
+/// an email once normalized (`＠` becomes `@`) are refused: logins couldn't reach them.
+pub fn new_username(username: &str) -> Option<String>
+{
+    let username = canonical_username(username);
+    if username.is_empty() || is_email(&username)
+    {
+        return None;
+    }
+    Some(username)
+}
+
+/// Canonical form used for lookups, whichever kind of identifier was given.
+pub fn normalize_identifier(identifier: &str) -> String
+{
+    if is_email(identifier)
+    {
+        normalize_email(identifier).unwrap_or_else(|| identifier.trim().to_lowercase())
+    }
+    else
+    {
+        canonical_username(identifier)
+    }
+}
+
+/// Escapes user input for use inside a MongoDB (PCRE) regex.
+pub fn regex_escape(value: &str) -> String
+{
+    value.chars()
+         .flat_map(|c| {
+             let escape = "\\^$.|?*+()[]{}".contains(c).then_some('\\');
+             escape.into_iter().chain(std::iter::once(c))
+         })
+         .collect()
+}
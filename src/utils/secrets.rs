@@ -0,0 +1,28 @@
+use data_encoding::{BASE64URL_NOPAD, HEXLOWER};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+pub fn random_bytes(len: usize) -> Vec<u8>
+{
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Opaque, URL-safe token with `len` bytes of entropy.
+pub fn random_token(len: usize) -> String
+{
+    BASE64URL_NOPAD.encode(&random_bytes(len))
+}
+
+/// Hash used to store high-entropy secrets (tokens, recovery codes) so they can still be looked up.
+pub fn sha256_hex(value: &str) -> String
+{
+    HEXLOWER.encode(&Sha256::digest(value.as_bytes()))
+}
+
+/// Compares two digests without short-circuiting on the first mismatching byte.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool
+{
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,79 @@
+use mongodb::bson::DateTime;
+use serde_json::json;
+use user::core::domain::auth::auth_access_token::{expiry_from_secs, AccessToken, CreateAccessToken, RevokeAccessToken, ACCESS_TOKEN_PREFIX};
+use user::core::domain::auth::auth_error::AuthError;
+use user::utils::domains_ids::{AuthID, UserID};
+use user::utils::secrets::sha256_hex;
+
+fn issue(expires_at: Option<DateTime>) -> (AccessToken, String)
+{
+    AccessToken::issue(AuthID::new(), UserID::new(), "ci".to_string(), vec![1, 5], expires_at)
+}
+
+#[test]
+fn issued_tokens_are_prefixed_and_stored_hashed()
+{
+    let (access_token, token) = issue(None);
+    assert!(token.starts_with(ACCESS_TOKEN_PREFIX));
+    assert_eq!(access_token.token_hash, sha256_hex(&token));
+    assert!(!access_token.token_hash.contains(&token));
+    assert_ne!(issue(None).1, token);
+}
+
+#[test]
+fn revoked_and_expired_tokens_are_not_usable()
+{
+    let now = DateTime::now();
+    let (mut access_token, _) = issue(Some(DateTime::from_millis(now.timestamp_millis() + 60_000)));
+    assert!(access_token.ensure_usable(now).is_ok());
+
+    let later = DateTime::from_millis(now.timestamp_millis() + 120_000);
+    assert!(matches!(access_token.ensure_usable(later), Err(AuthError::AccessTokenExpired)));
+
+    access_token.revoked_at = Some(now);
+    assert!(matches!(access_token.ensure_usable(now), Err(AuthError::AccessTokenRevoked)));
+}
+
+#[test]
+fn expiries_must_be_future_and_in_range()
+{
+    let now = DateTime::from_millis(1_000_000);
+    assert_eq!(expiry_from_secs(1_001, now).unwrap(), DateTime::from_millis(1_001_000));
+    assert!(matches!(expiry_from_secs(1_000, now), Err(AuthError::InvalidAccessToken(_))));
+    assert!(matches!(expiry_from_secs(i64::MAX, now), Err(AuthError::InvalidAccessToken(_))));
+    assert!(matches!(expiry_from_secs(i64::MIN, now), Err(AuthError::InvalidAccessToken(_))));
+}
+
+#[test]
+fn last_used_is_only_rewritten_once_stale()
+{
+    let (mut access_token, _) = issue(None);
+    assert!(access_token.needs_touch(DateTime::from_millis(1_000_000), 300));
+
+    access_token.last_used_at = Some(DateTime::from_millis(1_000_000));
+    assert!(!access_token.needs_touch(DateTime::from_millis(1_300_000), 300));
+    assert!(access_token.needs_touch(DateTime::from_millis(1_300_001), 300));
+}
+
+#[test]
+fn scopes_are_capped_by_the_owner_permissions()
+{
+    let (access_token, _) = issue(None);
+    assert_eq!(access_token.effective_permissions(&[1, 2, 5]), vec![1, 5]);
+    assert_eq!(access_token.effective_permissions(&[5]), vec![5]);
+    assert!(access_token.effective_permissions(&[]).is_empty());
+}
+
+#[test]
+fn token_requests_carry_the_second_factor()
+{
+    let request: CreateAccessToken = serde_json::from_value(json!({ "username": "ana", "password": "pw", "code": "123456",
+                                                                    "name": "ci", "scopes": [2] })).unwrap();
+    assert_eq!(request.proof.code.as_deref(), Some("123456"));
+    assert_eq!((request.name.as_str(), request.scopes), ("ci", vec![2]));
+
+    let request: RevokeAccessToken = serde_json::from_value(json!({ "identifier": "ana@example.com", "password": "pw",
+                                                                    "token_id": "abc" })).unwrap();
+    assert_eq!(request.proof.username, "ana@example.com");
+    assert_eq!(request.proof.code, None);
+}
@@ -0,0 +1,61 @@
+use mongodb::bson::DateTime;
+use user::core::domain::auth::auth_error::AuthError;
+use user::core::domain::auth::auth_type::Role;
+use user::core::domain::auth::Auth;
+use user::core::domain::user::user_status::{AccountStatus, StatusChange, Suspension};
+use user::utils::domains_ids::UserID;
+
+fn suspend() -> StatusChange
+{
+    StatusChange::Suspend { reason: "chargeback".to_string(), until: None }
+}
+
+fn auth(status: AccountStatus, suspension: Option<Suspension>) -> Auth
+{
+    Auth { _id: None,
+           user_id: UserID::new(),
+           username: "ana".to_string(),
+           email: "ana@example.com".to_string(),
+           password: String::new(),
+           roles: Role::Client,
+           permissions: Vec::new(),
+           two_factor: None,
+           passkeys: Vec::new(),
+           status,
+           suspension,
+           resource_grants: Vec::new() }
+}
+
+#[test]
+fn allows_only_valid_transitions()
+{
+    use AccountStatus::*;
+
+    assert_eq!(Active.apply(&suspend()).unwrap(), Suspended);
+    assert_eq!(Suspended.apply(&StatusChange::Activate).unwrap(), Active);
+    assert_eq!(Suspended.apply(&StatusChange::Deactivate).unwrap(), Deactivated);
+    assert_eq!(Deactivated.apply(&StatusChange::Reactivate).unwrap(), Active);
+
+    assert!(Active.apply(&StatusChange::Activate).is_err());
+    assert!(Active.apply(&StatusChange::Reactivate).is_err());
+    assert!(Deactivated.apply(&suspend()).is_err());
+    assert!(Deactivated.apply(&StatusChange::Activate).is_err());
+}
+
+#[test]
+fn login_gate_follows_status_and_suspension_end()
+{
+    let now = DateTime::now();
+    let past = DateTime::from_millis(now.timestamp_millis() - 1000);
+    let future = DateTime::from_millis(now.timestamp_millis() + 60_000);
+    let suspension = |until| Some(Suspension { reason: "x".to_string(), until, suspended_at: past });
+
+    assert!(auth(AccountStatus::Active, None).ensure_active(now).is_ok());
+    assert!(matches!(auth(AccountStatus::Suspended, suspension(None)).ensure_active(now),
+                     Err(AuthError::AccountSuspended)));
+    assert!(matches!(auth(AccountStatus::Suspended, suspension(Some(future))).ensure_active(now),
+                     Err(AuthError::AccountSuspended)));
+    assert!(auth(AccountStatus::Suspended, suspension(Some(past))).ensure_active(now).is_ok());
+    assert!(matches!(auth(AccountStatus::Deactivated, None).ensure_active(now),
+                     Err(AuthError::AccountDeactivated)));
+}
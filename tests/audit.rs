@@ -0,0 +1,130 @@
+use mongodb::bson::{from_document, oid::ObjectId, to_document, Bson, DateTime};
+use serde_json::json;
+use user::core::domain::audit::audit_chain::{entry_hash, ChainVerifier, CheckpointSigner, CheckpointVerifier, GENESIS_HASH};
+use user::core::domain::audit::audit_query::AuditQuery;
+use user::core::domain::audit::audit_type::{AuditAction, AuditDiff, AuditOrigin};
+use user::core::domain::audit::AuditEntry;
+
+fn chain(len: i64) -> Vec<AuditEntry>
+{
+    let origin = AuditOrigin { actor: Some("admin".to_string()), ..AuditOrigin::default() };
+    let mut prev_hash = GENESIS_HASH.to_string();
+    (1..=len).map(|seq| {
+                 let mut entry = AuditEntry::new(AuditAction::UserDeleted, &origin)
+                     .with_target(format!("user-{}", seq))
+                     .with_diff(AuditDiff::removed(&json!({ "username": "ana", "permissions": [2, 3] })));
+                 entry.link(seq, prev_hash.clone());
+                 prev_hash = entry.hash.clone();
+                 entry
+             })
+             .collect()
+}
+
+fn verify(entries: &[AuditEntry], verifier: &mut ChainVerifier) -> Option<i64>
+{
+    entries.iter().find_map(|entry| verifier.push(entry).err()).map(|link| link.seq)
+}
+
+#[test]
+fn diff_keeps_only_changed_fields()
+{
+    let before = json!({ "username": "ana", "status": "Active", "permissions": [2] });
+    let after = json!({ "username": "ana", "status": "Suspended", "role": "Admin" });
+
+    let diff = AuditDiff::between(&before, &after);
+    assert_eq!(diff.before, Some(json!({ "status": "Active", "permissions": [2], "role": null })));
+    assert_eq!(diff.after, Some(json!({ "status": "Suspended", "permissions": null, "role": "Admin" })));
+    assert!(AuditDiff::between(&before, &before).is_empty());
+    assert!(!AuditDiff::created(&after).is_empty());
+}
+
+#[test]
+fn query_filter_combines_fields_time_range_and_cursor()
+{
+    let cursor = ObjectId::new();
+    let query = AuditQuery { actor:  Some("65f000000000000000000001".to_string()),
+                             action: Some(AuditAction::LoginFailed),
+                             from:   Some(1_000),
+                             to:     Some(2_000),
+                             cursor: Some(cursor.to_hex()),
+                             ..AuditQuery::default() };
+
+    let filter = query.filter().unwrap();
+    assert_eq!(filter.get_str("action").unwrap(), "LoginFailed");
+    assert_eq!(filter.get_str("actor").unwrap(), "65f000000000000000000001");
+    assert!(filter.get("target").is_none());
+    let at = filter.get_document("at").unwrap();
+    assert_eq!(at.get("$gte"), Some(&Bson::DateTime(DateTime::from_millis(1_000))));
+    assert_eq!(at.get("$lt"), Some(&Bson::DateTime(DateTime::from_millis(2_000))));
+    assert_eq!(filter.get_document("_id").unwrap().get_object_id("$lt").unwrap(), cursor);
+
+    let invalid = AuditQuery { cursor: Some("nope".to_string()), ..AuditQuery::default() };
+    assert!(invalid.filter().is_err());
+}
+
+#[test]
+fn hash_survives_a_bson_round_trip()
+{
+    let entry = chain(1).remove(0);
+    let stored: AuditEntry = from_document(to_document(&entry).unwrap()).unwrap();
+    assert_eq!(entry_hash(&stored), entry.hash);
+}
+
+#[test]
+fn verifier_reports_the_first_broken_link()
+{
+    let entries = chain(5);
+    let mut verifier = ChainVerifier::new(Vec::new());
+    assert_eq!(verify(&entries, &mut verifier), None);
+    let report = verifier.finish(None);
+    assert_eq!((report.verified_entries, report.head_seq), (5, 5));
+    assert!(report.broken.is_none());
+
+    let mut edited = entries.clone();
+    edited[2].actor = Some("someone-else".to_string());
+    assert_eq!(verify(&edited, &mut ChainVerifier::new(Vec::new())), Some(3));
+
+    let mut removed = entries.clone();
+    removed.remove(1);
+    assert_eq!(verify(&removed, &mut ChainVerifier::new(Vec::new())), Some(3));
+}
+
+#[test]
+fn signed_checkpoints_catch_rewrites_and_truncation()
+{
+    let signer = CheckpointSigner::new([7; 32]);
+    let entries = chain(4);
+    let checkpoint = signer.sign(4, entries[3].hash.clone(), DateTime::now());
+    assert!(signer.verifier().verify(&checkpoint));
+    assert!(!CheckpointSigner::new([8; 32]).verifier().verify(&checkpoint));
+
+    let mut forged = checkpoint.clone();
+    forged.hash = GENESIS_HASH.to_string();
+    assert!(!signer.verifier().verify(&forged));
+
+    let mut verifier = ChainVerifier::new(vec![checkpoint.clone()]);
+    assert_eq!(verify(&entries[..3], &mut verifier), None);
+    let report = verifier.finish(None);
+    assert_eq!(report.broken.map(|link| link.seq), Some(4));
+
+    // A consistent rewrite of the last entry still disagrees with the signed hash.
+    let mut rewritten = entries.clone();
+    rewritten[3].detail = Some("rewritten".to_string());
+    let prev_hash = rewritten[3].prev_hash.clone();
+    rewritten[3].link(4, prev_hash);
+    let mut verifier = ChainVerifier::new(vec![checkpoint]);
+    assert_eq!(verify(&rewritten, &mut verifier), Some(4));
+}
+
+#[test]
+fn checkpoints_verify_with_the_public_key_alone()
+{
+    let signer = CheckpointSigner::new([7; 32]);
+    let checkpoint = signer.sign(1, chain(1)[0].hash.clone(), DateTime::now());
+
+    let verifier = CheckpointVerifier::from_base64(&signer.public_key()).unwrap();
+    assert!(verifier.verify(&checkpoint));
+    assert!(!CheckpointVerifier::from_base64(&CheckpointSigner::new([8; 32]).public_key()).unwrap().verify(&checkpoint));
+    assert!(CheckpointVerifier::from_base64("not base64").is_none());
+    assert!(CheckpointVerifier::from_base64(&data_encoding::BASE64.encode(&[1; 16])).is_none());
+}
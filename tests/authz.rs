@@ -0,0 +1,125 @@
+use mongodb::bson::DateTime;
+use serde_json::json;
+use user::core::domain::auth::auth_type::Role;
+use user::core::domain::auth::Auth;
+use user::core::domain::authz::authz_policy::PolicySet;
+use user::core::domain::authz::authz_resource::{Resource, ResourceGrant};
+use user::core::domain::authz::authz_type::{AuthzCheck, AuthzResult};
+use user::core::domain::authz::{decide, Decision, DecisionReason, Effect, Principal};
+use user::core::domain::perm::perm_cat::{CREATE_USER, DELETE_USER, READ_USER, UPDATE_USER};
+use user::core::domain::service_account::ServiceAccount;
+use user::core::domain::user::user_status::AccountStatus;
+use user::utils::domains_ids::UserID;
+
+fn auth(permissions: Vec<u32>, status: AccountStatus) -> Auth
+{
+    Auth { _id: None,
+           user_id: UserID::new(),
+           username: "ana".to_string(),
+           email: "ana@example.com".to_string(),
+           password: String::new(),
+           roles: Role::Admin,
+           permissions,
+           two_factor: None,
+           passkeys: Vec::new(),
+           status,
+           suspension: None,
+           resource_grants: Vec::new() }
+}
+
+#[test]
+fn decisions_tell_role_grants_from_per_user_overrides()
+{
+    let principal = Principal::user(&auth(vec![READ_USER, DELETE_USER], AccountStatus::Active),
+                                    vec![READ_USER, UPDATE_USER],
+                                    DateTime::now());
+
+    assert_eq!(principal.decide(READ_USER, None), Decision::allow(DecisionReason::GrantedByRole));
+    assert_eq!(principal.decide(DELETE_USER, None), Decision::allow(DecisionReason::GrantedToUser));
+    assert_eq!(principal.decide(UPDATE_USER, None), Decision::deny(DecisionReason::RevokedForUser));
+    assert_eq!(principal.decide(CREATE_USER, None), Decision::deny(DecisionReason::NotGranted));
+}
+
+#[test]
+fn inactive_and_unknown_subjects_are_denied()
+{
+    let suspended = Principal::user(&auth(vec![READ_USER], AccountStatus::Suspended), vec![READ_USER], DateTime::now());
+    assert_eq!(suspended.decide(READ_USER, None), Decision::deny(DecisionReason::InactiveSubject));
+    assert_eq!(decide(None, READ_USER, None, &PolicySet::default()), Decision::deny(DecisionReason::UnknownSubject));
+
+    let (mut service_account, _) = ServiceAccount::new("billing".to_string(), String::new(), Role::Client);
+    let principal = Principal::service_account(&service_account, vec![READ_USER]);
+    assert_eq!(principal.subject, service_account.client_id);
+    assert!(principal.decide(READ_USER, None).is_allowed());
+    service_account.disabled_at = Some(DateTime::now());
+    let disabled = Principal::service_account(&service_account, vec![READ_USER]);
+    assert_eq!(decide(Some(&disabled), READ_USER, None, &PolicySet::default()).effect, Effect::Deny);
+}
+
+#[test]
+fn results_carry_the_decision_and_its_reason()
+{
+    let check = AuthzCheck { subject: "64b7f0c2a1b2c3d4e5f60718".to_string(), permission: READ_USER, resource: None };
+    let result = AuthzResult::new(check, Decision::deny(DecisionReason::RevokedForUser));
+    assert_eq!(serde_json::to_value(result).unwrap(),
+               json!({ "subject": "64b7f0c2a1b2c3d4e5f60718", "permission": READ_USER, "effect": "deny", "reason": "revoked_for_user" }));
+}
+
+fn grant(permission: u32, resource: &str) -> ResourceGrant
+{
+    ResourceGrant { permission, resource_type: "user".to_string(), resource: resource.to_string() }
+}
+
+fn user_resource(id: &str) -> Resource
+{
+    Resource::new("user", id)
+}
+
+#[test]
+fn resource_grants_allow_only_the_resources_they_match()
+{
+    let mut auth = auth(vec![READ_USER], AccountStatus::Active);
+    auth.resource_grants = vec![grant(UPDATE_USER, "org-a/*"), grant(DELETE_USER, "org-a/7")];
+    let principal = Principal::user(&auth, vec![READ_USER], DateTime::now());
+
+    let inside = user_resource("org-a/42");
+    assert_eq!(principal.decide(UPDATE_USER, Some(&inside)), Decision::allow(DecisionReason::ScopedGrant));
+    assert_eq!(principal.decide(UPDATE_USER, Some(&user_resource("org-b/42"))), Decision::deny(DecisionReason::OutOfScope));
+    assert_eq!(principal.decide(DELETE_USER, Some(&inside)), Decision::deny(DecisionReason::OutOfScope));
+    assert!(principal.decide(DELETE_USER, Some(&user_resource("org-a/7"))).is_allowed());
+    // Without a resource, a scoped grant is not enough.
+    assert_eq!(principal.decide(UPDATE_USER, None), Decision::deny(DecisionReason::OutOfScope));
+    // Permissions held outright cover every resource.
+    assert_eq!(principal.decide(READ_USER, Some(&inside)), Decision::allow(DecisionReason::GrantedByRole));
+    // Grants of another resource type don't apply.
+    let org = Resource::new("org", "org-a/42");
+    assert_eq!(principal.decide(UPDATE_USER, Some(&org)), Decision::deny(DecisionReason::OutOfScope));
+}
+
+#[test]
+fn patterns_match_whole_ids()
+{
+    let covers = |pattern: &str, id: &str| grant(UPDATE_USER, pattern).covers(UPDATE_USER, &user_resource(id));
+    assert!(covers("*", "anything"));
+    assert!(covers("abc", "abc"));
+    assert!(!covers("abc", "abcd"));
+    assert!(covers("a*c", "abbbc"));
+    assert!(covers("a*b*c", "axbyc"));
+    assert!(!covers("a*b*c", "axyc"));
+    assert!(!covers("ab*b", "ab"));
+    assert!(covers("*-admin", "team-admin"));
+    assert!(!grant(UPDATE_USER, "*").covers(DELETE_USER, &user_resource("x")));
+    assert!(grant(UPDATE_USER, " ").validate().is_err());
+}
+
+#[test]
+fn oauth_tokens_keep_only_the_granted_scopes()
+{
+    let mut auth = auth(vec![READ_USER, DELETE_USER], AccountStatus::Active);
+    auth.resource_grants = vec![grant(UPDATE_USER, "*")];
+    let principal = Principal::user(&auth, vec![READ_USER, DELETE_USER], DateTime::now()).limited_to(&[READ_USER]);
+
+    assert!(principal.decide(READ_USER, None).is_allowed());
+    assert_eq!(principal.decide(DELETE_USER, None), Decision::deny(DecisionReason::NotGranted));
+    assert_eq!(principal.decide(UPDATE_USER, Some(&user_resource("x"))), Decision::deny(DecisionReason::NotGranted));
+}
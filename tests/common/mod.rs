@@ -0,0 +1,44 @@
+use std::env;
+
+use data_encoding::BASE64;
+use user::context::Context;
+use user::core::domain::auth::auth_password::Passwords;
+use user::core::domain::auth::auth_type::Role;
+use user::core::domain::auth::Auth;
+use user::core::domain::user::user_status::AccountStatus;
+use user::db::connect_to_db;
+use user::utils::domains_ids::UserID;
+
+/// Context on a throwaway database of the server at `MONGO_URI`, with a throwaway master key
+/// for the signing keys. Tests using it are `#[ignore]`d; run them with
+/// `cargo test -- --ignored` against a disposable server.
+pub async fn test_context() -> Context
+{
+    let client = connect_to_db().await;
+    env::set_var("MONGO_DATABASE", format!("user_test_{:08x}", rand::random::<u32>()));
+    env::set_var("SIGNING_KEY_ENCRYPTION_KEY", BASE64.encode(&rand::random::<[u8; 32]>()));
+    Context::new(client)
+}
+
+pub async fn drop_database(context: &Context)
+{
+    let db_name = env::var("MONGO_DATABASE").unwrap();
+    context.client.database(&db_name).drop().await.unwrap();
+}
+
+/// Auth record for a new active client account.
+pub fn new_auth(username: &str, password: &str) -> Auth
+{
+    Auth { _id:             None,
+           user_id:         UserID::new(),
+           username:        username.to_string(),
+           email:           format!("{}@example.com", username),
+           password:        Passwords::from_env().hash(password).unwrap(),
+           roles:           Role::Client,
+           permissions:     Vec::new(),
+           two_factor:      None,
+           passkeys:        Vec::new(),
+           status:          AccountStatus::Active,
+           suspension:      None,
+           resource_grants: Vec::new() }
+}
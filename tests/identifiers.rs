@@ -0,0 +1,60 @@
+use user::utils::identifiers::{canonical_username, is_email, new_username, normalize_email, normalize_identifier, regex_escape};
+
+#[test]
+fn emails_are_trimmed_lowercased_and_punycoded()
+{
+    assert_eq!(normalize_email("  Ana.Lopez@Example.COM "), Some("ana.lopez@example.com".to_string()));
+    assert_eq!(normalize_email("ana@ÑANDÚ.es"), Some("ana@xn--and-6ma2c.es".to_string()));
+    assert_eq!(normalize_email("ana@xn--and-6ma2c.es"), normalize_email("ana@ñandú.es"));
+    // The last `@` separates the domain.
+    assert_eq!(normalize_email("\"a@b\"@example.com"), Some("\"a@b\"@example.com".to_string()));
+    // Compatibility forms fold in the local part: a fullwidth `Ａ` is an `a`.
+    assert_eq!(normalize_email("Ａna@example.com"), Some("ana@example.com".to_string()));
+}
+
+#[test]
+fn malformed_emails_are_refused()
+{
+    for email in ["ana", "@example.com", "ana@", "ana@localhost", "ana@.example.com", "ana@example.com.", "a na@example.com"]
+    {
+        assert_eq!(normalize_email(email), None, "{}", email);
+    }
+}
+
+#[test]
+fn usernames_compare_without_case_or_compatibility_variants()
+{
+    assert_eq!(canonical_username("  Ana "), "ana");
+    assert_eq!(canonical_username("ＡＮＡ"), "ana");
+    assert_eq!(canonical_username("ﬁlo"), "filo");
+    assert_eq!(canonical_username("José"), canonical_username("Jose\u{301}"));
+}
+
+#[test]
+fn usernames_that_read_as_emails_are_refused()
+{
+    assert_eq!(new_username(" Ana "), Some("ana".to_string()));
+    // Login would look these up as emails and never find the account.
+    assert_eq!(new_username("ana@home"), None);
+    assert_eq!(new_username("ana＠home"), None);
+    assert_eq!(new_username("   "), None);
+}
+
+#[test]
+fn identifiers_are_normalized_by_kind()
+{
+    assert!(is_email("ana@example.com"));
+    assert!(!is_email("ana"));
+    assert_eq!(normalize_identifier(" Ana@Example.com"), "ana@example.com");
+    assert_eq!(normalize_identifier("ANA"), "ana");
+    // An invalid email is still looked up, just lowercased.
+    assert_eq!(normalize_identifier(" Ana@Localhost "), "ana@localhost");
+}
+
+#[test]
+fn regex_metacharacters_are_escaped()
+{
+    assert_eq!(regex_escape("a.b*c"), "a\\.b\\*c");
+    assert_eq!(regex_escape("(x|y)[z]{2}^$?+\\"), "\\(x\\|y\\)\\[z\\]\\{2\\}\\^\\$\\?\\+\\\\");
+    assert_eq!(regex_escape("plain"), "plain");
+}
@@ -0,0 +1,60 @@
+use serde_json::json;
+use user::core::domain::auth::auth_introspection::{Introspection, TokenDetails};
+use user::core::domain::oauth::format_granted_scope;
+use user::core::domain::oauth::oauth_oidc::IdentityScopes;
+use user::core::domain::service_account::service_account_token::ServiceAccountToken;
+use user::utils::secrets::sha256_hex;
+
+fn details(exp: Option<i64>) -> TokenDetails
+{
+    TokenDetails { sub:         "64b7f0c2a1b2c3d4e5f60718".to_string(),
+                   username:    "ada".to_string(),
+                   role:        "Admin".to_string(),
+                   permissions: vec![1, 3],
+                   scope:       "1 3".to_string(),
+                   exp,
+                   client_id:   None,
+                   token_type:  Some("Bearer"), }
+}
+
+#[test]
+fn inactive_tokens_reveal_nothing_else()
+{
+    assert_eq!(serde_json::to_value(Introspection::inactive()).unwrap(), json!({ "active": false }));
+
+    let active = serde_json::to_value(Introspection::active(details(Some(1_700_000_060)))).unwrap();
+    assert_eq!(active["active"], true);
+    assert_eq!(active["username"], "ada");
+    assert_eq!(active["permissions"], json!([1, 3]));
+    assert_eq!(active["exp"], 1_700_000_060);
+    assert!(active.get("client_id").is_none());
+}
+
+#[test]
+fn answers_are_not_cached_past_the_token_expiry()
+{
+    let now = 1_700_000_000;
+    assert_eq!(Introspection::active(details(Some(now + 600))).max_age(now, 30), 30);
+    assert_eq!(Introspection::active(details(Some(now + 10))).max_age(now, 30), 10);
+    assert_eq!(Introspection::active(details(Some(now - 5))).max_age(now, 30), 0);
+    assert_eq!(Introspection::active(details(None)).max_age(now, 30), 30);
+    assert_eq!(Introspection::inactive().max_age(now, 30), 30);
+}
+
+#[test]
+fn service_account_tokens_carry_only_what_the_role_still_grants()
+{
+    let record = ServiceAccountToken::new("header.claims.signature", "svc_reports".to_string(), vec![1, 4, 7], 1_700_000_600);
+    assert_eq!(record.token_hash, sha256_hex("header.claims.signature"));
+    assert_eq!(record.expires_at.timestamp_millis(), 1_700_000_600_000);
+    assert_eq!(record.effective_permissions(&[1, 2, 7]), vec![1, 7]);
+    assert!(record.effective_permissions(&[]).is_empty());
+}
+
+#[test]
+fn granted_scope_lists_identity_scopes_before_permissions()
+{
+    let identity = IdentityScopes { openid: true, profile: false, email: true };
+    assert_eq!(format_granted_scope(&identity, &[3, 1]), "openid email 3 1");
+    assert_eq!(format_granted_scope(&IdentityScopes::default(), &[2]), "2");
+}
@@ -0,0 +1,86 @@
+use std::net::IpAddr;
+
+use mongodb::bson::DateTime;
+use user::core::domain::auth::auth_lockout::{LockoutPolicy, LoginAttempt, LoginAttemptKey};
+use user::utils::client_ip::resolve_client_ip;
+
+fn policy() -> LockoutPolicy
+{
+    LockoutPolicy { max_attempts_per_account: 5,
+                    max_attempts_per_ip:      20,
+                    backoff_base_secs:        1,
+                    backoff_max_secs:         60,
+                    lockout_secs:             900,
+                    failure_window_secs:      600 }
+}
+
+fn at(secs: i64) -> DateTime
+{
+    DateTime::from_millis(secs * 1000)
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap()
+{
+    let policy = policy();
+    let backoff: Vec<i64> = (0..9).map(|failures| policy.backoff_secs(failures)).collect();
+    assert_eq!(backoff, [0, 1, 2, 4, 8, 16, 32, 60, 60]);
+    assert_eq!(policy.backoff_secs(u32::MAX), 60);
+}
+
+#[test]
+fn accounts_and_ips_have_their_own_thresholds_and_keys()
+{
+    let policy = policy();
+    let account = LoginAttemptKey::Account("ana".to_string());
+    let ip = LoginAttemptKey::Ip("203.0.113.7".to_string());
+    assert_eq!(policy.threshold(&account), 5);
+    assert_eq!(policy.threshold(&ip), 20);
+    assert_eq!(account.as_key(), "account:ana");
+    assert_eq!(ip.as_key(), "ip:203.0.113.7");
+}
+
+#[test]
+fn failure_counts_expire_with_their_window()
+{
+    assert_eq!(policy().window_start(at(1_000)), at(400));
+}
+
+#[test]
+fn locks_and_backoff_end_on_their_own()
+{
+    let attempt = LoginAttempt { _id:               None,
+                                 key:               "ip:203.0.113.7".to_string(),
+                                 failures:          20,
+                                 window_started_at: Some(at(0)),
+                                 last_failure_at:   at(100),
+                                 next_attempt_at:   Some(at(160)),
+                                 locked_until:      Some(at(1_000)) };
+    assert!(attempt.is_throttled(at(150)));
+    assert!(!attempt.is_throttled(at(160)));
+    assert!(attempt.is_locked(at(999)));
+    assert!(!attempt.is_locked(at(1_000)));
+}
+
+fn ip(value: &str) -> IpAddr
+{
+    value.parse().unwrap()
+}
+
+#[test]
+fn forwarded_addresses_count_only_behind_trusted_proxies()
+{
+    let proxy = ip("10.0.0.2");
+    let trusted = [proxy, ip("10.0.0.3")];
+
+    // A direct client can't pick its own address.
+    assert_eq!(resolve_client_ip(Some(ip("198.51.100.4")), Some("203.0.113.9"), &trusted), Some(ip("198.51.100.4")));
+    assert_eq!(resolve_client_ip(Some(proxy), None, &trusted), Some(proxy));
+    assert_eq!(resolve_client_ip(Some(proxy), Some("198.51.100.4"), &trusted), Some(ip("198.51.100.4")));
+    // Entries the client prepended are ignored; the first untrusted hop from the right wins.
+    assert_eq!(resolve_client_ip(Some(proxy), Some("203.0.113.9, 198.51.100.4, 10.0.0.3"), &trusted), Some(ip("198.51.100.4")));
+    // Garbage stops the walk at the last hop a trusted proxy vouched for.
+    assert_eq!(resolve_client_ip(Some(proxy), Some("not-an-ip, 10.0.0.3"), &trusted), Some(ip("10.0.0.3")));
+    assert_eq!(resolve_client_ip(None, Some("198.51.100.4"), &trusted), None);
+    assert_eq!(resolve_client_ip(Some(proxy), Some("198.51.100.4"), &[]), Some(proxy));
+}
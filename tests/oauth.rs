@@ -0,0 +1,136 @@
+use data_encoding::BASE64URL_NOPAD;
+use mongodb::bson::DateTime;
+use sha2::{Digest, Sha256};
+use user::core::domain::oauth::oauth_authorize::{verify_pkce, AuthorizationCode, AuthorizeRequest, OAuthConsent};
+use user::core::domain::oauth::oauth_client::{valid_redirect_uri, NewOAuthClient, OAuthClient};
+use user::core::domain::oauth::oauth_error::OAuthError;
+use user::utils::domains_ids::{AuthID, UserID};
+
+const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+fn challenge(verifier: &str) -> String
+{
+    BASE64URL_NOPAD.encode(&Sha256::digest(verifier.as_bytes()))
+}
+
+fn request() -> AuthorizeRequest
+{
+    AuthorizeRequest { response_type:         "code".to_string(),
+                       client_id:             "client".to_string(),
+                       redirect_uri:          "https://app.example.com/callback".to_string(),
+                       scope:                 Some("1 2".to_string()),
+                       state:                 Some("xyz".to_string()),
+                       code_challenge:        Some(challenge(VERIFIER)),
+                       code_challenge_method: Some("S256".to_string()),
+                       nonce:                 None, }
+}
+
+fn new_client(confidential: bool) -> NewOAuthClient
+{
+    NewOAuthClient { name: "Example".to_string(),
+                     redirect_uris: vec!["https://app.example.com/callback".to_string()],
+                     allowed_scopes: vec![2, 1, 2],
+                     confidential,
+                     first_party: false }
+}
+
+#[test]
+fn pkce_accepts_only_the_matching_verifier()
+{
+    // RFC 7636 appendix B.
+    assert_eq!(challenge(VERIFIER), "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    assert!(verify_pkce(VERIFIER, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"));
+    assert!(!verify_pkce("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXx", "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"));
+
+    let short = "abc";
+    assert!(!verify_pkce(short, &challenge(short)));
+}
+
+#[test]
+fn authorize_request_requires_s256_pkce()
+{
+    assert!(request().validate().is_ok());
+
+    let mut plain = request();
+    plain.code_challenge_method = Some("plain".to_string());
+    assert!(matches!(plain.validate(), Err(OAuthError::InvalidRequest(_))));
+
+    let mut missing = request();
+    missing.code_challenge = None;
+    assert!(matches!(missing.validate(), Err(OAuthError::InvalidRequest(_))));
+
+    let mut token = request();
+    token.response_type = "token".to_string();
+    assert!(matches!(token.validate(), Err(OAuthError::UnsupportedResponseType(_))));
+}
+
+#[test]
+fn redirect_uris_must_be_safe()
+{
+    assert!(valid_redirect_uri("https://app.example.com/callback"));
+    assert!(valid_redirect_uri("http://localhost:8080/callback"));
+    assert!(valid_redirect_uri("http://127.0.0.1/cb"));
+    assert!(valid_redirect_uri("com.example.app:/callback"));
+
+    assert!(!valid_redirect_uri("http://app.example.com/callback"));
+    assert!(!valid_redirect_uri("http://localhost.evil.com/callback"));
+    assert!(!valid_redirect_uri("https://app.example.com/callback#fragment"));
+    assert!(!valid_redirect_uri("javascript:alert(1)"));
+    assert!(!valid_redirect_uri("/relative"));
+}
+
+#[test]
+fn code_redeems_only_for_its_client_redirect_and_verifier()
+{
+    let (code, _) = AuthorizationCode::issue(&request(), AuthID::new(), UserID::new(), vec![1, 2]);
+    let redirect_uri = Some("https://app.example.com/callback");
+
+    assert!(code.redeem("client", redirect_uri, Some(VERIFIER)).is_ok());
+    assert!(matches!(code.redeem("other", redirect_uri, Some(VERIFIER)), Err(OAuthError::InvalidGrant(_))));
+    assert!(matches!(code.redeem("client", Some("https://app.example.com/other"), Some(VERIFIER)),
+                     Err(OAuthError::InvalidGrant(_))));
+    assert!(matches!(code.redeem("client", None, Some(VERIFIER)), Err(OAuthError::InvalidGrant(_))));
+    assert!(matches!(code.redeem("client", redirect_uri, None), Err(OAuthError::InvalidRequest(_))));
+    assert!(matches!(code.redeem("client", redirect_uri, Some("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXx")),
+                     Err(OAuthError::InvalidGrant(_))));
+}
+
+#[test]
+fn public_and_confidential_clients_authenticate_differently()
+{
+    let (public, secret) = OAuthClient::register(new_client(false)).unwrap();
+    assert!(secret.is_none());
+    assert_eq!(public.allowed_scopes, vec![1, 2]);
+    assert!(public.authenticate(None).is_ok());
+    assert!(matches!(public.authenticate(Some("anything")), Err(OAuthError::InvalidClient)));
+
+    let (mut confidential, secret) = OAuthClient::register(new_client(true)).unwrap();
+    let secret = secret.unwrap();
+    assert!(confidential.authenticate(Some(&secret)).is_ok());
+    assert!(matches!(confidential.authenticate(None), Err(OAuthError::InvalidClient)));
+    assert!(matches!(confidential.authenticate(Some("wrong")), Err(OAuthError::InvalidClient)));
+
+    confidential.disabled_at = Some(DateTime::now());
+    assert!(matches!(confidential.authenticate(Some(&secret)), Err(OAuthError::InvalidClient)));
+}
+
+#[test]
+fn registration_rejects_unsafe_redirect_uris()
+{
+    let mut client = new_client(false);
+    client.redirect_uris.push("http://app.example.com/callback".to_string());
+    assert!(matches!(OAuthClient::register(client), Err(OAuthError::InvalidRequest(_))));
+}
+
+#[test]
+fn consent_covers_only_granted_scopes()
+{
+    let consent = OAuthConsent { _id: None,
+                                 user_id: UserID::new(),
+                                 client_id: "client".to_string(),
+                                 scopes: vec![1, 2],
+                                 granted_at: DateTime::now() };
+    assert!(consent.covers(&[1]));
+    assert!(consent.covers(&[1, 2]));
+    assert!(!consent.covers(&[1, 3]));
+}
@@ -0,0 +1,96 @@
+use data_encoding::BASE64URL_NOPAD;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde_json::Value;
+use user::core::domain::oauth::oauth_oidc::{DiscoveryDocument, IdTokenClaims, IdentityScopes, UserInfo};
+use user::core::domain::signing_key::{KeyPair, SigningAlgorithm};
+use user::core::domain::user::user_status::AccountStatus;
+use user::core::domain::user::User;
+use user::utils::domains_ids::UserID;
+
+fn user() -> User
+{
+    User { _id:      Some(UserID::new()),
+           username: "ada".to_string(),
+           email:    "ada@example.com".to_string(),
+           name:     "Ada Lovelace".to_string(),
+           status:   AccountStatus::default(), }
+}
+
+fn decode(part: &str) -> Value
+{
+    serde_json::from_slice(&BASE64URL_NOPAD.decode(part.as_bytes()).unwrap()).unwrap()
+}
+
+#[test]
+fn identity_scopes_are_split_from_permission_scopes()
+{
+    let (identity, permissions) = IdentityScopes::split(Some("openid 3 email 1"));
+    assert_eq!(identity, IdentityScopes { openid: true, profile: false, email: true });
+    assert_eq!(permissions.as_deref(), Some("3 1"));
+    assert_eq!(identity.names(), vec!["openid", "email"]);
+
+    let (identity, permissions) = IdentityScopes::split(Some("openid profile"));
+    assert!(identity.profile);
+    assert_eq!(permissions, None);
+
+    // Without `openid` there is no OpenID request and no claims.
+    let (identity, permissions) = IdentityScopes::split(Some("email 2"));
+    assert_eq!(identity, IdentityScopes::default());
+    assert_eq!(permissions.as_deref(), Some("2"));
+}
+
+#[test]
+fn userinfo_releases_only_the_claims_of_the_granted_scopes()
+{
+    let user = user();
+    let openid = UserInfo::new(&user, &IdentityScopes { openid: true, profile: false, email: false });
+    assert_eq!(openid.sub, user._id.as_ref().unwrap().value().to_hex());
+    assert_eq!((openid.name, openid.preferred_username, openid.email), (None, None, None));
+
+    let full = UserInfo::new(&user, &IdentityScopes { openid: true, profile: true, email: true });
+    assert_eq!(full.name.as_deref(), Some("Ada Lovelace"));
+    assert_eq!(full.preferred_username.as_deref(), Some("ada"));
+    assert_eq!(full.email.as_deref(), Some("ada@example.com"));
+}
+
+#[test]
+fn id_token_verifies_against_the_published_key()
+{
+    let key_pair = KeyPair::generate(SigningAlgorithm::EdDSA).unwrap();
+    let jwk = key_pair.public_jwk();
+    let userinfo = UserInfo::new(&user(), &IdentityScopes { openid: true, profile: false, email: true });
+    let claims = IdTokenClaims::new("https://id.example.com", "client", userinfo, Some("n-0S6_WzA2Mj".to_string()), 1_700_000_000, 3600);
+    let id_token = key_pair.sign_jwt(&claims).unwrap();
+
+    let parts: Vec<&str> = id_token.split('.').collect();
+    assert_eq!(parts.len(), 3);
+    let header = decode(parts[0]);
+    assert_eq!(header["alg"], "EdDSA");
+    assert_eq!(header["kid"], jwk.kid.as_str());
+
+    let payload = decode(parts[1]);
+    assert_eq!(payload["iss"], "https://id.example.com");
+    assert_eq!(payload["aud"], "client");
+    assert_eq!(payload["exp"], 1_700_003_600);
+    assert_eq!(payload["nonce"], "n-0S6_WzA2Mj");
+    assert_eq!(payload["email"], "ada@example.com");
+    assert!(payload.get("name").is_none());
+
+    let key_bytes: [u8; 32] = BASE64URL_NOPAD.decode(jwk.x.unwrap().as_bytes()).unwrap().try_into().unwrap();
+    let key = VerifyingKey::from_bytes(&key_bytes).unwrap();
+    let signature = Signature::from_slice(&BASE64URL_NOPAD.decode(parts[2].as_bytes()).unwrap()).unwrap();
+    let signing_input = format!("{}.{}", parts[0], parts[1]);
+    assert!(key.verify_strict(signing_input.as_bytes(), &signature).is_ok());
+    assert!(key.verify_strict(b"tampered", &signature).is_err());
+}
+
+#[test]
+fn discovery_points_at_this_issuer()
+{
+    let document = DiscoveryDocument::new("https://id.example.com/");
+    assert_eq!(document.issuer, "https://id.example.com");
+    assert_eq!(document.token_endpoint, "https://id.example.com/api/oauth/token");
+    assert_eq!(document.jwks_uri, "https://id.example.com/.well-known/jwks.json");
+    assert!(document.scopes_supported.contains(&"openid"));
+    assert_eq!(document.code_challenge_methods_supported, vec!["S256"]);
+}
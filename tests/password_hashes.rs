@@ -0,0 +1,94 @@
+use user::core::domain::auth::auth_password::{
+    Argon2idHasher,
+    BcryptHasher,
+    PasswordHasher,
+    Passwords,
+    Pbkdf2Sha256Hasher,
+    SaltedMd5Hasher,
+    Sha512CryptHasher,
+};
+
+const DJANGO_HASH: &str = "pbkdf2_sha256$1000$seasalt$mQnueSakb748zqBAC1tmWVZsZbi2zPGZarEzTGdfmso=";
+const SHA512_CRYPT_HASH: &str =
+    "$6$saltstring$svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJuesI68u4OTLiBFdcbYEdFCoEOfaS35inz1";
+const MD5_DIGEST: &str = "1368f3f60b9c67d93b78a1940467fe19";
+
+fn passwords() -> Passwords
+{
+    Passwords::new(Box::new(Argon2idHasher::new(1024, 1, 1).unwrap()),
+                   vec![Box::new(Pbkdf2Sha256Hasher::new(1000)),
+                        Box::new(Sha512CryptHasher),
+                        Box::new(SaltedMd5Hasher),])
+}
+
+#[test]
+fn verifies_foreign_hashes()
+{
+    let passwords = passwords();
+
+    assert!(passwords.verify("correct horse", DJANGO_HASH).unwrap());
+    assert!(!passwords.verify("wrong horse", DJANGO_HASH).unwrap());
+
+    assert!(passwords.verify("Hello world!", SHA512_CRYPT_HASH).unwrap());
+    assert!(!passwords.verify("Hello world", SHA512_CRYPT_HASH).unwrap());
+
+    let md5 = SaltedMd5Hasher.tag(MD5_DIGEST, Some("abc"));
+    assert_eq!(md5, format!("md5$abc${}", MD5_DIGEST));
+    assert!(passwords.verify("correct horse", &md5).unwrap());
+    assert!(!passwords.verify("correct horse", &SaltedMd5Hasher.tag(MD5_DIGEST, Some("abd"))).unwrap());
+}
+
+#[test]
+fn foreign_hashes_are_upgraded()
+{
+    let passwords = passwords();
+    for stored in [DJANGO_HASH, SHA512_CRYPT_HASH]
+    {
+        assert!(passwords.needs_rehash(stored));
+    }
+
+    let upgraded = passwords.hash("correct horse").unwrap();
+    assert!(upgraded.starts_with("$argon2id$"));
+    assert!(!passwords.needs_rehash(&upgraded));
+    assert!(passwords.verify("correct horse", &upgraded).unwrap());
+}
+
+#[test]
+fn rejects_malformed_and_unknown_hashes()
+{
+    let passwords = passwords();
+    let pbkdf2 = passwords.hasher("pbkdf2_sha256").unwrap();
+
+    assert!(!pbkdf2.recognizes("pbkdf2_sha256$0$salt$mQnueSakb748zqBAC1tmWVZsZbi2zPGZarEzTGdfmso="));
+    assert!(!pbkdf2.recognizes("pbkdf2_sha256$1000$salt"));
+    assert!(!SaltedMd5Hasher.recognizes("md5$abc$not-hex"));
+    assert!(passwords.hasher("sha1").is_none());
+    assert!(passwords.verify("anything", "plaintext").is_err());
+}
+
+#[test]
+fn bcrypt_can_be_the_preferred_scheme()
+{
+    let passwords = Passwords::new(Box::new(BcryptHasher::new(4)), vec![Box::new(Argon2idHasher::new(1024, 1, 1).unwrap())]);
+    let argon2 = Argon2idHasher::new(1024, 1, 1).unwrap().hash("correct horse").unwrap();
+    assert!(passwords.verify("correct horse", &argon2).unwrap());
+    assert!(passwords.needs_rehash(&argon2));
+
+    let hashed = passwords.hash("correct horse").unwrap();
+    assert!(hashed.starts_with("$2"));
+    assert!(!passwords.needs_rehash(&hashed));
+    assert!(!passwords.verify("wrong horse", &hashed).unwrap());
+}
+
+#[actix_web::test]
+async fn hashing_off_the_worker_matches_the_blocking_calls()
+{
+    let passwords = passwords();
+    let hashed = passwords.hash_async("correct horse").await.unwrap();
+    assert!(hashed.starts_with("$argon2id$"));
+    assert!(passwords.verify("correct horse", &hashed).unwrap());
+
+    assert!(passwords.verify_async("correct horse", DJANGO_HASH).await.unwrap());
+    assert!(!passwords.verify_async("wrong horse", DJANGO_HASH).await.unwrap());
+    assert!(passwords.verify_async("anything", "plaintext").await.is_err());
+}
@@ -0,0 +1,167 @@
+use serde::Deserialize;
+use serde_json::json;
+use user::core::domain::auth::auth_type::Role;
+use user::core::domain::authz::authz_policy::{AccessRequest, Policy, PolicyDecision, PolicySet};
+use user::core::domain::authz::authz_resource::Resource;
+use user::core::domain::authz::{decide, DecisionReason, Effect, Principal};
+use user::core::domain::perm::perm_cat::{READ_USER, UPDATE_USER};
+use user::data::catalog_importer::MongoCatalogRepo;
+use user::error::ServiceError;
+
+const POLICIES: &str = include_str!("fixtures/policies.json");
+const CASES: &str = include_str!("fixtures/policy_cases.json");
+
+#[derive(Deserialize)]
+struct Subject
+{
+    id:          String,
+    role:        Role,
+    permissions: Vec<u32>,
+}
+
+/// One row of the case table: what the policies alone decide, which policy decides it, and
+/// the final decision once the subject's permissions are taken into account.
+#[derive(Deserialize)]
+struct Case
+{
+    name:     String,
+    subject:  Subject,
+    action:   u32,
+    resource: Option<Resource>,
+    policies: String,
+    policy:   Option<String>,
+    decision: Effect,
+}
+
+fn principal(subject: &Subject) -> Principal
+{
+    Principal { subject:          subject.id.clone(),
+                role:             subject.role.clone(),
+                role_permissions: subject.permissions.clone(),
+                permissions:      subject.permissions.clone(),
+                resource_grants:  Vec::new(),
+                active:           true }
+}
+
+fn outcome(decision: &PolicyDecision) -> (&str, Option<String>)
+{
+    match decision
+    {
+        PolicyDecision::NotApplicable => ("not_applicable", None),
+        PolicyDecision::Allow(policy) => ("allow", Some(policy.clone())),
+        PolicyDecision::Deny(policy) => ("deny", Some(policy.clone())),
+    }
+}
+
+#[test]
+fn policy_catalog_decides_every_case()
+{
+    let policies = PolicySet::from_json(POLICIES).expect("policy catalog is valid");
+    let cases: Vec<Case> = serde_json::from_str(CASES).expect("case table is valid");
+
+    let mut mismatches = Vec::new();
+    for case in &cases
+    {
+        let principal = principal(&case.subject);
+        let evaluated = policies.evaluate(&AccessRequest::new(&principal, case.action, case.resource.as_ref()));
+        let (effect, policy) = outcome(&evaluated);
+        if effect != case.policies || policy != case.policy
+        {
+            mismatches.push(format!("{}: policies gave {} {:?}, expected {} {:?}", case.name, effect, policy, case.policies, case.policy));
+        }
+        let decision = decide(Some(&principal), case.action, case.resource.as_ref(), &policies);
+        if decision.effect != case.decision
+        {
+            mismatches.push(format!("{}: decided {:?} ({:?}), expected {:?}", case.name, decision.effect, decision.reason, case.decision));
+        }
+    }
+    assert!(mismatches.is_empty(), "{} of {} cases failed:\n{}", mismatches.len(), cases.len(), mismatches.join("\n"));
+}
+
+fn policy(value: serde_json::Value) -> Policy
+{
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn invalid_catalogs_are_refused()
+{
+    let valid = json!({ "id": "a", "effect": "allow", "actions": [READ_USER] });
+    assert!(PolicySet::new(vec![policy(valid.clone())]).is_ok());
+    assert!(PolicySet::new(vec![policy(valid.clone()), policy(valid)]).unwrap_err().contains("twice"));
+    assert!(PolicySet::new(vec![policy(json!({ "id": "a", "effect": "allow", "actions": [] }))]).is_err());
+    assert!(PolicySet::new(vec![policy(json!({ "id": " ", "effect": "allow", "actions": [READ_USER] }))]).is_err());
+    let unknown = json!({
+        "id": "a",
+        "effect": "deny",
+        "actions": [READ_USER],
+        "conditions": [{ "op": "equals", "attribute": "subject.id", "value": { "attribute": "tenant.id" } }]
+    });
+    assert!(PolicySet::new(vec![policy(unknown)]).unwrap_err().contains("tenant.id"));
+    assert!(PolicySet::from_json(r#"[{ "id": "a", "effect": "maybe", "actions": [2] }]"#).is_err());
+}
+
+#[test]
+fn deny_overrides_and_ties_follow_id_order()
+{
+    let rule = |id: &str, effect: &str| policy(json!({ "id": id, "effect": effect, "actions": [UPDATE_USER] }));
+    let subject = Subject { id: "ana".to_string(), role: Role::Admin, permissions: vec![UPDATE_USER] };
+    let request = AccessRequest::new(&principal(&subject), UPDATE_USER, None);
+
+    let policies = PolicySet::new(vec![rule("c", "allow"), rule("b", "deny"), rule("a", "allow"), rule("d", "deny")]).unwrap();
+    assert_eq!(policies.evaluate(&request), PolicyDecision::Deny("b".to_string()));
+    let ids: Vec<&str> = policies.policies().iter().map(|policy| policy.id.as_str()).collect();
+    assert_eq!(ids, ["a", "b", "c", "d"]);
+
+    let allows = PolicySet::new(vec![rule("z", "allow"), rule("m", "allow")]).unwrap();
+    assert_eq!(allows.evaluate(&request), PolicyDecision::Allow("m".to_string()));
+    assert_eq!(allows.evaluate(&AccessRequest::new(&principal(&subject), READ_USER, None)), PolicyDecision::NotApplicable);
+
+    let decision = decide(Some(&principal(&subject)), UPDATE_USER, None, &policies);
+    assert_eq!(decision.reason, DecisionReason::DeniedByPolicy);
+    assert_eq!(decision.policy.as_deref(), Some("b"));
+}
+
+#[test]
+fn conditions_compare_attributes()
+{
+    let subject = Subject { id: "ana".to_string(), role: Role::Client, permissions: vec![READ_USER] };
+    let principal = principal(&subject);
+    let resource = Resource::new("user", "bob").with_attribute("tags", json!(["vip", "eu"]))
+                                               .with_attribute("owner", "ana");
+    let applies = |condition: serde_json::Value| {
+        let set = PolicySet::new(vec![policy(json!({ "id": "p", "effect": "allow", "actions": [READ_USER], "conditions": [condition] }))]).unwrap();
+        set.evaluate(&AccessRequest::new(&principal, READ_USER, Some(&resource))) != PolicyDecision::NotApplicable
+    };
+
+    assert!(applies(json!({ "op": "equals", "attribute": "resource.owner", "value": { "attribute": "subject.id" } })));
+    assert!(!applies(json!({ "op": "equals", "attribute": "resource.id", "value": { "attribute": "subject.id" } })));
+    assert!(applies(json!({ "op": "not_equals", "attribute": "resource.id", "value": { "attribute": "subject.id" } })));
+    assert!(applies(json!({ "op": "in", "attribute": "subject.role", "values": ["Client", "Visitor"] })));
+    assert!(!applies(json!({ "op": "in", "attribute": "subject.role", "values": ["Admin"] })));
+    assert!(applies(json!({ "op": "contains", "attribute": "resource.tags", "value": "vip" })));
+    assert!(applies(json!({ "op": "contains", "attribute": "subject.permissions", "value": READ_USER })));
+    assert!(applies(json!({ "op": "equals", "attribute": "action", "value": READ_USER })));
+    // Missing attributes fail every test, even not_equals.
+    assert!(!applies(json!({ "op": "not_equals", "attribute": "resource.region", "value": "eu" })));
+    assert!(!applies(json!({ "op": "equals", "attribute": "resource.region", "value": null })));
+}
+
+#[actix_web::test]
+async fn policy_catalog_is_checked_before_import_and_optional()
+{
+    let dir = std::env::temp_dir().join(format!("policy_catalog_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("CATALOGS_PATH", &dir);
+    // The client only connects when used; loading never touches the database.
+    let repo = MongoCatalogRepo::new(mongodb::Client::with_uri_str("mongodb://localhost:1").await.unwrap());
+
+    assert!(repo.load_policies().unwrap().policies().is_empty());
+
+    std::fs::write(dir.join("policies.json"), POLICIES).unwrap();
+    assert_eq!(repo.load_policies().unwrap().policies().len(), PolicySet::from_json(POLICIES).unwrap().policies().len());
+
+    std::fs::write(dir.join("policies.json"), "{ not json").unwrap();
+    assert!(matches!(repo.load_policies(), Err(ServiceError::InvalidPolicy(_))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,96 @@
+use mongodb::bson::{doc, DateTime};
+use user::core::domain::audit::audit_query::involving;
+use user::core::domain::auth::auth_session::{Session, SessionSummary};
+use user::core::domain::auth::auth_totp::TwoFactor;
+use user::core::domain::auth::auth_type::{AccountProof, Role};
+use user::core::domain::auth::Auth;
+use user::core::domain::oauth::oauth_authorize::OAuthConsent;
+use user::core::domain::user::user_privacy::{AccountExport, ConsentExport, ErasedIdentity};
+use user::core::domain::user::user_status::AccountStatus;
+use user::utils::domains_ids::{AuthID, UserID};
+
+fn auth(user_id: UserID) -> Auth
+{
+    Auth { _id: None,
+           user_id,
+           username: "ana".to_string(),
+           email: "ana@example.com".to_string(),
+           password: "$argon2id$v=19$m=1024,t=1,p=1$c2FsdA$aGFzaA".to_string(),
+           roles: Role::Client,
+           permissions: vec![2],
+           two_factor: Some(TwoFactor { enabled: true,
+                                        recovery_codes: vec!["code-hash".to_string()],
+                                        ..TwoFactor::pending("TOTPSEED".to_string()) }),
+           passkeys: Vec::new(),
+           status: AccountStatus::Active,
+           suspension: None,
+           resource_grants: Vec::new() }
+}
+
+#[test]
+fn self_service_requests_accept_a_second_factor()
+{
+    let proof: AccountProof = serde_json::from_value(serde_json::json!({ "identifier": "ana", "password": "pw", "code": "123456" })).unwrap();
+    assert_eq!(proof.username, "ana");
+    assert_eq!(proof.code.as_deref(), Some("123456"));
+
+    let proof: AccountProof = serde_json::from_value(serde_json::json!({ "username": "ana", "password": "pw" })).unwrap();
+    assert_eq!(proof.code, None);
+}
+
+#[test]
+fn account_export_leaves_out_secrets()
+{
+    let export = serde_json::to_string(&AccountExport::from(&auth(UserID::new()))).unwrap();
+    assert!(export.contains("\"two_factor_enabled\":true"));
+    assert!(export.contains("\"recovery_codes_left\":1"));
+    for secret in ["argon2id", "TOTPSEED", "code-hash"]
+    {
+        assert!(!export.contains(secret), "{}", secret);
+    }
+}
+
+#[test]
+fn sessions_and_consents_are_exported_without_token_hashes()
+{
+    let user_id = UserID::new();
+    let session = Session { _id:          None,
+                            auth_id:      AuthID::new(),
+                            user_id:      user_id.clone(),
+                            token_hash:   "token-hash".to_string(),
+                            method:       "totp".to_string(),
+                            ip:           Some("198.51.100.4".to_string()),
+                            user_agent:   None,
+                            created_at:   DateTime::from_millis(1_000),
+                            last_seen_at: DateTime::from_millis(2_000),
+                            expires_at:   DateTime::from_millis(3_000),
+                            revoked_at:   None };
+    let summary = serde_json::to_string(&SessionSummary::new(&session, None)).unwrap();
+    assert!(summary.contains("198.51.100.4"));
+    assert!(!summary.contains("token-hash"));
+
+    let consent = ConsentExport::from(OAuthConsent { _id: None,
+                                                     user_id,
+                                                     client_id: "reports".to_string(),
+                                                     scopes: vec![2, 3],
+                                                     granted_at: DateTime::from_millis(5_000) });
+    assert_eq!((consent.client_id.as_str(), consent.scopes, consent.granted_at), ("reports", vec![2, 3], 5_000));
+}
+
+#[test]
+fn audit_entries_are_found_by_actor_or_target()
+{
+    let user_id = UserID::new().to_string();
+    assert_eq!(involving(&user_id), doc! { "$or": [{ "actor": &user_id }, { "target": &user_id }] });
+}
+
+#[test]
+fn erased_identities_derive_from_the_id()
+{
+    let user_id = UserID::new();
+    let identity = ErasedIdentity::for_user(&user_id);
+    let id = user_id.value().to_hex();
+    assert_eq!(identity.username, format!("erased-{}", id));
+    assert_eq!(identity.email, format!("erased-{}@erased.invalid", id));
+    assert!(identity.name.is_empty());
+}
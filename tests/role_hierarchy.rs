@@ -0,0 +1,74 @@
+use user::core::domain::auth::auth_type::Role;
+use user::core::domain::perm::perm_hierarchy::RoleHierarchy;
+use user::core::domain::perm::perm_type::PermsRelationship;
+
+const CATALOG: &str = include_str!("fixtures/perms_relationship.json");
+
+fn role(role: Role, perms: Vec<u32>, parents: Vec<Role>) -> PermsRelationship
+{
+    PermsRelationship { role, perms, parents }
+}
+
+#[test]
+fn catalog_roles_inherit_their_parents_permissions()
+{
+    let relationships: Vec<PermsRelationship> = serde_json::from_str(CATALOG).unwrap();
+    let hierarchy = RoleHierarchy::new(&relationships).unwrap();
+
+    assert_eq!(hierarchy.permissions(&Role::SuperAdmin), Some(&[1, 2, 3, 4, 7, 8, 9][..]));
+    assert_eq!(hierarchy.permissions(&Role::Admin), Some(&[2, 3, 4][..]));
+    assert_eq!(hierarchy.permissions(&Role::Client), Some(&[2][..]));
+    assert_eq!(hierarchy.permissions(&Role::Visitor), Some(&[2][..]));
+}
+
+#[test]
+fn roles_without_parents_keep_their_own_permissions()
+{
+    let relationships: Vec<PermsRelationship> = serde_json::from_str(r#"[{ "role": "Client", "perms": [2] }]"#).unwrap();
+    let hierarchy = RoleHierarchy::new(&relationships).unwrap();
+    assert_eq!(hierarchy.permissions(&Role::Client), Some(&[2][..]));
+    assert_eq!(hierarchy.permissions(&Role::Admin), None);
+}
+
+#[test]
+fn inheritance_is_transitive_and_shared_ancestors_count_once()
+{
+    let hierarchy = RoleHierarchy::new(&[role(Role::SuperAdmin, vec![9], vec![Role::Admin, Role::Client]),
+                                         role(Role::Admin, vec![4, 3], vec![Role::Visitor]),
+                                         role(Role::Client, vec![5], vec![Role::Visitor]),
+                                         role(Role::Visitor, vec![2], vec![])]).unwrap();
+
+    assert_eq!(hierarchy.permissions(&Role::SuperAdmin), Some(&[2, 3, 4, 5, 9][..]));
+    assert_eq!(hierarchy.permissions(&Role::Admin), Some(&[2, 3, 4][..]));
+    let permissions = hierarchy.into_permissions();
+    assert_eq!(permissions.len(), 4);
+    assert_eq!(permissions[&Role::Client], vec![2, 5]);
+}
+
+#[test]
+fn roles_listed_twice_are_merged()
+{
+    let hierarchy = RoleHierarchy::new(&[role(Role::Admin, vec![3], vec![]),
+                                         role(Role::Visitor, vec![2], vec![]),
+                                         role(Role::Admin, vec![4], vec![Role::Visitor])]).unwrap();
+    assert_eq!(hierarchy.permissions(&Role::Admin), Some(&[2, 3, 4][..]));
+}
+
+#[test]
+fn cycles_are_refused()
+{
+    let err = RoleHierarchy::new(&[role(Role::Admin, vec![3], vec![Role::Admin])]).unwrap_err();
+    assert_eq!(err, "roles inherit from each other: Admin -> Admin");
+
+    let err = RoleHierarchy::new(&[role(Role::SuperAdmin, vec![1], vec![Role::Admin]),
+                                   role(Role::Admin, vec![3], vec![Role::Client]),
+                                   role(Role::Client, vec![2], vec![Role::SuperAdmin])]).unwrap_err();
+    assert_eq!(err, "roles inherit from each other: SuperAdmin -> Admin -> Client -> SuperAdmin");
+}
+
+#[test]
+fn parents_must_be_in_the_catalog()
+{
+    let err = RoleHierarchy::new(&[role(Role::Admin, vec![3], vec![Role::Visitor])]).unwrap_err();
+    assert_eq!(err, "role Admin inherits from Visitor, which isn't in the catalog");
+}
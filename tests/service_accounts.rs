@@ -0,0 +1,65 @@
+use mongodb::bson::DateTime;
+use user::core::domain::auth::auth_type::Role;
+use user::core::domain::oauth::grant_scopes;
+use user::core::domain::oauth::oauth_error::OAuthError;
+use user::core::domain::service_account::service_account_error::ServiceAccountError;
+use user::core::domain::service_account::ServiceAccount;
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+
+fn at(now: DateTime, offset_ms: i64) -> DateTime
+{
+    DateTime::from_millis(now.timestamp_millis() + offset_ms)
+}
+
+#[test]
+fn rotation_keeps_the_previous_secret_during_the_overlap()
+{
+    let (mut service_account, first) = ServiceAccount::new("billing".to_string(), String::new(), Role::Client);
+    let now = DateTime::now();
+    assert!(service_account.verify_secret(&first, now));
+
+    let second = service_account.rotate_secret(now, HOUR_MS);
+    assert!(service_account.verify_secret(&first, at(now, HOUR_MS / 2)));
+    assert!(service_account.verify_secret(&second, at(now, HOUR_MS / 2)));
+    assert!(!service_account.verify_secret(&first, at(now, HOUR_MS)));
+    assert!(service_account.verify_secret(&second, at(now, 10 * HOUR_MS)));
+    assert!(!service_account.verify_secret("not-a-secret", now));
+}
+
+#[test]
+fn at_most_two_secrets_are_active()
+{
+    let (mut service_account, first) = ServiceAccount::new("billing".to_string(), String::new(), Role::Client);
+    let now = DateTime::now();
+    let second = service_account.rotate_secret(now, HOUR_MS);
+    let third = service_account.rotate_secret(at(now, 1000), HOUR_MS);
+
+    assert_eq!(service_account.secrets.len(), 2);
+    assert!(!service_account.verify_secret(&first, at(now, 2000)));
+    assert!(service_account.verify_secret(&second, at(now, 2000)));
+    assert!(service_account.verify_secret(&third, at(now, 2000)));
+}
+
+#[test]
+fn the_last_active_secret_cannot_be_revoked()
+{
+    let (mut service_account, _) = ServiceAccount::new("billing".to_string(), String::new(), Role::Client);
+    let now = DateTime::now();
+    let only = service_account.secrets[0].id.clone();
+    assert!(matches!(service_account.revoke_secret(&only, now), Err(ServiceAccountError::LastSecret)));
+
+    let current = service_account.rotate_secret(now, HOUR_MS);
+    assert!(service_account.revoke_secret(&only, now).is_ok());
+    assert!(service_account.verify_secret(&current, now));
+    assert!(matches!(service_account.revoke_secret(&only, now), Err(ServiceAccountError::SecretNotFound)));
+}
+
+#[test]
+fn requested_scopes_must_be_held()
+{
+    assert_eq!(grant_scopes(None, &[2, 3]).unwrap(), vec![2, 3]);
+    assert_eq!(grant_scopes(Some("3 3"), &[2, 3]).unwrap(), vec![3]);
+    assert!(matches!(grant_scopes(Some("2 7"), &[2, 3]), Err(OAuthError::InvalidScope(_))));
+    assert_eq!(grant_scopes(Some("read"), &[2]).unwrap_err().code(), "invalid_scope");
+}
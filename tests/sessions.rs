@@ -0,0 +1,44 @@
+use user::core::domain::audit::audit_type::AuditOrigin;
+use mongodb::bson::DateTime;
+use user::core::domain::auth::auth_session::{touch_cutoff, Session, SessionSummary};
+use user::utils::domains_ids::{AuthID, UserID};
+use user::utils::secrets::sha256_hex;
+
+fn session(token: &str) -> Session
+{
+    let origin = AuditOrigin { ip: Some("10.0.0.1".to_string()), user_agent: Some("curl/8".to_string()), ..AuditOrigin::default() };
+    Session::new(AuthID::new(), UserID::new(), token, "password", &origin)
+}
+
+#[test]
+fn sessions_store_only_the_token_hash()
+{
+    let session = session("header.payload.signature");
+    assert_eq!(session.token_hash, sha256_hex("header.payload.signature"));
+    assert_eq!(session.user_agent.as_deref(), Some("curl/8"));
+    assert!(session.expires_at > session.created_at);
+    assert!(session.revoked_at.is_none());
+}
+
+#[test]
+fn summary_marks_the_calling_session()
+{
+    let session = session("token-a");
+    let current = sha256_hex("token-a");
+    assert!(SessionSummary::new(&session, Some(&current)).current);
+    assert!(!SessionSummary::new(&session, Some(&sha256_hex("token-b"))).current);
+    assert!(!SessionSummary::new(&session, None).current);
+}
+
+#[test]
+fn last_seen_is_only_rewritten_once_stale()
+{
+    let mut session = session("token-a");
+    session.last_seen_at = DateTime::from_millis(1_000_000);
+
+    assert_eq!(touch_cutoff(DateTime::from_millis(1_300_000), 300), DateTime::from_millis(1_000_000));
+    assert!(!session.needs_touch(DateTime::from_millis(1_000_000), 300));
+    assert!(!session.needs_touch(DateTime::from_millis(1_300_000), 300));
+    assert!(session.needs_touch(DateTime::from_millis(1_300_001), 300));
+    assert!(session.needs_touch(DateTime::from_millis(1_000_001), 0));
+}
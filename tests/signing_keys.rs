@@ -0,0 +1,110 @@
+use data_encoding::BASE64URL_NOPAD;
+use mongodb::bson::DateTime;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use user::core::domain::signing_key::signing_key_error::SigningKeyError;
+use user::core::domain::signing_key::{verify_jwt, KeyCipher, SigningAlgorithm, SigningKey, SigningKeyStatus};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn decode(part: &str) -> Vec<u8>
+{
+    BASE64URL_NOPAD.decode(part.as_bytes()).unwrap()
+}
+
+#[test]
+fn generated_keys_are_stored_encrypted_and_come_back_intact()
+{
+    let cipher = KeyCipher::new([1; 32]);
+    let (signing_key, key_pair) = SigningKey::generate(SigningAlgorithm::EdDSA, &cipher).unwrap();
+    assert_eq!(signing_key.status, SigningKeyStatus::Active);
+    assert_eq!(signing_key.kid, signing_key.public_jwk.kid);
+    assert!(signing_key.expires_at.is_none());
+
+    let restored = signing_key.key_pair(&cipher).unwrap();
+    assert_eq!(restored.public_jwk(), key_pair.public_jwk());
+
+    let other_cipher = KeyCipher::new([2; 32]);
+    assert!(matches!(signing_key.key_pair(&other_cipher), Err(SigningKeyError::DecryptionFailed(_))));
+}
+
+#[test]
+fn ciphertext_is_bound_to_its_kid()
+{
+    let cipher = KeyCipher::new([3; 32]);
+    let encrypted = cipher.encrypt("kid-a", b"private").unwrap();
+    assert_eq!(cipher.decrypt("kid-a", &encrypted).unwrap(), b"private");
+    assert!(cipher.decrypt("kid-b", &encrypted).is_err());
+    assert!(cipher.decrypt("kid-a", "bm90IGVub3VnaA==").is_err());
+}
+
+#[test]
+fn rs256_tokens_carry_the_kid_and_verify_with_the_jwk()
+{
+    let cipher = KeyCipher::new([4; 32]);
+    let (signing_key, key_pair) = SigningKey::generate(SigningAlgorithm::RS256, &cipher).unwrap();
+    let jwk = &signing_key.public_jwk;
+    assert_eq!((jwk.kty.as_str(), jwk.alg.as_str(), jwk.key_use.as_str()), ("RSA", "RS256", "sig"));
+
+    let token = key_pair.sign_jwt(&json!({ "sub": "42" })).unwrap();
+    let parts: Vec<&str> = token.split('.').collect();
+    let header: Value = serde_json::from_slice(&decode(parts[0])).unwrap();
+    assert_eq!(header["alg"], "RS256");
+    assert_eq!(header["kid"], signing_key.kid.as_str());
+
+    let n = BigUint::from_bytes_be(&decode(jwk.n.as_deref().unwrap()));
+    let e = BigUint::from_bytes_be(&decode(jwk.e.as_deref().unwrap()));
+    let verifying_key = VerifyingKey::<Sha256>::new(RsaPublicKey::new(n, e).unwrap());
+    let signature = Signature::try_from(decode(parts[2]).as_slice()).unwrap();
+    let signing_input = format!("{}.{}", parts[0], parts[1]);
+    assert!(verifying_key.verify(signing_input.as_bytes(), &signature).is_ok());
+    assert!(verifying_key.verify(b"tampered", &signature).is_err());
+}
+
+#[test]
+fn tokens_only_verify_with_the_key_named_by_their_kid()
+{
+    let cipher = KeyCipher::new([6; 32]);
+    let (rsa_key, rsa_pair) = SigningKey::generate(SigningAlgorithm::RS256, &cipher).unwrap();
+    let (ed_key, ed_pair) = SigningKey::generate(SigningAlgorithm::EdDSA, &cipher).unwrap();
+    let keys = [rsa_key.public_jwk.clone(), ed_key.public_jwk.clone()];
+
+    for key_pair in [&rsa_pair, &ed_pair]
+    {
+        let token = key_pair.sign_jwt(&json!({ "sub": "42" })).unwrap();
+        let claims: Value = verify_jwt(&token, &keys).unwrap();
+        assert_eq!(claims["sub"], "42");
+        assert!(verify_jwt::<Value>(&token, &keys[..0]).is_none());
+
+        let (signing_input, _) = token.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}", signing_input, BASE64URL_NOPAD.encode(b"forged"));
+        assert!(verify_jwt::<Value>(&forged, &keys).is_none());
+    }
+
+    // A key published under another kid doesn't vouch for the token.
+    let mut renamed = rsa_key.public_jwk.clone();
+    renamed.kid = ed_key.kid.clone();
+    let token = rsa_pair.sign_jwt(&json!({ "sub": "42" })).unwrap();
+    assert!(verify_jwt::<Value>(&token, &[renamed]).is_none());
+}
+
+#[test]
+fn keys_are_due_after_the_rotation_period()
+{
+    let cipher = KeyCipher::new([5; 32]);
+    let (signing_key, _) = SigningKey::generate(SigningAlgorithm::EdDSA, &cipher).unwrap();
+    let created = signing_key.created_at.timestamp_millis();
+    assert!(!signing_key.is_due(DateTime::from_millis(created + 29 * DAY_MS), 30 * DAY_MS));
+    assert!(signing_key.is_due(DateTime::from_millis(created + 30 * DAY_MS), 30 * DAY_MS));
+}
+
+#[test]
+fn algorithm_names_round_trip()
+{
+    assert_eq!("RS256".parse::<SigningAlgorithm>().unwrap(), SigningAlgorithm::RS256);
+    assert_eq!(SigningAlgorithm::EdDSA.to_string(), "EdDSA");
+    assert!(matches!("HS256".parse::<SigningAlgorithm>(), Err(SigningKeyError::UnsupportedAlgorithm(_))));
+}
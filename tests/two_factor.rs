@@ -0,0 +1,75 @@
+use user::core::domain::auth::auth_totp::{generate_recovery_codes, otpauth_uri, totp_at, verify_totp, TwoFactor};
+
+/// The RFC 6238 SHA-1 seed, `12345678901234567890`, in base32.
+const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+#[test]
+fn codes_match_the_rfc_6238_vectors()
+{
+    // Appendix B lists 8 digit codes; ours are their last 6 digits.
+    let vectors = [(59, "287082"),
+                   (1_111_111_109, "081804"),
+                   (1_111_111_111, "050471"),
+                   (1_234_567_890, "005924"),
+                   (2_000_000_000, "279037"),
+                   (20_000_000_000, "353130")];
+    for (unix_secs, code) in vectors
+    {
+        assert_eq!(totp_at(RFC_SECRET, unix_secs / 30).as_deref(), Some(code), "at {}", unix_secs);
+        assert_eq!(verify_totp(RFC_SECRET, code, unix_secs), Some(unix_secs / 30));
+    }
+    assert_eq!(totp_at("not base32!", 1), None);
+}
+
+#[test]
+fn codes_are_accepted_one_step_either_side()
+{
+    let now = 1_234_567_890;
+    let step = now / 30;
+    let code_at = |step: i64| totp_at(RFC_SECRET, step).unwrap();
+
+    assert_eq!(verify_totp(RFC_SECRET, &code_at(step - 1), now), Some(step - 1));
+    assert_eq!(verify_totp(RFC_SECRET, &code_at(step + 1), now), Some(step + 1));
+    assert_eq!(verify_totp(RFC_SECRET, &code_at(step - 2), now), None);
+    assert_eq!(verify_totp(RFC_SECRET, &code_at(step + 2), now), None);
+    assert_eq!(verify_totp(RFC_SECRET, &format!(" {} ", code_at(step)), now), Some(step));
+}
+
+#[test]
+fn used_steps_cannot_be_replayed()
+{
+    let now = 1_234_567_890;
+    let step = now / 30;
+    let mut two_factor = TwoFactor::pending(RFC_SECRET.to_string());
+    let code = totp_at(RFC_SECRET, step).unwrap();
+
+    assert_eq!(two_factor.verify_code(&code, now), Some(step));
+    two_factor.last_used_step = Some(step);
+    assert_eq!(two_factor.verify_code(&code, now), None);
+    // An older code still inside the window was issued before the one used.
+    assert_eq!(two_factor.verify_code(&totp_at(RFC_SECRET, step - 1).unwrap(), now), None);
+    assert_eq!(two_factor.verify_code(&totp_at(RFC_SECRET, step + 1).unwrap(), now), Some(step + 1));
+}
+
+#[test]
+fn recovery_codes_work_once()
+{
+    let (plain, hashed) = generate_recovery_codes();
+    assert_eq!(plain.len(), 10);
+    assert!(plain.iter().all(|code| code.len() == 11 && code.as_bytes()[5] == b'-'));
+    assert!(!hashed.iter().any(|hash| plain.contains(hash)));
+
+    let mut two_factor = TwoFactor { recovery_codes: hashed, ..TwoFactor::pending(RFC_SECRET.to_string()) };
+    // Case and dashes don't matter.
+    assert!(two_factor.consume_recovery_code(&plain[0].to_uppercase().replace('-', "")));
+    assert!(!two_factor.consume_recovery_code(&plain[0]));
+    assert_eq!(two_factor.recovery_codes.len(), 9);
+}
+
+#[test]
+fn otpauth_uris_escape_the_labels()
+{
+    assert_eq!(otpauth_uri("Acme Corp", "ana@example.com", RFC_SECRET),
+               format!("otpauth://totp/Acme%20Corp:ana%40example.com?secret={}&issuer=Acme%20Corp&algorithm=SHA1&digits=6&period=30",
+                       RFC_SECRET));
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::options::IndexOptions;
+use mongodb::{Database, IndexModel};
+use user::core::domain::user::user_deletion::{soft_delete_update, tombstone, DeletedIdentity, DeletedUser, RetentionPolicy};
+use user::core::domain::user::user_error::UserError;
+use user::core::domain::user::User;
+use user::utils::identifiers::{new_username, normalize_email};
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+fn policy() -> RetentionPolicy
+{
+    RetentionPolicy { retention_secs: 30 * 24 * 60 * 60, purge_interval_secs: 3600 }
+}
+
+fn user(username: &str) -> User
+{
+    User { _id:      None,
+           username: username.to_string(),
+           email:    format!("{}@example.com", username),
+           name:     "Ana".to_string(),
+           status:   Default::default() }
+}
+
+fn deleted_on(day: i64) -> DeletedUser
+{
+    DeletedUser { user:             User { username: tombstone("64b7f0c2a1e4d3b2c1a09f8e"),
+                                           email: tombstone("64b7f0c2a1e4d3b2c1a09f8e"),
+                                           ..user("ana") },
+                  deleted_at:       DateTime::from_millis(day * DAY_MILLIS),
+                  deleted_by:       None,
+                  deleted_identity: DeletedIdentity { username: "ana".to_string(), email: "ana@example.com".to_string() } }
+}
+
+#[test]
+fn cutoff_is_the_retention_window_before_now()
+{
+    assert_eq!(policy().cutoff(DateTime::from_millis(40 * DAY_MILLIS)), DateTime::from_millis(10 * DAY_MILLIS));
+}
+
+#[test]
+fn users_are_restorable_within_the_window()
+{
+    let now = DateTime::from_millis(40 * DAY_MILLIS);
+    assert!(deleted_on(10).check_restorable(&policy(), now, false, false).is_ok());
+    assert!(matches!(deleted_on(9).check_restorable(&policy(), now, false, false), Err(UserError::RetentionExpired)));
+}
+
+#[test]
+fn identifiers_taken_since_the_deletion_block_the_restore()
+{
+    let now = DateTime::from_millis(20 * DAY_MILLIS);
+    let deleted = deleted_on(10);
+    assert!(matches!(deleted.check_restorable(&policy(), now, true, false), Err(UserError::EmailIsUsed)));
+    assert!(matches!(deleted.check_restorable(&policy(), now, false, true), Err(UserError::AlreadyExists)));
+    assert!(matches!(deleted.check_restorable(&policy(), now, true, true), Err(UserError::EmailIsUsed)));
+}
+
+#[test]
+fn deleted_users_set_their_identifiers_aside()
+{
+    let placeholder = tombstone("64b7f0c2a1e4d3b2c1a09f8e");
+    assert_eq!(new_username(&placeholder), None);
+    assert_eq!(normalize_email(&placeholder), None);
+
+    let update = soft_delete_update(&placeholder, DateTime::from_millis(0), Some("admin".to_string()));
+    let set = update[0].get_document("$set").unwrap();
+    assert_eq!(set.get_document("deleted_identity").unwrap(), &doc! { "username": "$username", "email": "$email" });
+    assert_eq!(set.get_document("email").unwrap(), &doc! { "$literal": placeholder.clone() });
+
+    let restored = deleted_on(10).into_user();
+    assert_eq!((restored.username.as_str(), restored.email.as_str()), ("ana", "ana@example.com"));
+}
+
+async fn create_unique_index(db: &Database, collection: &str, field: &str)
+{
+    let index = IndexModel::builder().keys(doc! { field: 1 })
+                                     .options(IndexOptions::builder().unique(true).build())
+                                     .build();
+    db.collection::<Document>(collection).create_index(index).await.unwrap();
+}
+
+#[actix_web::test]
+#[ignore = "needs the MongoDB server at MONGO_URI"]
+async fn soft_deleted_emails_can_be_registered_again()
+{
+    let context = common::test_context().await;
+    let db = context.client.database(&std::env::var("MONGO_DATABASE").unwrap());
+    // The same unique indexes migration v05 creates.
+    create_unique_index(&db, "users", "email").await;
+    create_unique_index(&db, "auth", "username").await;
+    create_unique_index(&db, "auth", "email").await;
+
+    let first = context.user_repo.create(user("ana")).await.unwrap();
+    let first_id = first._id.clone().unwrap();
+    let first_auth = context.auth_repo.create(common::new_auth("ana", "pw")).await.unwrap();
+    context.auth_repo.delete(first_auth._id.clone().unwrap(), None).await.unwrap();
+    context.user_repo.delete(first_id.clone(), None).await.unwrap();
+
+    // The unique indexes no longer see the deleted account.
+    let second = context.user_repo.create(user("ana")).await.unwrap();
+    let second_auth = context.auth_repo.create(common::new_auth("ana", "pw")).await.unwrap();
+
+    let deleted = context.user_repo.fetch_deleted(first_id.clone()).await.unwrap();
+    assert_eq!(deleted.deleted_identity.email, "ana@example.com");
+    let email_taken = context.user_repo.fetch_by_email(deleted.deleted_identity.email.clone()).await.is_ok();
+    let username_taken = context.auth_repo.fetch_by_username(deleted.deleted_identity.username.clone()).await.is_ok();
+    assert!(matches!(deleted.check_restorable(&RetentionPolicy::from_env(), DateTime::now(), email_taken, username_taken),
+                     Err(UserError::EmailIsUsed)));
+
+    // Once the newcomer is gone the first account gets its identifiers back.
+    context.auth_repo.delete(second_auth._id.unwrap(), None).await.unwrap();
+    context.user_repo.delete(second._id.unwrap(), None).await.unwrap();
+    context.user_repo.restore(first_id.clone()).await.unwrap();
+    context.auth_repo.restore(first_auth._id.unwrap()).await.unwrap();
+    assert_eq!(context.user_repo.fetch_by_id(first_id).await.unwrap().email, "ana@example.com");
+    assert!(context.auth_repo.fetch_by_username("ana".to_string()).await.is_ok());
+
+    common::drop_database(&context).await;
+}
@@ -0,0 +1,95 @@
+use mongodb::bson::{doc, from_document, oid::ObjectId, Bson};
+use user::core::domain::user::user_query::{
+    search_prefix_filter,
+    PageCursor,
+    SortOrder,
+    UserListQuery,
+    UserSearchHit,
+    UserSearchQuery,
+    UserSortField,
+};
+
+#[test]
+fn cursor_round_trips_and_rejects_garbage()
+{
+    let cursor = PageCursor { id: ObjectId::new(), value: Some("ana".to_string()) };
+    let decoded = PageCursor::decode(&cursor.encode()).unwrap();
+    assert_eq!(decoded.id, cursor.id);
+    assert_eq!(decoded.value.as_deref(), Some("ana"));
+
+    assert!(PageCursor::decode("not-a-cursor").is_err());
+}
+
+#[test]
+fn keyset_filter_follows_sort_field_and_order()
+{
+    let id = ObjectId::new();
+    let cursor = PageCursor { id, value: Some("ana".to_string()) };
+
+    let by_id = UserListQuery::default();
+    assert_eq!(by_id.cursor_filter(&cursor), doc! { "_id": { "$gt": id } });
+    assert_eq!(by_id.sort(), doc! { "_id": 1 });
+
+    let by_username = UserListQuery { sort: UserSortField::Username, order: SortOrder::Desc, ..UserListQuery::default() };
+    assert_eq!(by_username.cursor_filter(&cursor),
+               doc! { "$or": [{ "username": { "$lt": "ana" } }, { "username": "ana", "_id": { "$lt": id } }] });
+    assert_eq!(by_username.sort(), doc! { "username": -1, "_id": -1 });
+}
+
+#[test]
+fn validates_projection_and_page_size()
+{
+    let query = UserListQuery { fields: Some("username, role".to_string()), limit: Some(10_000), ..UserListQuery::default() };
+    assert_eq!(query.projected_fields().unwrap(), vec!["username", "role"]);
+    assert!(query.needs_role(&query.projected_fields().unwrap()));
+    assert_eq!(query.page_size(), 200);
+
+    let query = UserListQuery { fields: Some("password".to_string()), ..UserListQuery::default() };
+    assert!(query.projected_fields().is_err());
+}
+
+#[test]
+fn prefix_filters_are_escaped_and_anchored()
+{
+    let query = UserListQuery { username_prefix: Some("A.b".to_string()), ..UserListQuery::default() };
+    let Some(Bson::RegularExpression(regex)) = query.user_filter().get("username").cloned() else { panic!("no regex") };
+    assert_eq!(regex.pattern, "^a\\.b");
+}
+
+#[test]
+fn search_terms_are_trimmed_and_required()
+{
+    let query = UserSearchQuery { q: "  José Pérez ".to_string(), limit: None };
+    assert_eq!(query.terms().unwrap(), "José Pérez");
+    assert_eq!(query.limit(), 20);
+
+    assert!(UserSearchQuery { q: " \t".to_string(), limit: None }.terms().is_err());
+    assert_eq!(UserSearchQuery { q: "ana".to_string(), limit: Some(0) }.limit(), 1);
+    assert_eq!(UserSearchQuery { q: "ana".to_string(), limit: Some(10_000) }.limit(), 100);
+}
+
+#[test]
+fn search_top_up_matches_escaped_prefixes_of_every_field()
+{
+    let found = ObjectId::new();
+    let filter = search_prefix_filter("j.p", vec![Bson::ObjectId(found)]);
+    assert_eq!(filter.get_document("_id").unwrap(), &doc! { "$nin": [found] });
+
+    let fields = filter.get_array("$or").unwrap();
+    assert_eq!(fields.len(), 3);
+    for (field, condition) in ["username", "email", "name"].into_iter().zip(fields)
+    {
+        let Some(Bson::RegularExpression(regex)) = condition.as_document().and_then(|c| c.get(field)).cloned() else { panic!("no regex on {}", field) };
+        assert_eq!(regex.pattern, "^j\\.p");
+        assert_eq!(regex.options, "i");
+    }
+}
+
+#[test]
+fn search_hits_read_the_text_score()
+{
+    let id = ObjectId::new();
+    let hit: UserSearchHit = from_document(doc! { "_id": id, "username": "jose", "email": "jose@example.com", "name": "José", "score": 10.5 }).unwrap();
+    assert_eq!(hit.username, "jose");
+    assert_eq!(hit.score, 10.5);
+}
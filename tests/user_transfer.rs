@@ -0,0 +1,71 @@
+use user::core::domain::auth::auth_type::Role;
+use user::core::domain::user::user_transfer::{encode, ExportedUser, RecordParser, RecordSplitter, TransferFormat};
+
+fn split(format: TransferFormat, chunks: &[&[u8]]) -> Vec<Vec<u8>>
+{
+    let mut splitter = RecordSplitter::new(format);
+    let mut records: Vec<Vec<u8>> = chunks.iter().flat_map(|chunk| splitter.push(chunk).unwrap()).collect();
+    records.extend(splitter.finish());
+    records
+}
+
+#[test]
+fn csv_records_survive_chunk_boundaries_and_quoted_newlines()
+{
+    let records = split(TransferFormat::Csv,
+                        &[b"username,email,name,password,role\r\nana,ana@example.com,\"Ana\nMar",
+                          b"\xc3\xada\",secret,Admin\nluis,luis@example.com,Luis,secret,"]);
+    assert_eq!(records.len(), 3);
+
+    let mut parser = RecordParser::new(TransferFormat::Csv);
+    let rows: Vec<_> = records.iter().filter_map(|record| parser.parse(record)).collect();
+    assert_eq!(rows.len(), 2);
+
+    let ana = rows[0].as_ref().unwrap();
+    assert_eq!(ana.name, "Ana\nMaría");
+    assert_eq!(ana.role, Some(Role::Admin));
+    let luis = rows[1].as_ref().unwrap();
+    assert_eq!(luis.username, "luis");
+    assert_eq!(luis.role, None);
+}
+
+#[test]
+fn ndjson_rows_report_their_own_errors()
+{
+    let records = split(TransferFormat::Ndjson,
+                        &[b"{\"username\":\"ana\",\"email\":\"ana@example.com\",\"name\":\"Ana\",\"password\":\"x\"}\n\n",
+                          b"{\"username\":\"luis\"}\n{not json"]);
+
+    let mut parser = RecordParser::new(TransferFormat::Ndjson);
+    let rows: Vec<_> = records.iter().filter_map(|record| parser.parse(record)).collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].is_ok());
+    assert!(rows[1].is_err());
+    assert!(rows[2].is_err());
+}
+
+#[test]
+fn rejects_records_without_line_breaks()
+{
+    let mut splitter = RecordSplitter::new(TransferFormat::Ndjson);
+    assert!(splitter.push(&vec![b'a'; 70 * 1024]).is_err());
+}
+
+#[test]
+fn encodes_export_lines()
+{
+    let user = ExportedUser { id:       "65f000000000000000000001".to_string(),
+                              username: "ana".to_string(),
+                              email:    "ana@example.com".to_string(),
+                              name:     "Ana, María".to_string(),
+                              role:     Some(Role::Client), };
+
+    let csv = encode(TransferFormat::Csv, &user).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(),
+               "65f000000000000000000001,ana,ana@example.com,\"Ana, María\",Client\n");
+
+    let line = encode(TransferFormat::Ndjson, &user).unwrap();
+    assert_eq!(line.last(), Some(&b'\n'));
+    let decoded: ExportedUser = serde_json::from_slice(&line).unwrap();
+    assert_eq!(decoded.role, Some(Role::Client));
+}
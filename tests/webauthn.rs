@@ -0,0 +1,242 @@
+mod common;
+
+use ciborium::value::Value;
+use data_encoding::BASE64URL_NOPAD;
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use user::core::domain::audit::audit_type::AuditOrigin;
+use user::core::domain::auth::auth_error::AuthError;
+use user::core::domain::auth::auth_type::AccountProof;
+use user::core::domain::auth::auth_webauthn::{
+    verify_authentication,
+    verify_registration,
+    AssertionResponse,
+    AttestationResponse,
+    AuthenticationResponse,
+    RegistrationResponse,
+    RelyingParty,
+};
+use user::core::operation::passkey_ops::PasskeyOps;
+use user::core::operation::token_ops::TokenOps;
+
+/// Minimal software authenticator: one ES256 credential, `none` attestation.
+struct SoftAuthenticator
+{
+    credential_id: Vec<u8>,
+    key:           SigningKey,
+    counter:       u32,
+}
+
+impl SoftAuthenticator
+{
+    fn new() -> Self
+    {
+        let mut credential_id = vec![0u8; 16];
+        OsRng.fill_bytes(&mut credential_id);
+        Self { credential_id, key: SigningKey::random(&mut OsRng), counter: 0 }
+    }
+
+    fn client_data(kind: &str, challenge: &str, origin: &str) -> Vec<u8>
+    {
+        serde_json::json!({ "type": kind, "challenge": challenge, "origin": origin }).to_string()
+                                                                                      .into_bytes()
+    }
+
+    fn cose_key(&self) -> Vec<u8>
+    {
+        let point = self.key.verifying_key().to_encoded_point(false);
+        let key = Value::Map(vec![(Value::from(1), Value::from(2)),
+                                  (Value::from(3), Value::from(-7)),
+                                  (Value::from(-1), Value::from(1)),
+                                  (Value::from(-2), Value::Bytes(point.x().unwrap().to_vec())),
+                                  (Value::from(-3), Value::Bytes(point.y().unwrap().to_vec())),]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&key, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn authenticator_data(&self, rp_id: &str, flags: u8, attested: bool) -> Vec<u8>
+    {
+        let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&self.counter.to_be_bytes());
+        if attested
+        {
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            data.extend_from_slice(&self.credential_id);
+            data.extend_from_slice(&self.cose_key());
+        }
+        data
+    }
+
+    fn register(&self, rp_id: &str, origin: &str, challenge: &str) -> RegistrationResponse
+    {
+        let attestation = Value::Map(vec![(Value::from("fmt"), Value::from("none")),
+                                          (Value::from("attStmt"), Value::Map(Vec::new())),
+                                          (Value::from("authData"),
+                                           Value::Bytes(self.authenticator_data(rp_id, 0x45, true))),]);
+        let mut attestation_object = Vec::new();
+        ciborium::into_writer(&attestation, &mut attestation_object).unwrap();
+
+        RegistrationResponse {
+            id:       BASE64URL_NOPAD.encode(&self.credential_id),
+            name:     Some("soft key".to_string()),
+            response: AttestationResponse {
+                client_data_json:   BASE64URL_NOPAD.encode(&Self::client_data("webauthn.create", challenge, origin)),
+                attestation_object: BASE64URL_NOPAD.encode(&attestation_object),
+            },
+        }
+    }
+
+    fn assert(&mut self, rp_id: &str, origin: &str, challenge: &str) -> AuthenticationResponse
+    {
+        self.counter += 1;
+        let client_data = Self::client_data("webauthn.get", challenge, origin);
+        let authenticator_data = self.authenticator_data(rp_id, 0x05, false);
+
+        let mut message = authenticator_data.clone();
+        message.extend_from_slice(&Sha256::digest(&client_data));
+        let signature: Signature = self.key.sign(&message);
+
+        AuthenticationResponse {
+            id:       BASE64URL_NOPAD.encode(&self.credential_id),
+            response: AssertionResponse {
+                client_data_json:   BASE64URL_NOPAD.encode(&client_data),
+                authenticator_data: BASE64URL_NOPAD.encode(&authenticator_data),
+                signature:          BASE64URL_NOPAD.encode(signature.to_der().as_bytes()),
+                user_handle:        None,
+            },
+        }
+    }
+
+    fn assert_signature_for(&self, response: &AuthenticationResponse) -> String
+    {
+        let mut message = BASE64URL_NOPAD.decode(response.response.authenticator_data.as_bytes()).unwrap();
+        let client_data = BASE64URL_NOPAD.decode(response.response.client_data_json.as_bytes()).unwrap();
+        message.extend_from_slice(&Sha256::digest(&client_data));
+        let signature: Signature = self.key.sign(&message);
+        BASE64URL_NOPAD.encode(signature.to_der().as_bytes())
+    }
+}
+
+fn relying_party() -> RelyingParty
+{
+    RelyingParty { id:     "localhost".to_string(),
+                   name:   "user".to_string(),
+                   origin: "http://localhost:8080".to_string(), }
+}
+
+#[test]
+fn registers_and_authenticates_with_software_authenticator()
+{
+    let rp = relying_party();
+    let mut authenticator = SoftAuthenticator::new();
+
+    let registration = authenticator.register(&rp.id, &rp.origin, "register-challenge");
+    let mut credential = verify_registration(&rp, "register-challenge", &registration).unwrap();
+    assert_eq!(credential.credential_id, BASE64URL_NOPAD.encode(&authenticator.credential_id));
+    assert_eq!(credential.name, "soft key");
+
+    for round in 1..=3
+    {
+        let challenge = format!("login-challenge-{}", round);
+        let assertion = authenticator.assert(&rp.id, &rp.origin, &challenge);
+        credential.sign_count = verify_authentication(&rp, &challenge, &credential, &assertion).unwrap();
+        assert_eq!(credential.sign_count, round);
+    }
+}
+
+#[test]
+fn rejects_registration_from_other_origin_or_challenge()
+{
+    let rp = relying_party();
+    let authenticator = SoftAuthenticator::new();
+
+    let registration = authenticator.register(&rp.id, "https://evil.example", "challenge");
+    assert!(verify_registration(&rp, "challenge", &registration).is_err());
+
+    let registration = authenticator.register(&rp.id, &rp.origin, "challenge");
+    assert!(verify_registration(&rp, "another-challenge", &registration).is_err());
+
+    let registration = authenticator.register("evil.example", &rp.origin, "challenge");
+    assert!(verify_registration(&rp, "challenge", &registration).is_err());
+}
+
+#[test]
+fn rejects_replayed_counter_and_tampered_signature()
+{
+    let rp = relying_party();
+    let mut authenticator = SoftAuthenticator::new();
+    let registration = authenticator.register(&rp.id, &rp.origin, "challenge");
+    let mut credential = verify_registration(&rp, "challenge", &registration).unwrap();
+
+    let assertion = authenticator.assert(&rp.id, &rp.origin, "first");
+    credential.sign_count = verify_authentication(&rp, "first", &credential, &assertion).unwrap();
+
+    // Same counter again looks like a cloned authenticator.
+    authenticator.counter -= 1;
+    let replayed = authenticator.assert(&rp.id, &rp.origin, "second");
+    assert!(verify_authentication(&rp, "second", &credential, &replayed).is_err());
+
+    let mut tampered = authenticator.assert(&rp.id, &rp.origin, "third");
+    tampered.response.client_data_json = BASE64URL_NOPAD.encode(
+        serde_json::json!({ "type": "webauthn.get", "challenge": "third", "origin": rp.origin, "extra": 1 })
+            .to_string()
+            .as_bytes(),
+    );
+    assert!(verify_authentication(&rp, "third", &credential, &tampered).is_err());
+
+    let other = SoftAuthenticator::new();
+    let mut foreign = authenticator.assert(&rp.id, &rp.origin, "fourth");
+    foreign.response.signature = other.assert_signature_for(&foreign);
+    assert!(verify_authentication(&rp, "fourth", &credential, &foreign).is_err());
+}
+
+#[actix_web::test]
+#[ignore = "needs the MongoDB server at MONGO_URI"]
+async fn passkey_ceremonies_store_and_consume_their_state()
+{
+    let context = common::test_context().await;
+    let passkeys = PasskeyOps::new(&context.auth_repo, &context);
+    let rp = RelyingParty::from_env();
+    let auth = context.auth_repo.create(common::new_auth("passkey.owner", "correct horse")).await.unwrap();
+    let proof = AccountProof { username: auth.username.clone(), password: "correct horse".to_string(), code: None };
+    let mut authenticator = SoftAuthenticator::new();
+
+    let options = passkeys.start_registration(proof.clone(), AuditOrigin::default()).await.unwrap();
+    let registration = authenticator.register(&rp.id, &rp.origin, &options.challenge);
+    passkeys.finish_registration(registration.clone()).await.unwrap();
+    // Each challenge is answered once.
+    assert!(matches!(passkeys.finish_registration(registration).await, Err(AuthError::ChallengeNotFound)));
+
+    let options = passkeys.start_registration(proof, AuditOrigin::default()).await.unwrap();
+    assert_eq!(options.exclude_credentials.len(), 1);
+    let again = authenticator.register(&rp.id, &rp.origin, &options.challenge);
+    assert!(matches!(passkeys.finish_registration(again).await, Err(AuthError::PasskeyAlreadyRegistered)));
+
+    // Known and unknown accounts get the same shape of options.
+    let known = passkeys.start_authentication(Some(auth.username.clone())).await.unwrap();
+    let unknown = passkeys.start_authentication(Some("nobody".to_string())).await.unwrap();
+    assert!(known.allow_credentials.is_empty());
+    assert!(unknown.allow_credentials.is_empty());
+
+    let assertion = authenticator.assert(&rp.id, &rp.origin, &known.challenge);
+    let token = passkeys.finish_authentication(assertion.clone(), AuditOrigin::default()).await.unwrap();
+    let claims = TokenOps::new(&context).verify(&token.token).await.unwrap().unwrap();
+    assert_eq!(claims.sub, auth.user_id.value().to_hex());
+    let stored = context.auth_repo.fetch_by_user_id(auth.user_id.clone()).await.unwrap();
+    assert_eq!(stored.passkeys[0].sign_count, 1);
+    assert!(stored.passkeys[0].last_used_at.is_some());
+    assert!(matches!(passkeys.finish_authentication(assertion, AuditOrigin::default()).await,
+                     Err(AuthError::ChallengeNotFound)));
+
+    // A counter that didn't move past the stored one is refused.
+    authenticator.counter -= 1;
+    let stale = authenticator.assert(&rp.id, &rp.origin, &unknown.challenge);
+    assert!(matches!(passkeys.finish_authentication(stale, AuditOrigin::default()).await,
+                     Err(AuthError::PasskeyVerification(_))));
+
+    common::drop_database(&context).await;
+}